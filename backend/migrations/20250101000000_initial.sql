@@ -0,0 +1,39 @@
+CREATE TABLE users (
+    username TEXT PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    role TEXT NOT NULL
+);
+
+CREATE TABLE unconfirmed_users (
+    confirmation_token TEXT PRIMARY KEY,
+    host TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    confirmed BIGINT NOT NULL DEFAULT 0,
+    username TEXT NOT NULL,
+    email TEXT NOT NULL,
+    password TEXT NOT NULL,
+    role TEXT NOT NULL
+);
+
+CREATE INDEX unconfirmed_users_created_at ON unconfirmed_users (created_at);
+
+CREATE TABLE posts (
+    slug TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    summary TEXT,
+    author TEXT NOT NULL,
+    published_at BIGINT NOT NULL,
+    is_public BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX posts_published_at ON posts (published_at);
+
+CREATE TABLE temp_files (
+    path TEXT PRIMARY KEY,
+    filename TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX temp_files_created_at ON temp_files (created_at);
@@ -0,0 +1,11 @@
+CREATE TABLE post_revisions (
+    slug TEXT NOT NULL,
+    number BIGINT NOT NULL,
+    author TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    summary TEXT,
+    is_public BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (slug, number)
+);
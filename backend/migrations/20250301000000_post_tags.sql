@@ -0,0 +1,3 @@
+-- Tags are kept as `,tag-a,tag-b,` so a single LIKE finds a tag on both
+-- SQLite and PostgreSQL. Normalized tags never contain commas or wildcards.
+ALTER TABLE posts ADD COLUMN tags TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,14 @@
+CREATE TABLE series (
+    slug TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE series_posts (
+    series_slug TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    post_slug TEXT NOT NULL,
+    PRIMARY KEY (series_slug, position)
+);
+
+CREATE INDEX series_posts_post_slug ON series_posts (post_slug);
@@ -0,0 +1,13 @@
+CREATE TABLE comments (
+    id TEXT PRIMARY KEY,
+    post_slug TEXT NOT NULL,
+    parent_id TEXT,
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    edited_at BIGINT,
+    status TEXT NOT NULL
+);
+
+CREATE INDEX comments_post_slug ON comments (post_slug, created_at);
+CREATE INDEX comments_status ON comments (status, created_at);
@@ -0,0 +1,6 @@
+CREATE TABLE slug_history (
+    old_slug TEXT PRIMARY KEY,
+    slug TEXT NOT NULL
+);
+
+CREATE INDEX slug_history_slug ON slug_history (slug);
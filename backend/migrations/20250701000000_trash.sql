@@ -0,0 +1,14 @@
+CREATE TABLE trashed_posts (
+    slug TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    summary TEXT,
+    author TEXT NOT NULL,
+    published_at BIGINT NOT NULL,
+    is_public BIGINT NOT NULL DEFAULT 0,
+    tags TEXT NOT NULL DEFAULT '',
+    deleted_at BIGINT NOT NULL,
+    deleted_by TEXT NOT NULL
+);
+
+CREATE INDEX trashed_posts_deleted_at ON trashed_posts (deleted_at);
@@ -0,0 +1,3 @@
+-- Filled in by `SqlDBHandler::new` for the posts saved before this migration
+ALTER TABLE posts ADD COLUMN preview TEXT;
+ALTER TABLE trashed_posts ADD COLUMN preview TEXT;
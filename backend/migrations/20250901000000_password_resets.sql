@@ -0,0 +1,10 @@
+-- Sessions started before this time are logged out
+ALTER TABLE users ADD COLUMN password_changed_at BIGINT;
+
+CREATE TABLE password_resets (
+    token TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX password_resets_created_at ON password_resets (created_at);
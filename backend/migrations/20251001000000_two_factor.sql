@@ -0,0 +1,13 @@
+CREATE TABLE two_factor (
+    username TEXT PRIMARY KEY,
+    secret TEXT NOT NULL,
+    enabled BIGINT NOT NULL,
+    last_used_step BIGINT
+);
+
+-- Hashes of the recovery codes that weren't used yet
+CREATE TABLE two_factor_recovery_codes (
+    username TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    PRIMARY KEY (username, code_hash)
+);
@@ -0,0 +1,17 @@
+CREATE TABLE api_tokens (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT,
+    last_used_at BIGINT
+);
+
+CREATE INDEX api_tokens_username ON api_tokens (username);
+
+CREATE TABLE api_token_scopes (
+    token_id TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    PRIMARY KEY (token_id, scope)
+);
@@ -0,0 +1,3 @@
+-- Left NULL on the revisions saved before, which don't know them
+ALTER TABLE post_revisions ADD COLUMN published_at BIGINT;
+ALTER TABLE post_revisions ADD COLUMN tags TEXT;
@@ -0,0 +1,3 @@
+-- Only the hash of reset tokens is stored now, the links sent before stop working
+DELETE FROM password_resets;
+ALTER TABLE password_resets RENAME COLUMN token TO token_hash;
@@ -0,0 +1,7 @@
+-- A single row, created here so that saving only ever updates it
+CREATE TABLE settings (
+    id BIGINT PRIMARY KEY,
+    require_two_factor BIGINT NOT NULL
+);
+
+INSERT INTO settings (id, require_two_factor) VALUES (1, 0);
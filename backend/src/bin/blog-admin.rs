@@ -0,0 +1,544 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use backend::{
+    database::{mongo::MongoDBHandler, sql::SqlDBHandler, DBHandler, DbError},
+    models::{PageCursor, PostsQueryParams, User},
+    utils::{
+        export_site, import_site, save_revision, Highlighter, SiteImport, StaticExport,
+        MAX_PER_PAGE,
+    },
+    Config,
+};
+use bcrypt::{hash, DEFAULT_COST};
+use common::{utils::*, Capability, ImportOutcome, Post, Role, SiteSettings};
+use dotenv::dotenv;
+
+/// Posts read from the database at a time while listing them.
+const LIST_BATCH_SIZE: u64 = 100;
+
+const USAGE: &str = "usage: blog-admin <command>
+
+commands:
+    create-admin <username> <email>      create the first admin, reading the password from stdin
+    users list                           list confirmed users with their roles
+    users pending                        list users that haven't confirmed their email yet
+    users confirm <username>             confirm a pending user
+    users set-role <username> <role>     change the role of a user to Admin, Editor or Reader
+    users reset-password <username>      set a new password, read from stdin
+    users reset-two-factor <username>    remove two-factor authentication, for a lost device
+    users revoke-api-tokens <username>   revoke every API token of a user
+    posts list                           list every post, drafts included
+    posts publish <slug>                 make a post public
+    posts unpublish <slug>               turn a post back into a draft
+    posts transfer <slug> <username>     make another Admin or Editor the author of a post
+    settings show                        show the site settings
+    settings require-two-factor <on|off> make two-factor authentication mandatory for Admins
+                                         and Editors, or optional again
+    import-site <site-dir> --author <username> [--overwrite]
+                                         import the posts of a Hugo, Jekyll or Zola site
+    export-site <out-dir> [--per-page <n>]
+                                         render the public blog to static HTML files";
+
+fn usage() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, USAGE)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn db_error(err: DbError) -> io::Error {
+    io::Error::other(err)
+}
+
+/// The settings of the server a command needs, without requiring the
+/// variables of the others, like the SMTP and Redis ones.
+fn config(required: &[&str]) -> io::Result<Config> {
+    Config::with_required(required).map_err(invalid)
+}
+
+#[actix_web::main]
+async fn main() {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    if let Err(err) = connect_and_run().await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn connect_and_run() -> io::Result<()> {
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| {
+        invalid(String::from(
+            "Environment variable `DATABASE_URL` is required",
+        ))
+    })?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match database_url.split(':').next() {
+        Some("memory") => Err(invalid(String::from(
+            "the in-memory database is gone once the command exits",
+        ))),
+        Some("sqlite" | "postgres" | "postgresql") => {
+            let db_handler = SqlDBHandler::new(&database_url)
+                .await
+                .expect("Error creating database handler");
+
+            run(&db_handler, &args).await
+        }
+        _ => {
+            let db_handler = MongoDBHandler::new(&database_url, "rust_blog")
+                .await
+                .expect("Error creating database handler");
+
+            run(&db_handler, &args).await
+        }
+    }
+}
+
+async fn run(db_handler: &impl DBHandler, args: &[String]) -> io::Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["create-admin", username, email] => create_admin(db_handler, username, email).await,
+        ["users", "list"] => list_users(db_handler).await,
+        ["users", "pending"] => list_pending_users(db_handler).await,
+        ["users", "confirm", username] => confirm_user(db_handler, username).await,
+        ["users", "set-role", username, role] => set_role(db_handler, username, role).await,
+        ["users", "reset-password", username] => reset_password(db_handler, username).await,
+        ["users", "reset-two-factor", username] => reset_two_factor(db_handler, username).await,
+        ["users", "revoke-api-tokens", username] => revoke_api_tokens(db_handler, username).await,
+        ["posts", "list"] => list_posts(db_handler).await,
+        ["posts", "publish", slug] => set_public(db_handler, slug, true).await,
+        ["posts", "unpublish", slug] => set_public(db_handler, slug, false).await,
+        ["posts", "transfer", slug, username] => transfer_post(db_handler, slug, username).await,
+        ["settings", "show"] => show_settings(db_handler).await,
+        ["settings", "require-two-factor", value] => require_two_factor(db_handler, value).await,
+        ["import-site", rest @ ..] => import_site_command(db_handler, rest).await,
+        ["export-site", rest @ ..] => export_site_command(db_handler, rest).await,
+        _ => Err(usage()),
+    }
+}
+
+/// Reads a password from stdin, prompting for it when stdin is a terminal,
+/// and hashes it.
+fn read_password() -> io::Result<String> {
+    if io::stdin().is_terminal() {
+        eprint!("Password: ");
+        io::stderr().flush()?;
+    }
+
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if !is_valid_password(password) {
+        return Err(invalid(String::from("invalid password")));
+    }
+
+    hash(password, DEFAULT_COST).map_err(io::Error::other)
+}
+
+async fn create_admin(db_handler: &impl DBHandler, username: &str, email: &str) -> io::Result<()> {
+    let users = db_handler.get_users().await.map_err(db_error)?;
+    if users.iter().any(|user| user.role == Role::Admin) {
+        return Err(invalid(String::from(
+            "there already is an admin, use `users set-role` instead",
+        )));
+    }
+
+    let email = normalize_email(email);
+    if !is_valid_username(username) {
+        return Err(invalid(String::from("invalid username")));
+    }
+    if !is_valid_email(&email) {
+        return Err(invalid(String::from("invalid email")));
+    }
+    if users.iter().any(|user| user.username == username) {
+        return Err(invalid(format!("username {} is taken", username)));
+    }
+    if users.iter().any(|user| user.email == email) {
+        return Err(invalid(format!("email {} is taken", email)));
+    }
+
+    let password = read_password()?;
+    db_handler
+        .insert_user(&User {
+            id: None,
+            username: username.to_string(),
+            email,
+            password,
+            role: Role::Admin,
+            password_changed_at: None,
+            token_scopes: None,
+        })
+        .await
+        .map_err(db_error)?;
+    println!("created admin {}", username);
+
+    Ok(())
+}
+
+async fn list_users(db_handler: &impl DBHandler) -> io::Result<()> {
+    for user in db_handler.get_users().await.map_err(db_error)? {
+        println!("{:<10} {:<24} {}", user.role, user.username, user.email);
+    }
+
+    Ok(())
+}
+
+async fn list_pending_users(db_handler: &impl DBHandler) -> io::Result<()> {
+    for user in db_handler.get_unconfirmed_users().await.map_err(db_error)? {
+        println!(
+            "{}  {:<24} {}",
+            user.created_at.format("%Y-%m-%d %H:%M"),
+            user.username,
+            user.email
+        );
+    }
+
+    Ok(())
+}
+
+async fn confirm_user(db_handler: &impl DBHandler, username: &str) -> io::Result<()> {
+    let pending = db_handler
+        .find_unconfirmed_user(username)
+        .await
+        .map_err(db_error)?
+        .filter(|user| !user.confirmed)
+        .ok_or_else(|| invalid(format!("no pending user {}", username)))?;
+
+    match db_handler
+        .confirm_user(&pending.confirmation_token)
+        .await
+        .map_err(db_error)?
+    {
+        Some(user) => {
+            db_handler
+                .insert_user(&user.into())
+                .await
+                .map_err(db_error)?;
+            println!("confirmed {}", username);
+            Ok(())
+        }
+        None => Err(invalid(format!("no pending user {}", username))),
+    }
+}
+
+async fn set_role(db_handler: &impl DBHandler, username: &str, role: &str) -> io::Result<()> {
+    let role: Role = role.parse().map_err(invalid)?;
+
+    match db_handler
+        .update_user_role(username, role)
+        .await
+        .map_err(db_error)?
+    {
+        0 => Err(invalid(format!("no user {}", username))),
+        _ => {
+            println!("{} is now {}", username, role);
+            Ok(())
+        }
+    }
+}
+
+async fn reset_password(db_handler: &impl DBHandler, username: &str) -> io::Result<()> {
+    if db_handler
+        .find_user(username)
+        .await
+        .map_err(db_error)?
+        .is_none()
+    {
+        return Err(invalid(format!("no user {}", username)));
+    }
+
+    let password = read_password()?;
+    db_handler
+        .update_user_password(username, &password)
+        .await
+        .map_err(db_error)?;
+    println!("password of {} changed", username);
+
+    Ok(())
+}
+
+async fn reset_two_factor(db_handler: &impl DBHandler, username: &str) -> io::Result<()> {
+    match db_handler
+        .delete_two_factor(username)
+        .await
+        .map_err(db_error)?
+    {
+        0 => Err(invalid(format!(
+            "{} doesn't use two-factor authentication",
+            username
+        ))),
+        _ => {
+            println!("two-factor authentication of {} removed", username);
+            Ok(())
+        }
+    }
+}
+
+async fn revoke_api_tokens(db_handler: &impl DBHandler, username: &str) -> io::Result<()> {
+    let tokens = db_handler
+        .get_api_tokens(username)
+        .await
+        .map_err(db_error)?;
+    if tokens.is_empty() {
+        return Err(invalid(format!("{} has no API tokens", username)));
+    }
+
+    for token in &tokens {
+        db_handler
+            .delete_api_token(username, &token.id)
+            .await
+            .map_err(db_error)?;
+    }
+    println!("revoked {} API tokens of {}", tokens.len(), username);
+    Ok(())
+}
+
+async fn list_posts(db_handler: &impl DBHandler) -> io::Result<()> {
+    let query = PostsQueryParams {
+        per_page: Some(LIST_BATCH_SIZE),
+        ..Default::default()
+    };
+    let mut cursor = PageCursor::First;
+    loop {
+        let page = db_handler
+            .get_posts_page(&query, &cursor, LIST_BATCH_SIZE, true)
+            .await
+            .map_err(db_error)?;
+
+        for (post, _) in page.iter() {
+            let status = match post.public {
+                true if post.is_scheduled() => "scheduled",
+                true => "public",
+                false => "draft",
+            };
+            println!(
+                "{:<10} {}  {:<32} {}",
+                status,
+                post.published_at.format("%Y-%m-%d %H:%M"),
+                post.slug,
+                post.title
+            );
+        }
+
+        match page.last() {
+            Some((_, last)) if page.len() as u64 == LIST_BATCH_SIZE => {
+                cursor = PageCursor::After(last.clone());
+            }
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
+
+async fn show_settings(db_handler: &impl DBHandler) -> io::Result<()> {
+    let settings = db_handler.get_settings().await.map_err(db_error)?;
+    println!(
+        "require-two-factor {}",
+        if settings.require_two_factor {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    Ok(())
+}
+
+async fn require_two_factor(db_handler: &impl DBHandler, value: &str) -> io::Result<()> {
+    let required = match value {
+        "on" => true,
+        "off" => false,
+        _ => return Err(invalid(format!("expected on or off, got {}", value))),
+    };
+
+    db_handler
+        .save_settings(&SiteSettings {
+            require_two_factor: required,
+        })
+        .await
+        .map_err(db_error)?;
+    if required {
+        println!("two-factor authentication is now required for Admins and Editors");
+    } else {
+        println!("two-factor authentication is now optional");
+    }
+    Ok(())
+}
+
+async fn set_public(db_handler: &impl DBHandler, slug: &str, public: bool) -> io::Result<()> {
+    let current = db_handler
+        .get_post(slug, true)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| invalid(format!("no post {}", slug)))?;
+
+    let state = if public { "public" } else { "a draft" };
+    if current.public == public {
+        println!("{} already is {}", slug, state);
+        return Ok(());
+    }
+
+    // Same as editing the post, the change shows up in its history. The CLI
+    // has no user of its own, so the post's author signs the revision.
+    if db_handler
+        .get_revisions(slug)
+        .await
+        .map_err(db_error)?
+        .is_empty()
+    {
+        save_revision(db_handler, &current, &current.author)
+            .await
+            .map_err(db_error)?;
+    }
+    let updated = Post { public, ..current };
+    db_handler.update_post(&updated).await.map_err(db_error)?;
+    save_revision(db_handler, &updated, &updated.author)
+        .await
+        .map_err(db_error)?;
+    println!("{} is now {}", slug, state);
+
+    Ok(())
+}
+
+async fn transfer_post(db_handler: &impl DBHandler, slug: &str, username: &str) -> io::Result<()> {
+    let user = db_handler
+        .find_user(username)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| invalid(format!("no user {}", username)))?;
+    if !user.role.can(Capability::EditOwnPost) {
+        return Err(invalid(format!("{} can't write posts", username)));
+    }
+
+    match db_handler
+        .set_post_author(slug, username)
+        .await
+        .map_err(db_error)?
+    {
+        0 => Err(invalid(format!("no post {}", slug))),
+        _ => {
+            println!("{} now belongs to {}", slug, username);
+            Ok(())
+        }
+    }
+}
+
+/// `import-site <site-dir> --author <username> [--overwrite]`, reading the
+/// upload settings from the same environment variables as the server.
+async fn import_site_command(db_handler: &impl DBHandler, args: &[&str]) -> io::Result<()> {
+    let mut root = None;
+    let mut author = None;
+    let mut overwrite = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--author" => author = Some(*args.next().ok_or_else(usage)?),
+            "--overwrite" => overwrite = true,
+            _ if root.is_none() => root = Some(*arg),
+            _ => return Err(usage()),
+        }
+    }
+    let (Some(root), Some(author)) = (root, author) else {
+        return Err(usage());
+    };
+
+    let user = db_handler
+        .find_user(author)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| invalid(format!("no user {}", author)))?;
+    if !user.role.can(Capability::CreatePost) {
+        return Err(invalid(format!("{} can't write posts", author)));
+    }
+
+    let config = config(&["FILE_UPLOAD_PATH", "FILE_UPLOAD_URL"])?;
+    let report = import_site(
+        db_handler,
+        &SiteImport {
+            root: std::path::Path::new(root),
+            upload_path: &config.FILE_UPLOAD_PATH,
+            upload_url: &config.FILE_UPLOAD_URL,
+            author,
+            overwrite,
+        },
+    )
+    .await
+    .map_err(io::Error::other)?;
+
+    for file in report.files.iter() {
+        match file.outcome {
+            ImportOutcome::Created => println!("created      {}", file.path),
+            ImportOutcome::Overwritten => println!("overwritten  {}", file.path),
+            ImportOutcome::Skipped(ref reason) => {
+                println!("skipped      {}: {}", file.path, reason)
+            }
+            ImportOutcome::Failed(ref reason) => println!("failed       {}: {}", file.path, reason),
+        }
+        for warning in file.warnings.iter() {
+            println!("    warning: {}", warning);
+        }
+    }
+
+    let imported = report
+        .files
+        .iter()
+        .filter(|file| {
+            matches!(
+                file.outcome,
+                ImportOutcome::Created | ImportOutcome::Overwritten
+            )
+        })
+        .count();
+    println!("{} of {} files imported", imported, report.files.len());
+
+    Ok(())
+}
+
+/// `export-site <out-dir> [--per-page <n>]`, with the site settings of the server.
+async fn export_site_command(db_handler: &impl DBHandler, args: &[&str]) -> io::Result<()> {
+    let mut out_dir = None;
+    let mut per_page = 10;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--per-page" => {
+                per_page = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=MAX_PER_PAGE).contains(n))
+                    .ok_or_else(usage)?
+            }
+            _ if out_dir.is_none() => out_dir = Some(*arg),
+            _ => return Err(usage()),
+        }
+    }
+    let Some(out_dir) = out_dir else {
+        return Err(usage());
+    };
+
+    let config = config(&[
+        "WEBSITE_URL",
+        "RSS_TITLE",
+        "RSS_DESCRIPTION",
+        "FILE_UPLOAD_PATH",
+        "FILE_UPLOAD_URL",
+    ])?;
+    let written = export_site(
+        db_handler,
+        &config,
+        &Highlighter::new(),
+        &StaticExport {
+            out_dir: std::path::Path::new(out_dir),
+            per_page,
+        },
+    )
+    .await
+    .map_err(io::Error::other)?;
+    println!("{} files written to {}", written, out_dir);
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+
+use super::DbError;
+use common::{Comment, CommentStatus};
+
+pub trait CommentDb {
+    async fn create_comment(&self, comment: &Comment) -> Result<(), DbError>;
+    async fn get_comment(&self, id: &str) -> Result<Option<Comment>, DbError>;
+    /// Every comment of a post regardless of its status, oldest first.
+    async fn get_comments(&self, post_slug: &str) -> Result<Vec<Comment>, DbError>;
+    /// Comments of all posts with the given status, oldest first.
+    async fn get_comments_by_status(&self, status: CommentStatus) -> Result<Vec<Comment>, DbError>;
+    async fn update_comment(
+        &self,
+        id: &str,
+        content: &str,
+        edited_at: DateTime<Utc>,
+        status: CommentStatus,
+    ) -> Result<u64, DbError>;
+    async fn set_comment_status(&self, id: &str, status: CommentStatus) -> Result<u64, DbError>;
+    async fn delete_comment(&self, id: &str) -> Result<u64, DbError>;
+    async fn delete_post_comments(&self, post_slug: &str) -> Result<u64, DbError>;
+}
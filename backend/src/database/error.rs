@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt;
+
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use mongodb::error::{ErrorKind, WriteFailure};
+
+use common::ErrorResponse;
+
+type Source = Box<dyn Error + Send + Sync>;
+
+/// MongoDB code for a duplicate key write.
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+#[derive(Debug)]
+pub enum DbError {
+    /// The document the operation depends on doesn't exist.
+    NotFound,
+    /// A page cursor that doesn't hold a position this database can look up.
+    InvalidCursor,
+    /// The write would break a uniqueness constraint.
+    Conflict(Source),
+    /// The database can't be reached.
+    Unavailable(Source),
+    /// A stored document doesn't match the model, or the model can't be stored.
+    Serialization(Source),
+    /// Anything else.
+    Other(Source),
+}
+
+impl DbError {
+    pub fn other(message: &str) -> Self {
+        Self::Other(message.into())
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::NotFound => write!(f, "not found"),
+            DbError::InvalidCursor => write!(f, "invalid cursor"),
+            DbError::Conflict(source) => write!(f, "conflict: {}", source),
+            DbError::Unavailable(source) => write!(f, "database unavailable: {}", source),
+            DbError::Serialization(source) => write!(f, "serialization error: {}", source),
+            DbError::Other(source) => write!(f, "database error: {}", source),
+        }
+    }
+}
+
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::NotFound | DbError::InvalidCursor => None,
+            DbError::Conflict(source)
+            | DbError::Unavailable(source)
+            | DbError::Serialization(source)
+            | DbError::Other(source) => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for DbError {
+    fn from(value: mongodb::error::Error) -> Self {
+        match *value.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY_CODE =>
+            {
+                DbError::Conflict(value.into())
+            }
+            ErrorKind::Io(_)
+            | ErrorKind::ServerSelection { .. }
+            | ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::DnsResolve { .. }
+            | ErrorKind::Authentication { .. } => DbError::Unavailable(value.into()),
+            ErrorKind::BsonDeserialization(_) | ErrorKind::BsonSerialization(_) => {
+                DbError::Serialization(value.into())
+            }
+            _ => DbError::Other(value.into()),
+        }
+    }
+}
+
+impl From<bson::ser::Error> for DbError {
+    fn from(value: bson::ser::Error) -> Self {
+        DbError::Serialization(value.into())
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(value: sqlx::Error) -> Self {
+        match value {
+            sqlx::Error::RowNotFound => DbError::NotFound,
+            sqlx::Error::Database(ref db_error) if db_error.is_unique_violation() => {
+                DbError::Conflict(value.into())
+            }
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => DbError::Unavailable(value.into()),
+            sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::Decode(_)
+            | sqlx::Error::Encode(_)
+            | sqlx::Error::TypeNotFound { .. } => DbError::Serialization(value.into()),
+            _ => DbError::Other(value.into()),
+        }
+    }
+}
+
+impl ResponseError for DbError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DbError::NotFound => StatusCode::NOT_FOUND,
+            DbError::InvalidCursor => StatusCode::BAD_REQUEST,
+            DbError::Conflict(_) => StatusCode::CONFLICT,
+            DbError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            DbError::Serialization(_) | DbError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            log::error!("{}", self);
+        }
+
+        // Internal details stay in the logs
+        let error = match self {
+            DbError::NotFound => "not found",
+            DbError::InvalidCursor => "cursor",
+            DbError::Conflict(_) => "conflict",
+            DbError::Unavailable(_) => "database unavailable",
+            DbError::Serialization(_) | DbError::Other(_) => "internal error",
+        };
+
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            error: error.to_string(),
+        })
+    }
+}
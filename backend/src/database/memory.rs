@@ -0,0 +1,1068 @@
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{
+    comment::CommentDb,
+    post::PostDb,
+    revision::RevisionDb,
+    series::SeriesDb,
+    settings::SettingsDb,
+    slug::SlugDb,
+    trash::TrashDb,
+    user::{ApiTokenDb, PasswordResetDb, TwoFactorDb, UnconfirmedUserDb, UserDb},
+    DBHandler, DbError, EXPIRE_AFTER_SECS, MAX_SEARCH_RESULTS, PASSWORD_RESET_EXPIRE_AFTER_SECS,
+    UNCONFIRMED_USER_RETENTION_SECS,
+};
+use crate::{
+    models::{
+        ApiTokenModel, CommentModel, PageCursor, PasswordReset, PostCursor, PostModel,
+        PostRevisionModel, PostsQueryParams, SlugHistoryModel, TempFileModel, TrashedPostModel,
+        TwoFactor, UnconfirmedUser, User,
+    },
+    utils::rank_posts,
+};
+use common::{
+    Comment, CommentStatus, Post, PostRevision, PostSummary, Role, Series, SiteSettings, TagCount,
+    TrashedPost,
+};
+
+#[derive(Default)]
+struct Store {
+    users: Vec<User>,
+    unconfirmed_users: Vec<UnconfirmedUser>,
+    password_resets: Vec<PasswordReset>,
+    two_factors: Vec<TwoFactor>,
+    api_tokens: Vec<ApiTokenModel>,
+    posts: Vec<PostModel>,
+    temp_files: Vec<TempFileModel>,
+    post_revisions: Vec<PostRevisionModel>,
+    series: Vec<Series>,
+    comments: Vec<CommentModel>,
+    slug_history: Vec<SlugHistoryModel>,
+    trash: Vec<TrashedPostModel>,
+    settings: SiteSettings,
+}
+
+impl Store {
+    /// Mimics the TTL indexes created by `MongoDBHandler::new`.
+    fn remove_expired(&mut self) {
+        let threshold = Utc::now() - Duration::seconds(EXPIRE_AFTER_SECS as i64);
+
+        self.temp_files.retain(|file| file.created_at > threshold);
+
+        let threshold = Utc::now() - Duration::seconds(UNCONFIRMED_USER_RETENTION_SECS as i64);
+        self.unconfirmed_users
+            .retain(|user| user.created_at > threshold);
+
+        let threshold = Utc::now() - Duration::seconds(PASSWORD_RESET_EXPIRE_AFTER_SECS as i64);
+        self.password_resets
+            .retain(|reset| reset.created_at > threshold);
+    }
+}
+
+/// Database handler that keeps everything in process memory, meant for tests
+/// and local development. Nothing survives a restart.
+#[derive(Clone, Default)]
+pub struct InMemoryDBHandler {
+    store: Arc<RwLock<Store>>,
+}
+
+impl InMemoryDBHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read<R>(&self, f: impl FnOnce(&Store) -> R) -> Result<R, DbError> {
+        self.store
+            .read()
+            .map(|store| f(&store))
+            .or(Err(DbError::other("in-memory store lock is poisoned")))
+    }
+
+    fn write<R>(&self, f: impl FnOnce(&mut Store) -> R) -> Result<R, DbError> {
+        self.store
+            .write()
+            .map(|mut store| {
+                store.remove_expired();
+                f(&mut store)
+            })
+            .or(Err(DbError::other("in-memory store lock is poisoned")))
+    }
+}
+
+fn matches_tag(post: &PostModel, query: &PostsQueryParams) -> bool {
+    match query.tag {
+        Some(ref tag) => post.tags.contains(tag),
+        None => true,
+    }
+}
+
+impl DBHandler for InMemoryDBHandler {}
+
+impl UserDb for InMemoryDBHandler {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, DbError> {
+        self.read(|store| {
+            store
+                .users
+                .iter()
+                .find(|user| user.username == username)
+                .cloned()
+        })
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DbError> {
+        self.read(|store| store.users.iter().find(|user| user.email == email).cloned())
+    }
+
+    async fn insert_user(&self, user: &User) -> Result<(), DbError> {
+        self.write(|store| store.users.push(user.clone()))
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>, DbError> {
+        self.read(|store| {
+            let mut users = store.users.clone();
+            users.sort_by(|a, b| a.username.cmp(&b.username));
+            users
+        })
+    }
+
+    async fn update_user_role(&self, username: &str, role: Role) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store
+                .users
+                .iter_mut()
+                .find(|user| user.username == username)
+            {
+                Some(user) => {
+                    user.role = role;
+                    1
+                }
+                None => 0,
+            }
+        })
+    }
+
+    async fn update_user_password(&self, username: &str, password: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store
+                .users
+                .iter_mut()
+                .find(|user| user.username == username)
+            {
+                Some(user) => {
+                    user.password = password.to_string();
+                    user.password_changed_at = Some(Utc::now());
+                    1
+                }
+                None => 0,
+            }
+        })
+    }
+}
+
+impl PasswordResetDb for InMemoryDBHandler {
+    async fn insert_password_reset(&self, reset: &PasswordReset) -> Result<(), DbError> {
+        self.write(|store| store.password_resets.push(reset.clone()))
+    }
+
+    async fn take_password_reset(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PasswordReset>, DbError> {
+        self.write(|store| {
+            let reset = store
+                .password_resets
+                .iter()
+                .find(|reset| reset.token_hash == token_hash)
+                .cloned();
+            if let Some(ref reset) = reset {
+                store
+                    .password_resets
+                    .retain(|other| other.username != reset.username);
+            }
+
+            reset
+        })
+    }
+}
+
+impl TwoFactorDb for InMemoryDBHandler {
+    async fn find_two_factor(&self, username: &str) -> Result<Option<TwoFactor>, DbError> {
+        self.read(|store| {
+            store
+                .two_factors
+                .iter()
+                .find(|two_factor| two_factor.username == username)
+                .cloned()
+        })
+    }
+
+    async fn save_two_factor(&self, two_factor: &TwoFactor) -> Result<(), DbError> {
+        self.write(|store| {
+            store
+                .two_factors
+                .retain(|other| other.username != two_factor.username);
+            store.two_factors.push(two_factor.clone());
+        })
+    }
+
+    async fn delete_two_factor(&self, username: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let before = store.two_factors.len();
+            store
+                .two_factors
+                .retain(|two_factor| two_factor.username != username);
+            (before - store.two_factors.len()) as u64
+        })
+    }
+
+    async fn use_totp_step(&self, username: &str, step: i64) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store.two_factors.iter_mut().find(|two_factor| {
+                two_factor.username == username
+                    && two_factor.enabled
+                    && two_factor.last_used_step.is_none_or(|last| last < step)
+            }) {
+                Some(two_factor) => {
+                    two_factor.last_used_step = Some(step);
+                    1
+                }
+                None => 0,
+            }
+        })
+    }
+
+    async fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let Some(two_factor) = store
+                .two_factors
+                .iter_mut()
+                .find(|two_factor| two_factor.username == username && two_factor.enabled)
+            else {
+                return 0;
+            };
+            let before = two_factor.recovery_codes.len();
+            two_factor.recovery_codes.retain(|code| code != code_hash);
+            (before - two_factor.recovery_codes.len()) as u64
+        })
+    }
+}
+
+impl ApiTokenDb for InMemoryDBHandler {
+    async fn insert_api_token(&self, token: &ApiTokenModel) -> Result<(), DbError> {
+        self.write(|store| store.api_tokens.push(token.clone()))
+    }
+
+    async fn get_api_tokens(&self, username: &str) -> Result<Vec<ApiTokenModel>, DbError> {
+        self.read(|store| {
+            let mut tokens: Vec<ApiTokenModel> = store
+                .api_tokens
+                .iter()
+                .filter(|token| token.username == username)
+                .cloned()
+                .collect();
+            tokens.sort_by_key(|token| token.created_at);
+            tokens
+        })
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiTokenModel>, DbError> {
+        self.read(|store| {
+            store
+                .api_tokens
+                .iter()
+                .find(|token| token.token_hash == token_hash)
+                .cloned()
+        })
+    }
+
+    async fn update_api_token_last_used(
+        &self,
+        id: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        self.write(
+            |store| match store.api_tokens.iter_mut().find(|token| token.id == id) {
+                Some(token) => {
+                    token.last_used_at = Some(last_used_at);
+                    1
+                }
+                None => 0,
+            },
+        )
+    }
+
+    async fn delete_api_token(&self, username: &str, id: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let before = store.api_tokens.len();
+            store
+                .api_tokens
+                .retain(|token| token.username != username || token.id != id);
+            (before - store.api_tokens.len()) as u64
+        })
+    }
+}
+
+impl UnconfirmedUserDb for InMemoryDBHandler {
+    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), DbError> {
+        self.write(|store| store.unconfirmed_users.push(user.clone()))
+    }
+
+    async fn get_unconfirmed_users(&self) -> Result<Vec<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            let mut users: Vec<UnconfirmedUser> = store
+                .unconfirmed_users
+                .iter()
+                .filter(|user| !user.confirmed && !user.is_expired())
+                .cloned()
+                .collect();
+            users.sort_by_key(|user| user.created_at);
+            users
+        })
+    }
+
+    async fn confirm_user(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            match store
+                .unconfirmed_users
+                .iter_mut()
+                .find(|user| user.confirmation_token == confirmation_token)
+            {
+                Some(user) if !user.confirmed && !user.is_expired() => {
+                    user.confirmed = true;
+                    Some(user.clone())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    async fn renew_confirmation(
+        &self,
+        confirmation_token: &str,
+        new_token: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store
+                .unconfirmed_users
+                .iter_mut()
+                .find(|user| user.confirmation_token == confirmation_token && !user.confirmed)
+            {
+                Some(user) => {
+                    user.confirmation_token = new_token.to_string();
+                    user.created_at = created_at;
+                    1
+                }
+                None => 0,
+            }
+        })
+    }
+
+    async fn find_unconfirmed_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            store
+                .unconfirmed_users
+                .iter()
+                .find(|user| user.username == username && !user.is_expired())
+                .cloned()
+        })
+    }
+
+    async fn find_unconfirmed_user_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            store
+                .unconfirmed_users
+                .iter()
+                .find(|user| user.email == email && !user.is_expired())
+                .cloned()
+        })
+    }
+
+    async fn find_latest_unconfirmed_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            store
+                .unconfirmed_users
+                .iter()
+                .filter(|user| user.email == email && !user.confirmed)
+                .max_by_key(|user| user.created_at)
+                .cloned()
+        })
+    }
+
+    async fn find_confirmation(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.write(|store| {
+            store
+                .unconfirmed_users
+                .iter()
+                .find(|user| user.confirmation_token == confirmation_token)
+                .cloned()
+        })
+    }
+}
+
+impl PostDb for InMemoryDBHandler {
+    async fn create_post(&self, post: &Post) -> Result<(), DbError> {
+        self.write(|store| store.posts.push(PostModel::from(post.clone())))
+    }
+
+    async fn update_post(&self, updated_post: &Post) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store
+                .posts
+                .iter_mut()
+                .find(|post| post.slug == updated_post.slug)
+            {
+                Some(post) => {
+                    let updated = PostModel {
+                        content: updated_post.content.clone(),
+                        title: updated_post.title.clone(),
+                        summary: updated_post.summary.clone(),
+                        public: updated_post.public,
+                        published_at: updated_post.published_at,
+                        tags: updated_post.tags.clone(),
+                        preview: updated_post.preview(),
+                        ..post.clone()
+                    };
+
+                    // Like MongoDB, only count the document if something changed
+                    if *post == updated {
+                        0
+                    } else {
+                        *post = updated;
+                        1
+                    }
+                }
+                None => 0,
+            }
+        })
+    }
+
+    async fn set_post_author(&self, slug: &str, author: &str) -> Result<u64, DbError> {
+        self.write(
+            |store| match store.posts.iter_mut().find(|post| post.slug == slug) {
+                Some(post) => {
+                    post.author = author.to_string();
+                    1
+                }
+                None => 0,
+            },
+        )
+    }
+
+    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, DbError> {
+        self.read(|store| {
+            store
+                .posts
+                .iter()
+                .find(|post| post.slug == slug && (is_admin || post.is_visible()))
+                .map(|post| post.clone().into())
+        })
+    }
+
+    async fn get_posts(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<Vec<PostSummary>, DbError> {
+        let page = query.page.unwrap_or(1);
+        let per_page = query.per_page.unwrap_or(10);
+        let offset = page.saturating_sub(1).saturating_mul(per_page);
+
+        let (sort_by, descending) = match query.sort_by {
+            Some(ref sort_by) => (
+                sort_by.as_str(),
+                query.sort_order.as_deref() == Some("desc"),
+            ),
+            None => ("published_at", true),
+        };
+
+        self.read(|store| {
+            let mut posts: Vec<&PostModel> = store
+                .posts
+                .iter()
+                .filter(|post| is_admin || post.is_visible())
+                .filter(|post| matches_tag(post, query))
+                .collect();
+
+            // Unknown fields leave the natural order untouched, as MongoDB does
+            posts.sort_by(|a, b| {
+                let ordering = match sort_by {
+                    "slug" => a.slug.cmp(&b.slug),
+                    "title" => a.title.cmp(&b.title),
+                    "author" => a.author.cmp(&b.author),
+                    "published_at" => a.published_at.cmp(&b.published_at),
+                    "public" => a.public.cmp(&b.public),
+                    _ => std::cmp::Ordering::Equal,
+                };
+
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+
+            posts
+                .into_iter()
+                .skip(offset as usize)
+                .take(per_page as usize)
+                .map(PostSummary::from)
+                .collect()
+        })
+    }
+
+    async fn get_posts_page(
+        &self,
+        query: &PostsQueryParams,
+        cursor: &PageCursor,
+        limit: u64,
+        is_admin: bool,
+    ) -> Result<Vec<(PostSummary, PostCursor)>, DbError> {
+        self.read(|store| {
+            let mut posts: Vec<(&PostModel, PostCursor)> = store
+                .posts
+                .iter()
+                .filter(|post| is_admin || post.is_visible())
+                .filter(|post| matches_tag(post, query))
+                .map(|post| {
+                    let cursor = PostCursor {
+                        published_at: post.published_at,
+                        id: post.slug.clone(),
+                    };
+                    (post, cursor)
+                })
+                .collect();
+
+            // Encoded cursors only keep milliseconds, as do the other databases
+            let key =
+                |cursor: &PostCursor| (cursor.published_at.timestamp_millis(), cursor.id.clone());
+            posts.sort_by_key(|(_, post_cursor)| std::cmp::Reverse(key(post_cursor)));
+
+            let page: Vec<(&PostModel, PostCursor)> = match cursor {
+                PageCursor::First => posts.into_iter().take(limit as usize).collect(),
+                PageCursor::After(after) => posts
+                    .into_iter()
+                    .filter(|(_, post_cursor)| key(post_cursor) < key(after))
+                    .take(limit as usize)
+                    .collect(),
+                PageCursor::Before(before) => {
+                    let mut newer: Vec<(&PostModel, PostCursor)> = posts
+                        .into_iter()
+                        .rev()
+                        .filter(|(_, post_cursor)| key(post_cursor) > key(before))
+                        .take(limit as usize)
+                        .collect();
+                    newer.reverse();
+                    newer
+                }
+            };
+
+            page.into_iter()
+                .map(|(post, post_cursor)| (PostSummary::from(post), post_cursor))
+                .collect()
+        })
+    }
+
+    async fn calculate_total_pages(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<u64, DbError> {
+        let per_page = query.per_page.unwrap_or(10);
+
+        self.read(|store| {
+            let total_posts = store
+                .posts
+                .iter()
+                .filter(|post| is_admin || post.is_visible())
+                .filter(|post| matches_tag(post, query))
+                .count();
+
+            (total_posts as f64 / per_page as f64).ceil() as u64
+        })
+    }
+
+    async fn get_tags(&self, is_admin: bool) -> Result<Vec<TagCount>, DbError> {
+        self.read(|store| {
+            let mut tags: Vec<TagCount> = Vec::new();
+
+            for tag in store
+                .posts
+                .iter()
+                .filter(|post| is_admin || post.is_visible())
+                .flat_map(|post| &post.tags)
+            {
+                match tags.iter_mut().find(|count| &count.tag == tag) {
+                    Some(count) => count.count += 1,
+                    None => tags.push(TagCount {
+                        tag: tag.clone(),
+                        count: 1,
+                    }),
+                }
+            }
+
+            tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+            tags
+        })
+    }
+
+    async fn search_posts(&self, terms: &[String], is_admin: bool) -> Result<Vec<Post>, DbError> {
+        let posts = self.read(|store| {
+            store
+                .posts
+                .iter()
+                .filter(|post| is_admin || post.is_visible())
+                .map(|post| post.clone().into())
+                .collect()
+        })?;
+
+        Ok(rank_posts(posts, terms, MAX_SEARCH_RESULTS))
+    }
+
+    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), DbError> {
+        let Some(path_str) = path.to_str() else {
+            return Err(DbError::other("temporary file path is not valid UTF-8"));
+        };
+
+        self.write(|store| {
+            store.temp_files.push(TempFileModel {
+                path: String::from(path_str),
+                filename: String::from(filename),
+                created_at: Utc::now(),
+            })
+        })
+    }
+}
+
+impl RevisionDb for InMemoryDBHandler {
+    async fn create_revision(&self, revision: &PostRevision) -> Result<(), DbError> {
+        self.write(|store| {
+            if store
+                .post_revisions
+                .iter()
+                .any(|r| r.slug == revision.slug && r.number == revision.number)
+            {
+                return Err(DbError::Conflict("revision already exists".into()));
+            }
+
+            store
+                .post_revisions
+                .push(PostRevisionModel::from(revision.clone()));
+            Ok(())
+        })?
+    }
+
+    async fn get_revisions(&self, slug: &str) -> Result<Vec<PostRevision>, DbError> {
+        self.read(|store| {
+            let mut revisions: Vec<PostRevision> = store
+                .post_revisions
+                .iter()
+                .filter(|revision| revision.slug == slug)
+                .map(|revision| revision.clone().into())
+                .collect();
+
+            revisions.sort_by_key(|revision| revision.number);
+            revisions
+        })
+    }
+
+    async fn get_revision(&self, slug: &str, number: u64) -> Result<Option<PostRevision>, DbError> {
+        self.read(|store| {
+            store
+                .post_revisions
+                .iter()
+                .find(|revision| revision.slug == slug && revision.number == number)
+                .map(|revision| revision.clone().into())
+        })
+    }
+
+    async fn delete_revisions(&self, slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.post_revisions.len();
+            store
+                .post_revisions
+                .retain(|revision| revision.slug != slug);
+            (count - store.post_revisions.len()) as u64
+        })
+    }
+}
+
+impl SeriesDb for InMemoryDBHandler {
+    async fn create_series(&self, series: &Series) -> Result<(), DbError> {
+        self.write(|store| {
+            if store.series.iter().any(|s| s.slug == series.slug) {
+                return Err(DbError::Conflict("series already exists".into()));
+            }
+
+            store.series.push(series.clone());
+            Ok(())
+        })?
+    }
+
+    async fn update_series(&self, updated_series: &Series) -> Result<u64, DbError> {
+        self.write(|store| {
+            match store
+                .series
+                .iter_mut()
+                .find(|series| series.slug == updated_series.slug)
+            {
+                Some(series) if series != updated_series => {
+                    *series = updated_series.clone();
+                    1
+                }
+                _ => 0,
+            }
+        })
+    }
+
+    async fn delete_series(&self, slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.series.len();
+            store.series.retain(|series| series.slug != slug);
+            (count - store.series.len()) as u64
+        })
+    }
+
+    async fn get_series(&self, slug: &str) -> Result<Option<Series>, DbError> {
+        self.read(|store| {
+            store
+                .series
+                .iter()
+                .find(|series| series.slug == slug)
+                .cloned()
+        })
+    }
+
+    async fn get_all_series(&self) -> Result<Vec<Series>, DbError> {
+        self.read(|store| {
+            let mut series = store.series.clone();
+            series.sort_by(|a, b| a.title.cmp(&b.title));
+            series
+        })
+    }
+
+    async fn find_series_by_post(&self, post_slug: &str) -> Result<Option<Series>, DbError> {
+        self.read(|store| {
+            store
+                .series
+                .iter()
+                .find(|series| series.posts.iter().any(|slug| slug == post_slug))
+                .cloned()
+        })
+    }
+
+    async fn remove_post_from_series(&self, post_slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let mut modified = 0;
+            for series in store.series.iter_mut() {
+                let count = series.posts.len();
+                series.posts.retain(|slug| slug != post_slug);
+                if series.posts.len() != count {
+                    modified += 1;
+                }
+            }
+            modified
+        })
+    }
+}
+
+impl CommentDb for InMemoryDBHandler {
+    async fn create_comment(&self, comment: &Comment) -> Result<(), DbError> {
+        self.write(|store| {
+            if store.comments.iter().any(|c| c.id == comment.id) {
+                return Err(DbError::Conflict("comment already exists".into()));
+            }
+
+            store.comments.push(CommentModel::from(comment.clone()));
+            Ok(())
+        })?
+    }
+
+    async fn get_comment(&self, id: &str) -> Result<Option<Comment>, DbError> {
+        self.read(|store| {
+            store
+                .comments
+                .iter()
+                .find(|comment| comment.id == id)
+                .map(|comment| comment.clone().into())
+        })
+    }
+
+    async fn get_comments(&self, post_slug: &str) -> Result<Vec<Comment>, DbError> {
+        self.read(|store| {
+            let mut comments: Vec<Comment> = store
+                .comments
+                .iter()
+                .filter(|comment| comment.post_slug == post_slug)
+                .map(|comment| comment.clone().into())
+                .collect();
+
+            comments.sort_by_key(|comment| comment.created_at);
+            comments
+        })
+    }
+
+    async fn get_comments_by_status(&self, status: CommentStatus) -> Result<Vec<Comment>, DbError> {
+        self.read(|store| {
+            let mut comments: Vec<Comment> = store
+                .comments
+                .iter()
+                .filter(|comment| comment.status == status)
+                .map(|comment| comment.clone().into())
+                .collect();
+
+            comments.sort_by_key(|comment| comment.created_at);
+            comments
+        })
+    }
+
+    async fn update_comment(
+        &self,
+        id: &str,
+        content: &str,
+        edited_at: DateTime<Utc>,
+        status: CommentStatus,
+    ) -> Result<u64, DbError> {
+        self.write(
+            |store| match store.comments.iter_mut().find(|comment| comment.id == id) {
+                Some(comment) => {
+                    comment.content = content.to_string();
+                    comment.edited_at = Some(edited_at);
+                    comment.status = status;
+                    1
+                }
+                None => 0,
+            },
+        )
+    }
+
+    async fn set_comment_status(&self, id: &str, status: CommentStatus) -> Result<u64, DbError> {
+        self.write(
+            |store| match store.comments.iter_mut().find(|comment| comment.id == id) {
+                Some(comment) if comment.status != status => {
+                    comment.status = status;
+                    1
+                }
+                _ => 0,
+            },
+        )
+    }
+
+    async fn delete_comment(&self, id: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.comments.len();
+            store.comments.retain(|comment| comment.id != id);
+            (count - store.comments.len()) as u64
+        })
+    }
+
+    async fn delete_post_comments(&self, post_slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.comments.len();
+            store
+                .comments
+                .retain(|comment| comment.post_slug != post_slug);
+            (count - store.comments.len()) as u64
+        })
+    }
+}
+
+impl SlugDb for InMemoryDBHandler {
+    async fn rename_post(&self, old_slug: &str, new_slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            if store.posts.iter().any(|post| post.slug == new_slug) {
+                return Err(DbError::Conflict("post already exists".into()));
+            }
+            let Some(post) = store.posts.iter_mut().find(|post| post.slug == old_slug) else {
+                return Ok(0);
+            };
+            post.slug = new_slug.to_string();
+
+            for revision in store.post_revisions.iter_mut() {
+                if revision.slug == old_slug {
+                    revision.slug = new_slug.to_string();
+                }
+            }
+            for series in store.series.iter_mut() {
+                for slug in series.posts.iter_mut() {
+                    if slug == old_slug {
+                        *slug = new_slug.to_string();
+                    }
+                }
+            }
+            for comment in store.comments.iter_mut() {
+                if comment.post_slug == old_slug {
+                    comment.post_slug = new_slug.to_string();
+                }
+            }
+
+            // Older slugs point straight to the new one, and a slug that is
+            // taken back stops redirecting
+            store
+                .slug_history
+                .retain(|entry| entry.old_slug != new_slug);
+            for entry in store.slug_history.iter_mut() {
+                if entry.slug == old_slug {
+                    entry.slug = new_slug.to_string();
+                }
+            }
+            store.slug_history.push(SlugHistoryModel {
+                old_slug: old_slug.to_string(),
+                slug: new_slug.to_string(),
+            });
+
+            Ok(1)
+        })?
+    }
+
+    async fn add_slug_redirect(&self, old_slug: &str, slug: &str) -> Result<(), DbError> {
+        self.write(|store| {
+            if store
+                .slug_history
+                .iter()
+                .any(|entry| entry.old_slug == old_slug)
+            {
+                return Err(DbError::Conflict("slug already redirects".into()));
+            }
+
+            store.slug_history.push(SlugHistoryModel {
+                old_slug: old_slug.to_string(),
+                slug: slug.to_string(),
+            });
+            Ok(())
+        })?
+    }
+
+    async fn find_slug_redirect(&self, old_slug: &str) -> Result<Option<String>, DbError> {
+        self.read(|store| {
+            store
+                .slug_history
+                .iter()
+                .find(|entry| entry.old_slug == old_slug)
+                .map(|entry| entry.slug.clone())
+        })
+    }
+
+    async fn delete_slug_history(&self, slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.slug_history.len();
+            store.slug_history.retain(|entry| entry.slug != slug);
+            (count - store.slug_history.len()) as u64
+        })
+    }
+}
+
+impl TrashDb for InMemoryDBHandler {
+    async fn trash_post(
+        &self,
+        slug: &str,
+        deleted_by: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        self.write(
+            |store| match store.posts.iter().position(|post| post.slug == slug) {
+                Some(index) => {
+                    let post = store.posts.remove(index);
+                    store.trash.push(TrashedPostModel {
+                        post,
+                        deleted_at,
+                        deleted_by: deleted_by.to_string(),
+                    });
+                    1
+                }
+                None => 0,
+            },
+        )
+    }
+
+    async fn get_trashed_posts(&self) -> Result<Vec<TrashedPost>, DbError> {
+        self.read(|store| {
+            let mut trash: Vec<TrashedPost> = store
+                .trash
+                .iter()
+                .map(|trashed| trashed.clone().into())
+                .collect();
+
+            trash.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted_at));
+            trash
+        })
+    }
+
+    async fn get_trashed_post(&self, slug: &str) -> Result<Option<TrashedPost>, DbError> {
+        self.read(|store| {
+            store
+                .trash
+                .iter()
+                .find(|trashed| trashed.post.slug == slug)
+                .map(|trashed| trashed.clone().into())
+        })
+    }
+
+    async fn restore_post(&self, slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            if store.posts.iter().any(|post| post.slug == slug) {
+                return Err(DbError::Conflict("post already exists".into()));
+            }
+
+            match store
+                .trash
+                .iter()
+                .position(|trashed| trashed.post.slug == slug)
+            {
+                Some(index) => {
+                    let trashed = store.trash.remove(index);
+                    store.posts.push(trashed.post);
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        })?
+    }
+
+    async fn purge_post(&self, slug: &str) -> Result<u64, DbError> {
+        self.write(|store| {
+            let count = store.trash.len();
+            store.trash.retain(|trashed| trashed.post.slug != slug);
+            (count - store.trash.len()) as u64
+        })
+    }
+
+    async fn get_expired_trash(&self, threshold: DateTime<Utc>) -> Result<Vec<String>, DbError> {
+        self.read(|store| {
+            store
+                .trash
+                .iter()
+                .filter(|trashed| trashed.deleted_at <= threshold)
+                .map(|trashed| trashed.post.slug.clone())
+                .collect()
+        })
+    }
+}
+
+impl SettingsDb for InMemoryDBHandler {
+    async fn get_settings(&self) -> Result<SiteSettings, DbError> {
+        self.read(|store| store.settings.clone())
+    }
+
+    async fn save_settings(&self, settings: &SiteSettings) -> Result<(), DbError> {
+        self.write(|store| store.settings = settings.clone())
+    }
+}
@@ -1,5 +1,41 @@
+pub mod comment;
+mod error;
+pub mod memory;
 pub mod mongo;
 pub mod post;
+pub mod revision;
+pub mod series;
+pub mod settings;
+pub mod slug;
+pub mod sql;
+pub mod trash;
 pub mod user;
 
-pub trait DBHandler: user::UserDb + user::UnconfirmedUserDb + post::PostDb {}
+pub use error::DbError;
+
+/// Lifetime of unconfirmed users and temporary files.
+pub const EXPIRE_AFTER_SECS: u64 = 24 * 60 * 60;
+/// How long unconfirmed users are kept around, well past their expiry, so an
+/// old confirmation link can still tell why it no longer works.
+pub const UNCONFIRMED_USER_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+/// Lifetime of password reset links.
+pub const PASSWORD_RESET_EXPIRE_AFTER_SECS: u64 = 60 * 60;
+/// Most posts a search returns, so that a common word doesn't load the
+/// whole blog.
+pub const MAX_SEARCH_RESULTS: usize = 100;
+
+pub trait DBHandler:
+    user::UserDb
+    + user::UnconfirmedUserDb
+    + user::PasswordResetDb
+    + user::TwoFactorDb
+    + user::ApiTokenDb
+    + post::PostDb
+    + revision::RevisionDb
+    + series::SeriesDb
+    + settings::SettingsDb
+    + comment::CommentDb
+    + slug::SlugDb
+    + trash::TrashDb
+{
+}
@@ -1,28 +1,54 @@
 use std::error::Error;
 use std::path::Path;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures_util::TryStreamExt;
 use mongodb::{
-    bson::doc,
+    bson::{doc, oid::ObjectId, Bson, Document},
     options::{ClientOptions, IndexOptions},
     Client, IndexModel,
 };
 
 use super::{
+    comment::CommentDb,
     post::PostDb,
-    user::{UnconfirmedUserDb, UserDb},
-    DBHandler,
+    revision::RevisionDb,
+    series::SeriesDb,
+    settings::SettingsDb,
+    slug::SlugDb,
+    trash::TrashDb,
+    user::{ApiTokenDb, PasswordResetDb, TwoFactorDb, UnconfirmedUserDb, UserDb},
+    DBHandler, DbError, EXPIRE_AFTER_SECS, MAX_SEARCH_RESULTS, PASSWORD_RESET_EXPIRE_AFTER_SECS,
+    UNCONFIRMED_USER_RETENTION_SECS,
+};
+use crate::models::{
+    ApiTokenModel, CommentModel, PageCursor, PasswordReset, PostCursor, PostModel,
+    PostRevisionModel, PostSummaryModel, PostsQueryParams, SlugHistoryModel, TempFileModel,
+    TrashedPostModel, TwoFactor, UnconfirmedUser, User,
+};
+use common::{
+    Comment, CommentStatus, Post, PostRevision, PostSummary, Role, Series, SiteSettings, TagCount,
+    TrashedPost,
 };
-use crate::models::{PostModel, PostsQueryParams, TempFileModel, UnconfirmedUser, User};
-use common::Post;
 
 #[derive(Clone)]
 pub struct MongoDBHandler {
     user_collection: mongodb::Collection<User>,
     unconfirmed_user_collection: mongodb::Collection<UnconfirmedUser>,
+    password_reset_collection: mongodb::Collection<PasswordReset>,
+    two_factor_collection: mongodb::Collection<TwoFactor>,
+    api_token_collection: mongodb::Collection<ApiTokenModel>,
     post_collection: mongodb::Collection<PostModel>,
+    /// Same collection as `post_collection`, read with `summary_projection`.
+    post_summary_collection: mongodb::Collection<PostSummaryModel>,
     temp_file_collection: mongodb::Collection<TempFileModel>,
+    post_revision_collection: mongodb::Collection<PostRevisionModel>,
+    series_collection: mongodb::Collection<Series>,
+    comment_collection: mongodb::Collection<CommentModel>,
+    slug_history_collection: mongodb::Collection<SlugHistoryModel>,
+    trash_collection: mongodb::Collection<TrashedPostModel>,
+    /// Holds a single document.
+    settings_collection: mongodb::Collection<SiteSettings>,
 }
 
 impl MongoDBHandler {
@@ -38,18 +64,42 @@ impl MongoDBHandler {
         let user_collection = db_client.collection::<User>("users");
         let unconfirmed_user_collection =
             db_client.collection::<UnconfirmedUser>("unconfirmed_users");
+        let password_reset_collection = db_client.collection::<PasswordReset>("password_resets");
+        let two_factor_collection = db_client.collection::<TwoFactor>("two_factor");
+        let api_token_collection = db_client.collection::<ApiTokenModel>("api_tokens");
         let post_collection = db_client.collection::<PostModel>("posts");
         let temp_file_collection = db_client.collection::<TempFileModel>("temp_files");
+        let post_revision_collection = db_client.collection::<PostRevisionModel>("post_revisions");
+        let series_collection = db_client.collection::<Series>("series");
+        let comment_collection = db_client.collection::<CommentModel>("comments");
+        let slug_history_collection = db_client.collection::<SlugHistoryModel>("slug_history");
+        let trash_collection = db_client.collection::<TrashedPostModel>("trash");
+        let settings_collection = db_client.collection::<SiteSettings>("settings");
 
         let options = IndexOptions::builder()
-            .expire_after(std::time::Duration::from_secs(24 * 60 * 60))
+            .expire_after(std::time::Duration::from_secs(EXPIRE_AFTER_SECS))
             .build();
 
+        // Unconfirmed users used to be removed as soon as they expired, and
+        // an index can't be created again with a different expiry
+        let retention = std::time::Duration::from_secs(UNCONFIRMED_USER_RETENTION_SECS);
+        let mut indexes = unconfirmed_user_collection.list_indexes().await?;
+        while let Some(index) = indexes.try_next().await? {
+            let Some(options) = index.options else {
+                continue;
+            };
+            if let (Some(expire_after), Some(name)) = (options.expire_after, options.name) {
+                if expire_after != retention {
+                    unconfirmed_user_collection.drop_index(name).await?;
+                }
+            }
+        }
+
         unconfirmed_user_collection
             .create_index(
                 IndexModel::builder()
                     .keys(doc! {"created_at": 1})
-                    .options(options.clone())
+                    .options(IndexOptions::builder().expire_after(retention).build())
                     .build(),
             )
             .await?;
@@ -63,152 +113,595 @@ impl MongoDBHandler {
             )
             .await?;
 
+        unconfirmed_user_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"confirmation_token": 1})
+                    .build(),
+            )
+            .await?;
+
+        password_reset_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"created_at": 1})
+                    .options(
+                        IndexOptions::builder()
+                            .expire_after(std::time::Duration::from_secs(
+                                PASSWORD_RESET_EXPIRE_AFTER_SECS,
+                            ))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await?;
+
+        // Resets used to store the token itself, those links stop working
+        if password_reset_collection
+            .list_index_names()
+            .await?
+            .iter()
+            .any(|name| name == "token_1")
+        {
+            password_reset_collection
+                .delete_many(doc! {"token_hash": {"$exists": false}})
+                .await?;
+            password_reset_collection.drop_index("token_1").await?;
+        }
+
+        password_reset_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"token_hash": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        two_factor_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"username": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        for key in ["id", "token_hash"] {
+            api_token_collection
+                .create_index(
+                    IndexModel::builder()
+                        .keys(doc! {key: 1})
+                        .options(IndexOptions::builder().unique(true).build())
+                        .build(),
+                )
+                .await?;
+        }
+
+        api_token_collection
+            .create_index(IndexModel::builder().keys(doc! {"username": 1}).build())
+            .await?;
+
+        post_collection
+            .create_index(IndexModel::builder().keys(doc! {"tags": 1}).build())
+            .await?;
+
+        post_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"published_at": -1, "_id": -1})
+                    .build(),
+            )
+            .await?;
+
+        post_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"title": "text", "summary": "text", "content": "text"})
+                    .options(
+                        IndexOptions::builder()
+                            .weights(doc! {"title": 10, "summary": 5, "content": 1})
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await?;
+
+        post_revision_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"slug": 1, "number": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        series_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"slug": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        series_collection
+            .create_index(IndexModel::builder().keys(doc! {"posts": 1}).build())
+            .await?;
+
+        comment_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"id": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        comment_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"post_slug": 1, "created_at": 1})
+                    .build(),
+            )
+            .await?;
+
+        comment_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"status": 1, "created_at": 1})
+                    .build(),
+            )
+            .await?;
+
+        slug_history_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"old_slug": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        slug_history_collection
+            .create_index(IndexModel::builder().keys(doc! {"slug": 1}).build())
+            .await?;
+
+        trash_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! {"post.slug": 1})
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+
+        trash_collection
+            .create_index(IndexModel::builder().keys(doc! {"deleted_at": 1}).build())
+            .await?;
+
+        // Posts saved before previews were stored get theirs now
+        let mut outdated = post_collection
+            .find(doc! {"preview": {"$exists": false}})
+            .await?;
+        while let Some(post) = outdated.try_next().await? {
+            let preview = Post::from(post.clone()).preview();
+            post_collection
+                .update_one(
+                    doc! {"_id": post.id},
+                    doc! {"$set": doc! {"preview": preview}},
+                )
+                .await?;
+        }
+
+        let mut outdated = trash_collection
+            .find(doc! {"post.preview": {"$exists": false}})
+            .await?;
+        while let Some(trashed) = outdated.try_next().await? {
+            let preview = Post::from(trashed.post.clone()).preview();
+            trash_collection
+                .update_one(
+                    doc! {"post.slug": &trashed.post.slug},
+                    doc! {"$set": doc! {"post.preview": preview}},
+                )
+                .await?;
+        }
+
         Ok(Self {
             user_collection,
             unconfirmed_user_collection,
+            password_reset_collection,
+            two_factor_collection,
+            api_token_collection,
+            post_summary_collection: post_collection.clone_with_type(),
             post_collection,
             temp_file_collection,
+            post_revision_collection,
+            series_collection,
+            comment_collection,
+            slug_history_collection,
+            trash_collection,
+            settings_collection,
         })
     }
 }
 
+/// Unconfirmed users created before this have expired.
+fn confirmation_threshold() -> bson::DateTime {
+    bson::DateTime::from_chrono(Utc::now() - chrono::Duration::seconds(EXPIRE_AFTER_SECS as i64))
+}
+
+fn visibility_filter(is_admin: bool) -> Document {
+    if is_admin {
+        doc! {}
+    } else {
+        doc! { "public": true, "published_at": { "$lte": bson::DateTime::now() } }
+    }
+}
+
+/// Leaves the content out of the posts read through `post_summary_collection`.
+fn summary_projection() -> Document {
+    doc! { "content": 0, "summary": 0 }
+}
+
+fn posts_filter(query: &PostsQueryParams, is_admin: bool) -> Document {
+    let mut filter = visibility_filter(is_admin);
+    if let Some(ref tag) = query.tag {
+        filter.insert("tags", tag);
+    }
+    filter
+}
+
 impl DBHandler for MongoDBHandler {}
 
 impl UserDb for MongoDBHandler {
-    async fn find_user(&self, username: &str) -> Result<Option<User>, ()> {
-        self.user_collection
+    async fn find_user(&self, username: &str) -> Result<Option<User>, DbError> {
+        Ok(self
+            .user_collection
             .find_one(doc! {"username": username})
-            .await
-            .or(Err(()))
+            .await?)
     }
 
-    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, ()> {
-        self.user_collection
-            .find_one(doc! {"email": email})
-            .await
-            .or(Err(()))
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DbError> {
+        Ok(self.user_collection.find_one(doc! {"email": email}).await?)
     }
 
-    async fn insert_user(&self, user: &User) -> Result<(), ()> {
-        match self.user_collection.insert_one(user).await {
-            Ok(_) => Ok(()),
-            Err(_) => Err(()),
-        }
+    async fn insert_user(&self, user: &User) -> Result<(), DbError> {
+        self.user_collection.insert_one(user).await?;
+        Ok(())
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>, DbError> {
+        Ok(self
+            .user_collection
+            .find(doc! {})
+            .sort(doc! {"username": 1})
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    async fn update_user_role(&self, username: &str, role: Role) -> Result<u64, DbError> {
+        let result = self
+            .user_collection
+            .update_one(
+                doc! {"username": username},
+                doc! {"$set": doc! {"role": role.as_str()}},
+            )
+            .await?;
+
+        Ok(result.matched_count)
+    }
+
+    async fn update_user_password(&self, username: &str, password: &str) -> Result<u64, DbError> {
+        let result = self
+            .user_collection
+            .update_one(
+                doc! {"username": username},
+                doc! {"$set": doc! {
+                    "password": password,
+                    "password_changed_at": bson::DateTime::now(),
+                }},
+            )
+            .await?;
+
+        Ok(result.matched_count)
+    }
+}
+
+impl PasswordResetDb for MongoDBHandler {
+    async fn insert_password_reset(&self, reset: &PasswordReset) -> Result<(), DbError> {
+        self.password_reset_collection.insert_one(reset).await?;
+        Ok(())
+    }
+
+    async fn take_password_reset(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PasswordReset>, DbError> {
+        // The TTL monitor only runs every minute or so
+        let threshold =
+            Utc::now() - chrono::Duration::seconds(PASSWORD_RESET_EXPIRE_AFTER_SECS as i64);
+        let Some(reset) = self
+            .password_reset_collection
+            .find_one_and_delete(doc! {
+                "token_hash": token_hash,
+                "created_at": {"$gt": bson::DateTime::from_chrono(threshold)},
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.password_reset_collection
+            .delete_many(doc! {"username": &reset.username})
+            .await?;
+
+        Ok(Some(reset))
+    }
+}
+
+impl TwoFactorDb for MongoDBHandler {
+    async fn find_two_factor(&self, username: &str) -> Result<Option<TwoFactor>, DbError> {
+        Ok(self
+            .two_factor_collection
+            .find_one(doc! {"username": username})
+            .await?)
+    }
+
+    async fn save_two_factor(&self, two_factor: &TwoFactor) -> Result<(), DbError> {
+        self.two_factor_collection
+            .replace_one(doc! {"username": &two_factor.username}, two_factor)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_two_factor(&self, username: &str) -> Result<u64, DbError> {
+        let result = self
+            .two_factor_collection
+            .delete_one(doc! {"username": username})
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn use_totp_step(&self, username: &str, step: i64) -> Result<u64, DbError> {
+        let result = self
+            .two_factor_collection
+            .update_one(
+                doc! {
+                    "username": username,
+                    "enabled": true,
+                    "$or": [
+                        {"last_used_step": null},
+                        {"last_used_step": {"$lt": step}},
+                    ],
+                },
+                doc! {"$set": doc! {"last_used_step": step}},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<u64, DbError> {
+        let result = self
+            .two_factor_collection
+            .update_one(
+                doc! {"username": username, "enabled": true, "recovery_codes": code_hash},
+                doc! {"$pull": doc! {"recovery_codes": code_hash}},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+}
+
+impl ApiTokenDb for MongoDBHandler {
+    async fn insert_api_token(&self, token: &ApiTokenModel) -> Result<(), DbError> {
+        self.api_token_collection.insert_one(token).await?;
+        Ok(())
+    }
+
+    async fn get_api_tokens(&self, username: &str) -> Result<Vec<ApiTokenModel>, DbError> {
+        Ok(self
+            .api_token_collection
+            .find(doc! {"username": username})
+            .sort(doc! {"created_at": 1})
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiTokenModel>, DbError> {
+        Ok(self
+            .api_token_collection
+            .find_one(doc! {"token_hash": token_hash})
+            .await?)
+    }
+
+    async fn update_api_token_last_used(
+        &self,
+        id: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let result = self
+            .api_token_collection
+            .update_one(
+                doc! {"id": id},
+                doc! {"$set": doc! {"last_used_at": bson::DateTime::from_chrono(last_used_at)}},
+            )
+            .await?;
+
+        Ok(result.matched_count)
+    }
+
+    async fn delete_api_token(&self, username: &str, id: &str) -> Result<u64, DbError> {
+        let result = self
+            .api_token_collection
+            .delete_one(doc! {"username": username, "id": id})
+            .await?;
+
+        Ok(result.deleted_count)
     }
 }
 
 impl UnconfirmedUserDb for MongoDBHandler {
-    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), ()> {
-        match self.unconfirmed_user_collection.insert_one(user).await {
-            Ok(_) => Ok(()),
-            Err(_) => Err(()),
-        }
+    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), DbError> {
+        self.unconfirmed_user_collection.insert_one(user).await?;
+        Ok(())
     }
 
-    async fn confirm_user(&self, confirmation_token: &str) -> Result<Option<UnconfirmedUser>, ()> {
-        if let Ok(user_option) = self
+    async fn get_unconfirmed_users(&self) -> Result<Vec<UnconfirmedUser>, DbError> {
+        Ok(self
             .unconfirmed_user_collection
-            .find_one(doc! {"confirmation_token": confirmation_token})
-            .await
-        {
-            match user_option {
-                Some(user) if !user.confirmed => {
-                    if self
-                        .unconfirmed_user_collection
-                        .update_one(
-                            doc! {"confirmation_token": confirmation_token},
-                            doc! {"$set": doc! {"confirmed": true}},
-                        )
-                        .await
-                        .is_ok()
-                    {
-                        return Ok(Some(user));
-                    }
-                }
-                _ => return Ok(None),
-            }
-        }
+            .find(doc! {"confirmed": false, "created_at": {"$gt": confirmation_threshold()}})
+            .sort(doc! {"created_at": 1})
+            .await?
+            .try_collect()
+            .await?)
+    }
 
-        Err(())
+    async fn confirm_user(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        // Only one concurrent confirmation can flip the flag
+        Ok(self
+            .unconfirmed_user_collection
+            .find_one_and_update(
+                doc! {
+                    "confirmation_token": confirmation_token,
+                    "confirmed": false,
+                    "created_at": {"$gt": confirmation_threshold()},
+                },
+                doc! {"$set": doc! {"confirmed": true}},
+            )
+            .await?)
     }
 
-    async fn find_unconfirmed_user(&self, username: &str) -> Result<Option<UnconfirmedUser>, ()> {
-        self.unconfirmed_user_collection
-            .find_one(doc! {"username": username})
-            .await
-            .or(Err(()))
+    async fn renew_confirmation(
+        &self,
+        confirmation_token: &str,
+        new_token: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let result = self
+            .unconfirmed_user_collection
+            .update_one(
+                doc! {"confirmation_token": confirmation_token, "confirmed": false},
+                doc! {"$set": doc! {
+                    "confirmation_token": new_token,
+                    "created_at": bson::DateTime::from_chrono(created_at),
+                }},
+            )
+            .await?;
+
+        Ok(result.matched_count)
+    }
+
+    async fn find_unconfirmed_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        Ok(self
+            .unconfirmed_user_collection
+            .find_one(doc! {"username": username, "created_at": {"$gt": confirmation_threshold()}})
+            .await?)
     }
 
     async fn find_unconfirmed_user_user_by_email(
         &self,
         email: &str,
-    ) -> Result<Option<UnconfirmedUser>, ()> {
-        self.unconfirmed_user_collection
-            .find_one(doc! {"email": email})
-            .await
-            .or(Err(()))
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        Ok(self
+            .unconfirmed_user_collection
+            .find_one(doc! {"email": email, "created_at": {"$gt": confirmation_threshold()}})
+            .await?)
+    }
+
+    async fn find_latest_unconfirmed_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        Ok(self
+            .unconfirmed_user_collection
+            .find_one(doc! {"email": email, "confirmed": false})
+            .sort(doc! {"created_at": -1})
+            .await?)
+    }
+
+    async fn find_confirmation(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        Ok(self
+            .unconfirmed_user_collection
+            .find_one(doc! {"confirmation_token": confirmation_token})
+            .await?)
     }
 }
 
 impl PostDb for MongoDBHandler {
-    async fn create_post(&self, post: &Post) -> Result<(), ()> {
-        match self
-            .post_collection
+    async fn create_post(&self, post: &Post) -> Result<(), DbError> {
+        self.post_collection
             .insert_one(PostModel::from(post.clone()))
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(_) => Err(()),
-        }
+            .await?;
+        Ok(())
     }
 
-    async fn update_post(
-        &self,
-        slug: &str,
-        updated_content: &str,
-        updated_title: &str,
-        updated_summary: Option<&str>,
-        updated_public: bool,
-    ) -> Result<u64, ()> {
-        match self
+    async fn update_post(&self, post: &Post) -> Result<u64, DbError> {
+        let result = self
             .post_collection
             .update_one(
-                doc! {"slug": slug},
-                doc! {"$set": doc! {"content": updated_content, "title": updated_title, "summary": updated_summary, "public": updated_public}},
+                doc! {"slug": &post.slug},
+                doc! {"$set": doc! {
+                    "content": &post.content,
+                    "title": &post.title,
+                    "summary": &post.summary,
+                    "public": post.public,
+                    "published_at": bson::DateTime::from_chrono(post.published_at),
+                    "tags": &post.tags,
+                    "preview": post.preview(),
+                }},
             )
-            .await
-        {
-            Ok(result) => Ok(result.modified_count),
-            Err(_) => Err(()),
-        }
+            .await?;
+
+        Ok(result.modified_count)
     }
 
-    async fn delete_post(&self, slug: &str) -> Result<u64, ()> {
-        match self.post_collection.delete_one(doc! {"slug": slug}).await {
-            Ok(result) => Ok(result.deleted_count),
-            Err(_) => Err(()),
-        }
+    async fn set_post_author(&self, slug: &str, author: &str) -> Result<u64, DbError> {
+        let result = self
+            .post_collection
+            .update_one(doc! {"slug": slug}, doc! {"$set": {"author": author}})
+            .await?;
+
+        Ok(result.matched_count)
     }
 
-    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, ()> {
+    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, DbError> {
         let filter = if is_admin {
             doc! {"slug": slug}
         } else {
-            doc! {"slug": slug, "public": true}
+            doc! {"slug": slug, "public": true, "published_at": {"$lte": bson::DateTime::now()}}
         };
 
-        match self.post_collection.find_one(filter).await {
-            Ok(Some(post)) => Ok(Some(post.into())),
-            Ok(None) => Ok(None),
-            Err(_) => Err(()),
-        }
+        Ok(self
+            .post_collection
+            .find_one(filter)
+            .await?
+            .map(|post| post.into()))
     }
 
-    async fn get_posts(&self, query: &PostsQueryParams, is_admin: bool) -> Result<Vec<Post>, ()> {
+    async fn get_posts(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<Vec<PostSummary>, DbError> {
         let page = query.page.unwrap_or(1);
         let per_page = query.per_page.unwrap_or(10);
         let limit = per_page as i64;
-        let offset = (page - 1) * per_page;
+        let offset = page.saturating_sub(1).saturating_mul(per_page);
 
         let sort_option = if let Some(ref sort_by) = query.sort_by {
             let sort_order = if query.sort_order.as_deref() == Some("desc") {
@@ -221,57 +714,542 @@ impl PostDb for MongoDBHandler {
             doc! { "published_at": -1 }
         };
 
-        let filter = if is_admin {
-            doc! {}
-        } else {
-            doc! { "public": true }
-        };
+        let filter = posts_filter(query, is_admin);
 
-        if let Ok(cursor) = self
-            .post_collection
+        let cursor = self
+            .post_summary_collection
             .find(filter)
+            .projection(summary_projection())
             .limit(limit)
             .skip(offset)
             .sort(sort_option)
-            .await
-        {
-            match cursor.try_collect::<Vec<PostModel>>().await {
-                Ok(v) => {
-                    return Ok(v.into_iter().map(|post| post.into()).collect());
-                }
-                Err(e) => {
-                    println!("Error: {}", e);
-                }
+            .await?;
+
+        let posts = cursor.try_collect::<Vec<PostSummaryModel>>().await?;
+        Ok(posts.into_iter().map(|post| post.into()).collect())
+    }
+
+    async fn get_posts_page(
+        &self,
+        query: &PostsQueryParams,
+        cursor: &PageCursor,
+        limit: u64,
+        is_admin: bool,
+    ) -> Result<Vec<(PostSummary, PostCursor)>, DbError> {
+        let mut filter = posts_filter(query, is_admin);
+        let mut sort_order = -1;
+
+        let position = match cursor {
+            PageCursor::First => None,
+            PageCursor::After(after) => Some((after, "$lt")),
+            PageCursor::Before(before) => {
+                sort_order = 1;
+                Some((before, "$gt"))
             }
+        };
+        if let Some((cursor, operator)) = position {
+            let Ok(id) = ObjectId::parse_str(&cursor.id) else {
+                return Err(DbError::InvalidCursor);
+            };
+            let published_at = bson::DateTime::from_chrono(cursor.published_at);
+
+            filter.insert(
+                "$or",
+                vec![
+                    doc! { "published_at": { operator: published_at } },
+                    doc! { "published_at": published_at, "_id": { operator: id } },
+                ],
+            );
         }
 
-        Err(())
+        let mut posts: Vec<PostSummaryModel> = self
+            .post_summary_collection
+            .find(filter)
+            .projection(summary_projection())
+            .sort(doc! { "published_at": sort_order, "_id": sort_order })
+            .limit(limit as i64)
+            .await?
+            .try_collect()
+            .await?;
+        if sort_order == 1 {
+            posts.reverse();
+        }
+
+        Ok(posts
+            .into_iter()
+            .map(|post| {
+                let cursor = PostCursor {
+                    published_at: post.published_at,
+                    id: post.id.map(|id| id.to_hex()).unwrap_or_default(),
+                };
+                (post.into(), cursor)
+            })
+            .collect())
     }
 
-    async fn calculate_total_pages(&self, per_page: u64) -> Result<u64, ()> {
-        if let Ok(total_posts) = self.post_collection.count_documents(doc! {}).await {
-            let total_pages = (total_posts as f64 / per_page as f64).ceil() as u64;
-            return Ok(total_pages);
-        }
-        Err(())
+    async fn calculate_total_pages(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<u64, DbError> {
+        let per_page = query.per_page.unwrap_or(10);
+        let filter = posts_filter(query, is_admin);
+        let total_posts = self.post_collection.count_documents(filter).await?;
+        Ok((total_posts as f64 / per_page as f64).ceil() as u64)
     }
 
-    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), ()> {
-        if let Some(path_str) = path.to_str() {
-            return match self
-                .temp_file_collection
-                .insert_one(TempFileModel {
-                    path: String::from(path_str),
-                    filename: String::from(filename),
-                    created_at: Utc::now(),
+    async fn get_tags(&self, is_admin: bool) -> Result<Vec<TagCount>, DbError> {
+        let pipeline = [
+            doc! { "$match": visibility_filter(is_admin) },
+            doc! { "$unwind": "$tags" },
+            doc! { "$group": { "_id": "$tags", "count": { "$sum": 1 } } },
+            doc! { "$sort": { "count": -1, "_id": 1 } },
+        ];
+
+        let groups = self
+            .post_collection
+            .aggregate(pipeline)
+            .await?
+            .try_collect::<Vec<Document>>()
+            .await?;
+
+        groups
+            .iter()
+            .map(|group| {
+                let tag = group
+                    .get_str("_id")
+                    .map_err(|err| DbError::Serialization(err.into()))?;
+                let count = match group.get("count") {
+                    Some(Bson::Int32(count)) => *count as u64,
+                    Some(Bson::Int64(count)) => *count as u64,
+                    _ => 0,
+                };
+
+                Ok(TagCount {
+                    tag: tag.to_string(),
+                    count,
                 })
-                .await
-            {
-                Ok(_) => Ok(()),
-                Err(_) => Err(()),
-            };
+            })
+            .collect()
+    }
+
+    async fn search_posts(&self, terms: &[String], is_admin: bool) -> Result<Vec<Post>, DbError> {
+        let mut filter = visibility_filter(is_admin);
+        filter.insert("$text", doc! { "$search": terms.join(" ") });
+
+        let cursor = self
+            .post_collection
+            .find(filter)
+            .sort(doc! { "score": { "$meta": "textScore" }, "published_at": -1 })
+            .limit(MAX_SEARCH_RESULTS as i64)
+            .await?;
+
+        let posts = cursor.try_collect::<Vec<PostModel>>().await?;
+        Ok(posts.into_iter().map(|post| post.into()).collect())
+    }
+
+    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), DbError> {
+        let Some(path_str) = path.to_str() else {
+            return Err(DbError::other("temporary file path is not valid UTF-8"));
         };
 
-        Err(())
+        self.temp_file_collection
+            .insert_one(TempFileModel {
+                path: String::from(path_str),
+                filename: String::from(filename),
+                created_at: Utc::now(),
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl RevisionDb for MongoDBHandler {
+    async fn create_revision(&self, revision: &PostRevision) -> Result<(), DbError> {
+        self.post_revision_collection
+            .insert_one(PostRevisionModel::from(revision.clone()))
+            .await?;
+        Ok(())
+    }
+
+    async fn get_revisions(&self, slug: &str) -> Result<Vec<PostRevision>, DbError> {
+        let cursor = self
+            .post_revision_collection
+            .find(doc! {"slug": slug})
+            .sort(doc! {"number": 1})
+            .await?;
+
+        let revisions = cursor.try_collect::<Vec<PostRevisionModel>>().await?;
+        Ok(revisions
+            .into_iter()
+            .map(|revision| revision.into())
+            .collect())
+    }
+
+    async fn get_revision(&self, slug: &str, number: u64) -> Result<Option<PostRevision>, DbError> {
+        Ok(self
+            .post_revision_collection
+            .find_one(doc! {"slug": slug, "number": number as i64})
+            .await?
+            .map(|revision| revision.into()))
+    }
+
+    async fn delete_revisions(&self, slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .post_revision_collection
+            .delete_many(doc! {"slug": slug})
+            .await?;
+        Ok(result.deleted_count)
+    }
+}
+
+impl SeriesDb for MongoDBHandler {
+    async fn create_series(&self, series: &Series) -> Result<(), DbError> {
+        self.series_collection.insert_one(series).await?;
+        Ok(())
+    }
+
+    async fn update_series(&self, series: &Series) -> Result<u64, DbError> {
+        let result = self
+            .series_collection
+            .update_one(
+                doc! {"slug": &series.slug},
+                doc! {"$set": doc! {
+                    "title": &series.title,
+                    "description": &series.description,
+                    "posts": &series.posts,
+                }},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_series(&self, slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .series_collection
+            .delete_one(doc! {"slug": slug})
+            .await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn get_series(&self, slug: &str) -> Result<Option<Series>, DbError> {
+        Ok(self.series_collection.find_one(doc! {"slug": slug}).await?)
+    }
+
+    async fn get_all_series(&self) -> Result<Vec<Series>, DbError> {
+        let cursor = self
+            .series_collection
+            .find(doc! {})
+            .sort(doc! {"title": 1})
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn find_series_by_post(&self, post_slug: &str) -> Result<Option<Series>, DbError> {
+        Ok(self
+            .series_collection
+            .find_one(doc! {"posts": post_slug})
+            .await?)
+    }
+
+    async fn remove_post_from_series(&self, post_slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .series_collection
+            .update_many(
+                doc! {"posts": post_slug},
+                doc! {"$pull": doc! {"posts": post_slug}},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+}
+
+impl CommentDb for MongoDBHandler {
+    async fn create_comment(&self, comment: &Comment) -> Result<(), DbError> {
+        self.comment_collection
+            .insert_one(CommentModel::from(comment.clone()))
+            .await?;
+        Ok(())
+    }
+
+    async fn get_comment(&self, id: &str) -> Result<Option<Comment>, DbError> {
+        Ok(self
+            .comment_collection
+            .find_one(doc! {"id": id})
+            .await?
+            .map(|comment| comment.into()))
+    }
+
+    async fn get_comments(&self, post_slug: &str) -> Result<Vec<Comment>, DbError> {
+        let cursor = self
+            .comment_collection
+            .find(doc! {"post_slug": post_slug})
+            .sort(doc! {"created_at": 1})
+            .await?;
+
+        let comments = cursor.try_collect::<Vec<CommentModel>>().await?;
+        Ok(comments.into_iter().map(|comment| comment.into()).collect())
+    }
+
+    async fn get_comments_by_status(&self, status: CommentStatus) -> Result<Vec<Comment>, DbError> {
+        let cursor = self
+            .comment_collection
+            .find(doc! {"status": bson::to_bson(&status)?})
+            .sort(doc! {"created_at": 1})
+            .await?;
+
+        let comments = cursor.try_collect::<Vec<CommentModel>>().await?;
+        Ok(comments.into_iter().map(|comment| comment.into()).collect())
+    }
+
+    async fn update_comment(
+        &self,
+        id: &str,
+        content: &str,
+        edited_at: DateTime<Utc>,
+        status: CommentStatus,
+    ) -> Result<u64, DbError> {
+        let result = self
+            .comment_collection
+            .update_one(
+                doc! {"id": id},
+                doc! {"$set": doc! {
+                    "content": content,
+                    "edited_at": bson::DateTime::from_chrono(edited_at),
+                    "status": bson::to_bson(&status)?,
+                }},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn set_comment_status(&self, id: &str, status: CommentStatus) -> Result<u64, DbError> {
+        let result = self
+            .comment_collection
+            .update_one(
+                doc! {"id": id},
+                doc! {"$set": doc! {"status": bson::to_bson(&status)?}},
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_comment(&self, id: &str) -> Result<u64, DbError> {
+        let result = self.comment_collection.delete_one(doc! {"id": id}).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn delete_post_comments(&self, post_slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .comment_collection
+            .delete_many(doc! {"post_slug": post_slug})
+            .await?;
+        Ok(result.deleted_count)
+    }
+}
+
+impl SlugDb for MongoDBHandler {
+    async fn rename_post(&self, old_slug: &str, new_slug: &str) -> Result<u64, DbError> {
+        if self
+            .post_collection
+            .find_one(doc! {"slug": new_slug})
+            .await?
+            .is_some()
+        {
+            return Err(DbError::Conflict("post already exists".into()));
+        }
+
+        let result = self
+            .post_collection
+            .update_one(
+                doc! {"slug": old_slug},
+                doc! {"$set": doc! {"slug": new_slug}},
+            )
+            .await?;
+        if result.matched_count == 0 {
+            return Ok(0);
+        }
+
+        self.post_revision_collection
+            .update_many(
+                doc! {"slug": old_slug},
+                doc! {"$set": doc! {"slug": new_slug}},
+            )
+            .await?;
+        // A post is listed at most once per series
+        self.series_collection
+            .update_many(
+                doc! {"posts": old_slug},
+                doc! {"$set": doc! {"posts.$": new_slug}},
+            )
+            .await?;
+        self.comment_collection
+            .update_many(
+                doc! {"post_slug": old_slug},
+                doc! {"$set": doc! {"post_slug": new_slug}},
+            )
+            .await?;
+
+        // Older slugs point straight to the new one, and a slug that is taken
+        // back stops redirecting
+        self.slug_history_collection
+            .delete_one(doc! {"old_slug": new_slug})
+            .await?;
+        self.slug_history_collection
+            .update_many(
+                doc! {"slug": old_slug},
+                doc! {"$set": doc! {"slug": new_slug}},
+            )
+            .await?;
+        self.slug_history_collection
+            .insert_one(SlugHistoryModel {
+                old_slug: old_slug.to_string(),
+                slug: new_slug.to_string(),
+            })
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn add_slug_redirect(&self, old_slug: &str, slug: &str) -> Result<(), DbError> {
+        self.slug_history_collection
+            .insert_one(SlugHistoryModel {
+                old_slug: old_slug.to_string(),
+                slug: slug.to_string(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_slug_redirect(&self, old_slug: &str) -> Result<Option<String>, DbError> {
+        Ok(self
+            .slug_history_collection
+            .find_one(doc! {"old_slug": old_slug})
+            .await?
+            .map(|entry| entry.slug))
+    }
+
+    async fn delete_slug_history(&self, slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .slug_history_collection
+            .delete_many(doc! {"slug": slug})
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+}
+
+impl TrashDb for MongoDBHandler {
+    async fn trash_post(
+        &self,
+        slug: &str,
+        deleted_by: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let Some(post) = self.post_collection.find_one(doc! {"slug": slug}).await? else {
+            return Ok(0);
+        };
+
+        self.trash_collection
+            .insert_one(TrashedPostModel {
+                post,
+                deleted_at,
+                deleted_by: deleted_by.to_string(),
+            })
+            .await?;
+        let result = self.post_collection.delete_one(doc! {"slug": slug}).await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn get_trashed_posts(&self) -> Result<Vec<TrashedPost>, DbError> {
+        let trash: Vec<TrashedPostModel> = self
+            .trash_collection
+            .find(doc! {})
+            .sort(doc! {"deleted_at": -1})
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(trash.into_iter().map(|trashed| trashed.into()).collect())
+    }
+
+    async fn get_trashed_post(&self, slug: &str) -> Result<Option<TrashedPost>, DbError> {
+        Ok(self
+            .trash_collection
+            .find_one(doc! {"post.slug": slug})
+            .await?
+            .map(|trashed| trashed.into()))
+    }
+
+    async fn restore_post(&self, slug: &str) -> Result<u64, DbError> {
+        if self
+            .post_collection
+            .find_one(doc! {"slug": slug})
+            .await?
+            .is_some()
+        {
+            return Err(DbError::Conflict("post already exists".into()));
+        }
+
+        let Some(trashed) = self
+            .trash_collection
+            .find_one(doc! {"post.slug": slug})
+            .await?
+        else {
+            return Ok(0);
+        };
+
+        self.post_collection.insert_one(trashed.post).await?;
+        let result = self
+            .trash_collection
+            .delete_one(doc! {"post.slug": slug})
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn purge_post(&self, slug: &str) -> Result<u64, DbError> {
+        let result = self
+            .trash_collection
+            .delete_one(doc! {"post.slug": slug})
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn get_expired_trash(&self, threshold: DateTime<Utc>) -> Result<Vec<String>, DbError> {
+        let trash: Vec<TrashedPostModel> = self
+            .trash_collection
+            .find(doc! {"deleted_at": {"$lte": bson::DateTime::from_chrono(threshold)}})
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(trash.into_iter().map(|trashed| trashed.post.slug).collect())
+    }
+}
+
+impl SettingsDb for MongoDBHandler {
+    async fn get_settings(&self) -> Result<SiteSettings, DbError> {
+        Ok(self
+            .settings_collection
+            .find_one(doc! {})
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn save_settings(&self, settings: &SiteSettings) -> Result<(), DbError> {
+        self.settings_collection
+            .replace_one(doc! {}, settings)
+            .upsert(true)
+            .await?;
+        Ok(())
     }
 }
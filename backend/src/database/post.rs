@@ -1,21 +1,41 @@
 use std::path::Path;
 
-use crate::models::PostsQueryParams;
-use common::Post;
+use super::DbError;
+use crate::models::{PageCursor, PostCursor, PostsQueryParams};
+use common::{Post, PostSummary, TagCount};
 
 pub trait PostDb {
-    async fn create_post(&self, post: &Post) -> Result<(), ()>;
-    async fn update_post(
+    async fn create_post(&self, post: &Post) -> Result<(), DbError>;
+    /// Overwrites the editable fields of the post with the same slug.
+    async fn update_post(&self, post: &Post) -> Result<u64, DbError>;
+    /// Hands the post over to another user.
+    async fn set_post_author(&self, slug: &str, author: &str) -> Result<u64, DbError>;
+    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, DbError>;
+    /// A page of posts without their content, for listings.
+    async fn get_posts(
         &self,
-        slug: &str,
-        updated_content: &str,
-        updated_title: &str,
-        updated_summary: Option<&str>,
-        updated_public: bool,
-    ) -> Result<u64, ()>;
-    async fn delete_post(&self, slug: &str) -> Result<u64, ()>;
-    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, ()>;
-    async fn get_posts(&self, query: &PostsQueryParams, is_admin: bool) -> Result<Vec<Post>, ()>;
-    async fn calculate_total_pages(&self, per_page: u64) -> Result<u64, ()>;
-    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), ()>;
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<Vec<PostSummary>, DbError>;
+    /// Up to `limit` posts from `cursor` on, newest first, each with its own cursor.
+    /// Like `get_posts`, it leaves out the content of the posts.
+    async fn get_posts_page(
+        &self,
+        query: &PostsQueryParams,
+        cursor: &PageCursor,
+        limit: u64,
+        is_admin: bool,
+    ) -> Result<Vec<(PostSummary, PostCursor)>, DbError>;
+    /// Pages needed to list the posts matching `query` that the caller can see.
+    async fn calculate_total_pages(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<u64, DbError>;
+    /// Number of posts per tag, most used first.
+    async fn get_tags(&self, is_admin: bool) -> Result<Vec<TagCount>, DbError>;
+    /// Posts matching any of the lowercase `terms`, most relevant first and
+    /// at most `MAX_SEARCH_RESULTS` of them.
+    async fn search_posts(&self, terms: &[String], is_admin: bool) -> Result<Vec<Post>, DbError>;
+    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), DbError>;
 }
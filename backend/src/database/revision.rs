@@ -0,0 +1,10 @@
+use super::DbError;
+use common::PostRevision;
+
+pub trait RevisionDb {
+    async fn create_revision(&self, revision: &PostRevision) -> Result<(), DbError>;
+    /// Revisions of a post, oldest first.
+    async fn get_revisions(&self, slug: &str) -> Result<Vec<PostRevision>, DbError>;
+    async fn get_revision(&self, slug: &str, number: u64) -> Result<Option<PostRevision>, DbError>;
+    async fn delete_revisions(&self, slug: &str) -> Result<u64, DbError>;
+}
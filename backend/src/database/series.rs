@@ -0,0 +1,15 @@
+use super::DbError;
+use common::Series;
+
+pub trait SeriesDb {
+    async fn create_series(&self, series: &Series) -> Result<(), DbError>;
+    /// Overwrites the title, description and parts of the series with the same slug.
+    async fn update_series(&self, series: &Series) -> Result<u64, DbError>;
+    async fn delete_series(&self, slug: &str) -> Result<u64, DbError>;
+    async fn get_series(&self, slug: &str) -> Result<Option<Series>, DbError>;
+    async fn get_all_series(&self) -> Result<Vec<Series>, DbError>;
+    /// The series `post_slug` is a part of, if any.
+    async fn find_series_by_post(&self, post_slug: &str) -> Result<Option<Series>, DbError>;
+    /// Drops `post_slug` from every series listing it.
+    async fn remove_post_from_series(&self, post_slug: &str) -> Result<u64, DbError>;
+}
@@ -0,0 +1,8 @@
+use super::DbError;
+use common::SiteSettings;
+
+pub trait SettingsDb {
+    /// The stored settings, or the defaults when they were never saved.
+    async fn get_settings(&self) -> Result<SiteSettings, DbError>;
+    async fn save_settings(&self, settings: &SiteSettings) -> Result<(), DbError>;
+}
@@ -0,0 +1,14 @@
+use super::DbError;
+
+pub trait SlugDb {
+    /// Moves the post at `old_slug`, with its revisions, series entries and
+    /// comments, to `new_slug` and keeps `old_slug` in the slug history.
+    async fn rename_post(&self, old_slug: &str, new_slug: &str) -> Result<u64, DbError>;
+    /// Makes `old_slug` redirect to the post at `slug`, e.g. for the aliases
+    /// of imported posts.
+    async fn add_slug_redirect(&self, old_slug: &str, slug: &str) -> Result<(), DbError>;
+    /// Current slug of the post that was once at `old_slug`.
+    async fn find_slug_redirect(&self, old_slug: &str) -> Result<Option<String>, DbError>;
+    /// Forgets every previous slug of the post at `slug`.
+    async fn delete_slug_history(&self, slug: &str) -> Result<u64, DbError>;
+}
@@ -0,0 +1,1589 @@
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{
+    any::{install_default_drivers, AnyArguments, AnyPoolOptions, AnyRow},
+    migrate::MigrateDatabase,
+    query::Query,
+    Any, AnyPool, Row, Transaction,
+};
+
+use super::{
+    comment::CommentDb,
+    post::PostDb,
+    revision::RevisionDb,
+    series::SeriesDb,
+    settings::SettingsDb,
+    slug::SlugDb,
+    trash::TrashDb,
+    user::{ApiTokenDb, PasswordResetDb, TwoFactorDb, UnconfirmedUserDb, UserDb},
+    DBHandler, DbError, EXPIRE_AFTER_SECS, MAX_SEARCH_RESULTS, PASSWORD_RESET_EXPIRE_AFTER_SECS,
+    UNCONFIRMED_USER_RETENTION_SECS,
+};
+use crate::{
+    models::{
+        ApiTokenModel, PageCursor, PasswordReset, PostCursor, PostsQueryParams, TwoFactor,
+        UnconfirmedUser, User,
+    },
+    utils::rank_posts,
+};
+use common::{
+    Comment, CommentStatus, Post, PostRevision, PostSummary, Role, Series, SiteSettings, TagCount,
+    TrashedPost,
+};
+
+/// Database handler for SQLite and PostgreSQL, the driver is picked from the
+/// scheme of the connection URL. Queries stick to the SQL both understand,
+/// which is why booleans are stored as `0`/`1` and timestamps as milliseconds.
+#[derive(Clone)]
+pub struct SqlDBHandler {
+    pool: AnyPool,
+}
+
+impl SqlDBHandler {
+    pub async fn new(database_url: &str) -> Result<Self, Box<dyn Error>> {
+        install_default_drivers();
+
+        if !Any::database_exists(database_url).await? {
+            Any::create_database(database_url).await?;
+        }
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        // Posts saved before previews were stored get theirs now
+        for table in ["posts", "trashed_posts"] {
+            let rows = sqlx::query(&format!("SELECT * FROM {} WHERE preview IS NULL", table))
+                .fetch_all(&pool)
+                .await?;
+            for row in rows.iter() {
+                let post = post_from_row(row)?;
+                sqlx::query(&format!(
+                    "UPDATE {} SET preview = $1 WHERE slug = $2",
+                    table
+                ))
+                .bind(post.preview())
+                .bind(&post.slug)
+                .execute(&pool)
+                .await?;
+            }
+        }
+
+        Ok(Self { pool })
+    }
+
+    /// Mimics the TTL indexes created by `MongoDBHandler::new`.
+    async fn remove_expired(&self) -> Result<(), DbError> {
+        let threshold = expiration_threshold();
+        let retention_threshold = (Utc::now()
+            - Duration::seconds(UNCONFIRMED_USER_RETENTION_SECS as i64))
+        .timestamp_millis();
+
+        sqlx::query("DELETE FROM unconfirmed_users WHERE created_at <= $1")
+            .bind(retention_threshold)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM temp_files WHERE created_at <= $1")
+            .bind(threshold)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM password_resets WHERE created_at <= $1")
+            .bind(password_reset_expiration_threshold())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl SqlDBHandler {
+    /// Fills in the parts of the series stored in `row`.
+    async fn series_from_row(&self, row: &AnyRow) -> Result<Series, DbError> {
+        let slug: String = row.try_get("slug")?;
+        let posts = sqlx::query(
+            "SELECT post_slug FROM series_posts WHERE series_slug = $1 ORDER BY position",
+        )
+        .bind(&slug)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| row.try_get("post_slug"))
+        .collect::<Result<_, _>>()?;
+
+        Ok(Series {
+            slug,
+            title: row.try_get("title")?,
+            description: row.try_get("description")?,
+            posts,
+        })
+    }
+
+    /// Fills in the scopes of the API token stored in `row`. Scopes the app
+    /// doesn't know about are left out, so they grant nothing.
+    async fn api_token_from_row(&self, row: &AnyRow) -> Result<ApiTokenModel, DbError> {
+        let id: String = row.try_get("id")?;
+        let scopes = sqlx::query("SELECT scope FROM api_token_scopes WHERE token_id = $1")
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<String, _>("scope"))
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|scope| scope.parse().ok())
+            .collect();
+
+        Ok(ApiTokenModel {
+            id,
+            username: row.try_get("username")?,
+            name: row.try_get("name")?,
+            token_hash: row.try_get("token_hash")?,
+            scopes,
+            created_at: timestamp_from_millis(row.try_get("created_at")?),
+            expires_at: row
+                .try_get::<Option<i64>, _>("expires_at")?
+                .map(timestamp_from_millis),
+            last_used_at: row
+                .try_get::<Option<i64>, _>("last_used_at")?
+                .map(timestamp_from_millis),
+        })
+    }
+}
+
+async fn insert_series_posts(
+    tx: &mut Transaction<'_, Any>,
+    series: &Series,
+) -> Result<(), DbError> {
+    for (position, post_slug) in series.posts.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO series_posts (series_slug, position, post_slug) VALUES ($1, $2, $3)",
+        )
+        .bind(&series.slug)
+        .bind(position as i64)
+        .bind(post_slug)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn expiration_threshold() -> i64 {
+    (Utc::now() - Duration::seconds(EXPIRE_AFTER_SECS as i64)).timestamp_millis()
+}
+
+fn password_reset_expiration_threshold() -> i64 {
+    (Utc::now() - Duration::seconds(PASSWORD_RESET_EXPIRE_AFTER_SECS as i64)).timestamp_millis()
+}
+
+fn timestamp_from_millis(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+/// Roles the app doesn't know about grant nothing, same as when
+/// deserializing them.
+fn role_from_row(row: &AnyRow) -> Result<Role, sqlx::Error> {
+    Ok(row
+        .try_get::<String, _>("role")?
+        .parse()
+        .unwrap_or_default())
+}
+
+fn user_from_row(row: &AnyRow) -> Result<User, sqlx::Error> {
+    Ok(User {
+        id: None,
+        username: row.try_get("username")?,
+        email: row.try_get("email")?,
+        password: row.try_get("password")?,
+        role: role_from_row(row)?,
+        password_changed_at: row
+            .try_get::<Option<i64>, _>("password_changed_at")?
+            .map(timestamp_from_millis),
+        token_scopes: None,
+    })
+}
+
+fn unconfirmed_user_from_row(row: &AnyRow) -> Result<UnconfirmedUser, sqlx::Error> {
+    Ok(UnconfirmedUser {
+        confirmation_token: row.try_get("confirmation_token")?,
+        host: row.try_get("host")?,
+        created_at: timestamp_from_millis(row.try_get("created_at")?),
+        confirmed: row.try_get::<i64, _>("confirmed")? != 0,
+        username: row.try_get("username")?,
+        email: row.try_get("email")?,
+        password: row.try_get("password")?,
+        role: role_from_row(row)?,
+    })
+}
+
+fn post_from_row(row: &AnyRow) -> Result<Post, sqlx::Error> {
+    Ok(Post {
+        slug: row.try_get("slug")?,
+        title: row.try_get("title")?,
+        content: row.try_get("content")?,
+        summary: row.try_get("summary")?,
+        author: row.try_get("author")?,
+        published_at: timestamp_from_millis(row.try_get("published_at")?),
+        public: row.try_get::<i64, _>("is_public")? != 0,
+        tags: tags_from_column(&row.try_get::<String, _>("tags")?),
+    })
+}
+
+fn post_summary_from_row(row: &AnyRow) -> Result<PostSummary, sqlx::Error> {
+    Ok(PostSummary {
+        slug: row.try_get("slug")?,
+        title: row.try_get("title")?,
+        summary: row
+            .try_get::<Option<String>, _>("preview")?
+            .unwrap_or_default(),
+        author: row.try_get("author")?,
+        published_at: timestamp_from_millis(row.try_get("published_at")?),
+        public: row.try_get::<i64, _>("is_public")? != 0,
+        tags: tags_from_column(&row.try_get::<String, _>("tags")?),
+    })
+}
+
+fn trashed_post_from_row(row: &AnyRow) -> Result<TrashedPost, sqlx::Error> {
+    Ok(TrashedPost {
+        post: post_from_row(row)?,
+        deleted_at: timestamp_from_millis(row.try_get("deleted_at")?),
+        deleted_by: row.try_get("deleted_by")?,
+    })
+}
+
+/// The columns read by listings, everything but the content.
+const POST_SUMMARY_COLUMNS: &str = "slug, title, preview, author, published_at, is_public, tags";
+
+fn tags_to_column(tags: &[String]) -> String {
+    if tags.is_empty() {
+        String::new()
+    } else {
+        format!(",{},", tags.join(","))
+    }
+}
+
+fn tags_from_column(column: &str) -> Vec<String> {
+    column
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// `WHERE` clause for the posts matching `query`, numbering its parameters
+/// from `$first`. Bind them with `bind_posts_filter`.
+fn posts_filter(query: &PostsQueryParams, is_admin: bool, first: usize) -> String {
+    let mut conditions = Vec::new();
+    let mut param = first;
+
+    if !is_admin {
+        conditions.push(format!("is_public = 1 AND published_at <= ${}", param));
+        param += 1;
+    }
+    if query.tag.is_some() {
+        conditions.push(format!("tags LIKE ${} ESCAPE '\\'", param));
+    }
+
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+fn bind_posts_filter<'q>(
+    mut sql_query: Query<'q, Any, AnyArguments<'q>>,
+    query: &PostsQueryParams,
+    is_admin: bool,
+) -> Query<'q, Any, AnyArguments<'q>> {
+    if !is_admin {
+        sql_query = sql_query.bind(Utc::now().timestamp_millis());
+    }
+    if let Some(ref tag) = query.tag {
+        sql_query = sql_query.bind(like_pattern(&format!(",{},", tag)));
+    }
+    sql_query
+}
+
+/// `%term%` with the LIKE wildcards in `term` escaped.
+fn like_pattern(term: &str) -> String {
+    format!(
+        "%{}%",
+        term.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
+fn status_to_column(status: CommentStatus) -> &'static str {
+    match status {
+        CommentStatus::Pending => "pending",
+        CommentStatus::Approved => "approved",
+        CommentStatus::Rejected => "rejected",
+    }
+}
+
+fn status_from_column(column: &str) -> Result<CommentStatus, sqlx::Error> {
+    match column {
+        "pending" => Ok(CommentStatus::Pending),
+        "approved" => Ok(CommentStatus::Approved),
+        "rejected" => Ok(CommentStatus::Rejected),
+        _ => Err(sqlx::Error::Decode(
+            format!("unknown comment status {:?}", column).into(),
+        )),
+    }
+}
+
+fn comment_from_row(row: &AnyRow) -> Result<Comment, sqlx::Error> {
+    Ok(Comment {
+        id: row.try_get("id")?,
+        post_slug: row.try_get("post_slug")?,
+        parent_id: row.try_get("parent_id")?,
+        author: row.try_get("author")?,
+        content: row.try_get("content")?,
+        created_at: timestamp_from_millis(row.try_get("created_at")?),
+        edited_at: row
+            .try_get::<Option<i64>, _>("edited_at")?
+            .map(timestamp_from_millis),
+        status: status_from_column(row.try_get("status")?)?,
+    })
+}
+
+fn revision_from_row(row: &AnyRow) -> Result<PostRevision, sqlx::Error> {
+    Ok(PostRevision {
+        slug: row.try_get("slug")?,
+        number: row.try_get::<i64, _>("number")? as u64,
+        author: row.try_get("author")?,
+        created_at: timestamp_from_millis(row.try_get("created_at")?),
+        title: row.try_get("title")?,
+        content: row.try_get("content")?,
+        summary: row.try_get("summary")?,
+        public: row.try_get::<i64, _>("is_public")? != 0,
+        published_at: row
+            .try_get::<Option<i64>, _>("published_at")?
+            .map(timestamp_from_millis),
+        tags: row
+            .try_get::<Option<String>, _>("tags")?
+            .map(|tags| tags_from_column(&tags)),
+    })
+}
+
+impl DBHandler for SqlDBHandler {}
+
+impl UserDb for SqlDBHandler {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, DbError> {
+        let row = sqlx::query("SELECT * FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(user_from_row).transpose()?)
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DbError> {
+        let row = sqlx::query("SELECT * FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(user_from_row).transpose()?)
+    }
+
+    async fn insert_user(&self, user: &User) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO users (username, email, password, role) VALUES ($1, $2, $3, $4)")
+            .bind(&user.username)
+            .bind(&user.email)
+            .bind(&user.password)
+            .bind(user.role.as_str())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>, DbError> {
+        let rows = sqlx::query("SELECT * FROM users ORDER BY username")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(user_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn update_user_role(&self, username: &str, role: Role) -> Result<u64, DbError> {
+        let result = sqlx::query("UPDATE users SET role = $1 WHERE username = $2")
+            .bind(role.as_str())
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn update_user_password(&self, username: &str, password: &str) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "UPDATE users SET password = $1, password_changed_at = $2 WHERE username = $3",
+        )
+        .bind(password)
+        .bind(Utc::now().timestamp_millis())
+        .bind(username)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl PasswordResetDb for SqlDBHandler {
+    async fn insert_password_reset(&self, reset: &PasswordReset) -> Result<(), DbError> {
+        self.remove_expired().await?;
+
+        sqlx::query(
+            "INSERT INTO password_resets (token_hash, username, created_at) VALUES ($1, $2, $3)",
+        )
+        .bind(&reset.token_hash)
+        .bind(&reset.username)
+        .bind(reset.created_at.timestamp_millis())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn take_password_reset(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PasswordReset>, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(row) =
+            sqlx::query("SELECT * FROM password_resets WHERE token_hash = $1 AND created_at > $2")
+                .bind(token_hash)
+                .bind(password_reset_expiration_threshold())
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+        let reset = PasswordReset {
+            token_hash: row.try_get("token_hash")?,
+            username: row.try_get("username")?,
+            created_at: timestamp_from_millis(row.try_get("created_at")?),
+        };
+
+        // Whoever deletes the token first is the one using it
+        let result = sqlx::query("DELETE FROM password_resets WHERE token_hash = $1")
+            .bind(token_hash)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        sqlx::query("DELETE FROM password_resets WHERE username = $1")
+            .bind(&reset.username)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(reset))
+    }
+}
+
+impl TwoFactorDb for SqlDBHandler {
+    async fn find_two_factor(&self, username: &str) -> Result<Option<TwoFactor>, DbError> {
+        let Some(row) = sqlx::query("SELECT * FROM two_factor WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let recovery_codes =
+            sqlx::query("SELECT code_hash FROM two_factor_recovery_codes WHERE username = $1")
+                .bind(username)
+                .fetch_all(&self.pool)
+                .await?
+                .iter()
+                .map(|row| row.try_get("code_hash"))
+                .collect::<Result<_, _>>()?;
+
+        Ok(Some(TwoFactor {
+            username: row.try_get("username")?,
+            secret: row.try_get("secret")?,
+            enabled: row.try_get::<i64, _>("enabled")? != 0,
+            recovery_codes,
+            last_used_step: row.try_get("last_used_step")?,
+        }))
+    }
+
+    async fn save_two_factor(&self, two_factor: &TwoFactor) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM two_factor WHERE username = $1")
+            .bind(&two_factor.username)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM two_factor_recovery_codes WHERE username = $1")
+            .bind(&two_factor.username)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO two_factor (username, secret, enabled, last_used_step) \
+            VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&two_factor.username)
+        .bind(&two_factor.secret)
+        .bind(two_factor.enabled as i64)
+        .bind(two_factor.last_used_step)
+        .execute(&mut *tx)
+        .await?;
+        for code_hash in &two_factor.recovery_codes {
+            sqlx::query(
+                "INSERT INTO two_factor_recovery_codes (username, code_hash) VALUES ($1, $2)",
+            )
+            .bind(&two_factor.username)
+            .bind(code_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_two_factor(&self, username: &str) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM two_factor_recovery_codes WHERE username = $1")
+            .bind(username)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM two_factor WHERE username = $1")
+            .bind(username)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn use_totp_step(&self, username: &str, step: i64) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "UPDATE two_factor SET last_used_step = $1 WHERE username = $2 AND enabled = 1 \
+            AND (last_used_step IS NULL OR last_used_step < $1)",
+        )
+        .bind(step)
+        .bind(username)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "DELETE FROM two_factor_recovery_codes WHERE username = $1 AND code_hash = $2 \
+            AND username IN (SELECT username FROM two_factor WHERE enabled = 1)",
+        )
+        .bind(username)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl ApiTokenDb for SqlDBHandler {
+    async fn insert_api_token(&self, token: &ApiTokenModel) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO api_tokens \
+            (id, username, name, token_hash, created_at, expires_at, last_used_at) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&token.id)
+        .bind(&token.username)
+        .bind(&token.name)
+        .bind(&token.token_hash)
+        .bind(token.created_at.timestamp_millis())
+        .bind(token.expires_at.map(|at| at.timestamp_millis()))
+        .bind(token.last_used_at.map(|at| at.timestamp_millis()))
+        .execute(&mut *tx)
+        .await?;
+        for scope in &token.scopes {
+            sqlx::query("INSERT INTO api_token_scopes (token_id, scope) VALUES ($1, $2)")
+                .bind(&token.id)
+                .bind(scope.as_str())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_api_tokens(&self, username: &str) -> Result<Vec<ApiTokenModel>, DbError> {
+        let rows = sqlx::query("SELECT * FROM api_tokens WHERE username = $1 ORDER BY created_at")
+            .bind(username)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut tokens = Vec::with_capacity(rows.len());
+        for row in &rows {
+            tokens.push(self.api_token_from_row(row).await?);
+        }
+        Ok(tokens)
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiTokenModel>, DbError> {
+        match sqlx::query("SELECT * FROM api_tokens WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?
+        {
+            Some(row) => Ok(Some(self.api_token_from_row(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn update_api_token_last_used(
+        &self,
+        id: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let result = sqlx::query("UPDATE api_tokens SET last_used_at = $1 WHERE id = $2")
+            .bind(last_used_at.timestamp_millis())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_api_token(&self, username: &str, id: &str) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM api_tokens WHERE username = $1 AND id = $2")
+            .bind(username)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() > 0 {
+            sqlx::query("DELETE FROM api_token_scopes WHERE token_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+}
+
+impl UnconfirmedUserDb for SqlDBHandler {
+    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), DbError> {
+        self.remove_expired().await?;
+
+        sqlx::query(
+            "INSERT INTO unconfirmed_users \
+            (confirmation_token, host, created_at, confirmed, username, email, password, role) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(&user.confirmation_token)
+        .bind(&user.host)
+        .bind(user.created_at.timestamp_millis())
+        .bind(user.confirmed as i64)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password)
+        .bind(user.role.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_unconfirmed_users(&self) -> Result<Vec<UnconfirmedUser>, DbError> {
+        let rows = sqlx::query(
+            "SELECT * FROM unconfirmed_users WHERE confirmed = 0 AND created_at > $1 \
+            ORDER BY created_at",
+        )
+        .bind(expiration_threshold())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(unconfirmed_user_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn confirm_user(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        self.remove_expired().await?;
+
+        // Only one concurrent confirmation can flip the flag
+        let result = sqlx::query(
+            "UPDATE unconfirmed_users SET confirmed = 1 \
+            WHERE confirmation_token = $1 AND confirmed = 0 AND created_at > $2",
+        )
+        .bind(confirmation_token)
+        .bind(expiration_threshold())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        self.find_confirmation(confirmation_token).await
+    }
+
+    async fn renew_confirmation(
+        &self,
+        confirmation_token: &str,
+        new_token: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "UPDATE unconfirmed_users SET confirmation_token = $1, created_at = $2 \
+            WHERE confirmation_token = $3 AND confirmed = 0",
+        )
+        .bind(new_token)
+        .bind(created_at.timestamp_millis())
+        .bind(confirmation_token)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn find_confirmation(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        let row = sqlx::query("SELECT * FROM unconfirmed_users WHERE confirmation_token = $1")
+            .bind(confirmation_token)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(unconfirmed_user_from_row).transpose()?)
+    }
+
+    async fn find_latest_unconfirmed_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        let row = sqlx::query(
+            "SELECT * FROM unconfirmed_users WHERE email = $1 AND confirmed = 0 \
+            ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(unconfirmed_user_from_row).transpose()?)
+    }
+
+    async fn find_unconfirmed_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        let row =
+            sqlx::query("SELECT * FROM unconfirmed_users WHERE username = $1 AND created_at > $2")
+                .bind(username)
+                .bind(expiration_threshold())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.as_ref().map(unconfirmed_user_from_row).transpose()?)
+    }
+
+    async fn find_unconfirmed_user_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError> {
+        let row =
+            sqlx::query("SELECT * FROM unconfirmed_users WHERE email = $1 AND created_at > $2")
+                .bind(email)
+                .bind(expiration_threshold())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.as_ref().map(unconfirmed_user_from_row).transpose()?)
+    }
+}
+
+impl PostDb for SqlDBHandler {
+    async fn create_post(&self, post: &Post) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO posts \
+            (slug, title, content, summary, author, published_at, is_public, tags, preview) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(&post.slug)
+        .bind(&post.title)
+        .bind(&post.content)
+        .bind(&post.summary)
+        .bind(&post.author)
+        .bind(post.published_at.timestamp_millis())
+        .bind(post.public as i64)
+        .bind(tags_to_column(&post.tags))
+        .bind(post.preview())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_post(&self, post: &Post) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "UPDATE posts SET content = $1, title = $2, summary = $3, is_public = $4, \
+            published_at = $5, tags = $6, preview = $7 WHERE slug = $8",
+        )
+        .bind(&post.content)
+        .bind(&post.title)
+        .bind(&post.summary)
+        .bind(post.public as i64)
+        .bind(post.published_at.timestamp_millis())
+        .bind(tags_to_column(&post.tags))
+        .bind(post.preview())
+        .bind(&post.slug)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn set_post_author(&self, slug: &str, author: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("UPDATE posts SET author = $1 WHERE slug = $2")
+            .bind(author)
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_post(&self, slug: &str, is_admin: bool) -> Result<Option<Post>, DbError> {
+        let sql = if is_admin {
+            "SELECT * FROM posts WHERE slug = $1"
+        } else {
+            "SELECT * FROM posts WHERE slug = $1 AND is_public = 1 AND published_at <= $2"
+        };
+
+        let mut query = sqlx::query(sql).bind(slug);
+        if !is_admin {
+            query = query.bind(Utc::now().timestamp_millis());
+        }
+        let row = query.fetch_optional(&self.pool).await?;
+
+        Ok(row.as_ref().map(post_from_row).transpose()?)
+    }
+
+    async fn get_posts(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<Vec<PostSummary>, DbError> {
+        let page = query.page.unwrap_or(1);
+        let per_page = query.per_page.unwrap_or(10);
+        let limit = per_page as i64;
+        let offset =
+            i64::try_from(page.saturating_sub(1).saturating_mul(per_page)).unwrap_or(i64::MAX);
+
+        // Column names can't be bound, so only known ones make it into the query
+        let sort_option = match query.sort_by.as_deref() {
+            Some(sort_by) => {
+                let sort_order = if query.sort_order.as_deref() == Some("desc") {
+                    "DESC"
+                } else {
+                    "ASC"
+                };
+
+                match sort_by {
+                    "slug" | "title" | "author" | "published_at" => {
+                        format!("ORDER BY {} {}", sort_by, sort_order)
+                    }
+                    "public" => format!("ORDER BY is_public {}", sort_order),
+                    _ => String::new(),
+                }
+            }
+            None => String::from("ORDER BY published_at DESC"),
+        };
+
+        let sql = format!(
+            "SELECT {} FROM posts {} {} LIMIT $1 OFFSET $2",
+            POST_SUMMARY_COLUMNS,
+            posts_filter(query, is_admin, 3),
+            sort_option
+        );
+        let rows = bind_posts_filter(sqlx::query(&sql).bind(limit).bind(offset), query, is_admin)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(post_summary_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_posts_page(
+        &self,
+        query: &PostsQueryParams,
+        cursor: &PageCursor,
+        limit: u64,
+        is_admin: bool,
+    ) -> Result<Vec<(PostSummary, PostCursor)>, DbError> {
+        // The slug stands in for MongoDB's `_id` to order posts published together
+        let (position, sort_order) = match cursor {
+            PageCursor::First => (None, "DESC"),
+            PageCursor::After(after) => (Some((after, "<")), "DESC"),
+            PageCursor::Before(before) => (Some((before, ">")), "ASC"),
+        };
+
+        let sql = match position {
+            Some((_, operator)) => {
+                let filter = posts_filter(query, is_admin, 4);
+                let condition = format!(
+                    "(published_at {op} $1 OR (published_at = $1 AND slug {op} $2))",
+                    op = operator
+                );
+                format!(
+                    "SELECT {} FROM posts {} {} ORDER BY published_at {order}, slug {order} \
+                    LIMIT $3",
+                    POST_SUMMARY_COLUMNS,
+                    if filter.is_empty() { "WHERE" } else { &filter },
+                    if filter.is_empty() {
+                        condition
+                    } else {
+                        format!("AND {}", condition)
+                    },
+                    order = sort_order
+                )
+            }
+            None => format!(
+                "SELECT {} FROM posts {} ORDER BY published_at DESC, slug DESC LIMIT $1",
+                POST_SUMMARY_COLUMNS,
+                posts_filter(query, is_admin, 2)
+            ),
+        };
+
+        let mut sql_query = sqlx::query(&sql);
+        if let Some((cursor, _)) = position {
+            sql_query = sql_query
+                .bind(cursor.published_at.timestamp_millis())
+                .bind(&cursor.id);
+        }
+        let rows = bind_posts_filter(sql_query.bind(limit as i64), query, is_admin)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut posts = rows
+            .iter()
+            .map(|row| {
+                let post = post_summary_from_row(row)?;
+                let cursor = PostCursor {
+                    published_at: post.published_at,
+                    id: post.slug.clone(),
+                };
+                Ok((post, cursor))
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+        if sort_order == "ASC" {
+            posts.reverse();
+        }
+
+        Ok(posts)
+    }
+
+    async fn calculate_total_pages(
+        &self,
+        query: &PostsQueryParams,
+        is_admin: bool,
+    ) -> Result<u64, DbError> {
+        let per_page = query.per_page.unwrap_or(10);
+
+        let sql = format!(
+            "SELECT COUNT(*) AS total FROM posts {}",
+            posts_filter(query, is_admin, 1)
+        );
+        let row = bind_posts_filter(sqlx::query(&sql), query, is_admin)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let total_posts: i64 = row.try_get("total")?;
+        Ok((total_posts as f64 / per_page as f64).ceil() as u64)
+    }
+
+    async fn get_tags(&self, is_admin: bool) -> Result<Vec<TagCount>, DbError> {
+        let query = PostsQueryParams::default();
+        let sql = format!(
+            "SELECT tags FROM posts {}",
+            posts_filter(&query, is_admin, 1)
+        );
+        let rows = bind_posts_filter(sqlx::query(&sql), &query, is_admin)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut tags: Vec<TagCount> = Vec::new();
+        for row in rows.iter() {
+            for tag in tags_from_column(&row.try_get::<String, _>("tags")?) {
+                match tags.iter_mut().find(|count| count.tag == tag) {
+                    Some(count) => count.count += 1,
+                    None => tags.push(TagCount { tag, count: 1 }),
+                }
+            }
+        }
+
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        Ok(tags)
+    }
+
+    async fn search_posts(&self, terms: &[String], is_admin: bool) -> Result<Vec<Post>, DbError> {
+        if terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // LIKE narrows down the candidates, the ranking happens in Rust so it
+        // is the same for both databases. Only so many candidates are ranked,
+        // the ones matching in the title first as those rank highest.
+        let title_conditions: Vec<String> = (1..=terms.len())
+            .map(|param| format!("LOWER(title) LIKE ${} ESCAPE '\\'", param))
+            .collect();
+        let conditions: Vec<String> = (1..=terms.len())
+            .map(|param| {
+                format!(
+                    "LOWER(COALESCE(summary, '')) LIKE ${0} ESCAPE '\\' \
+                    OR LOWER(content) LIKE ${0} ESCAPE '\\'",
+                    param
+                )
+            })
+            .chain(title_conditions.iter().cloned())
+            .collect();
+
+        let query = PostsQueryParams::default();
+        let filter = posts_filter(&query, is_admin, terms.len() + 1);
+        let sql = format!(
+            "SELECT slug, title, content, summary, author, published_at, is_public, tags \
+            FROM posts {} {} ({}) \
+            ORDER BY CASE WHEN {} THEN 0 ELSE 1 END, published_at DESC LIMIT {}",
+            filter,
+            if filter.is_empty() { "WHERE" } else { "AND" },
+            conditions.join(" OR "),
+            title_conditions.join(" OR "),
+            MAX_SEARCH_RESULTS
+        );
+
+        let mut sql_query = sqlx::query(&sql);
+        for term in terms {
+            sql_query = sql_query.bind(like_pattern(term));
+        }
+        let rows = bind_posts_filter(sql_query, &query, is_admin)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let posts = rows.iter().map(post_from_row).collect::<Result<_, _>>()?;
+        Ok(rank_posts(posts, terms, MAX_SEARCH_RESULTS))
+    }
+
+    async fn create_temp_file(&self, path: &Path, filename: &str) -> Result<(), DbError> {
+        let Some(path_str) = path.to_str() else {
+            return Err(DbError::other("temporary file path is not valid UTF-8"));
+        };
+
+        self.remove_expired().await?;
+
+        sqlx::query("INSERT INTO temp_files (path, filename, created_at) VALUES ($1, $2, $3)")
+            .bind(path_str)
+            .bind(filename)
+            .bind(Utc::now().timestamp_millis())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl RevisionDb for SqlDBHandler {
+    async fn create_revision(&self, revision: &PostRevision) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO post_revisions \
+            (slug, number, author, created_at, title, content, summary, is_public, \
+            published_at, tags) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(&revision.slug)
+        .bind(revision.number as i64)
+        .bind(&revision.author)
+        .bind(revision.created_at.timestamp_millis())
+        .bind(&revision.title)
+        .bind(&revision.content)
+        .bind(&revision.summary)
+        .bind(revision.public as i64)
+        .bind(revision.published_at.map(|at| at.timestamp_millis()))
+        .bind(revision.tags.as_deref().map(tags_to_column))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_revisions(&self, slug: &str) -> Result<Vec<PostRevision>, DbError> {
+        let rows = sqlx::query("SELECT * FROM post_revisions WHERE slug = $1 ORDER BY number")
+            .bind(slug)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(revision_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_revision(&self, slug: &str, number: u64) -> Result<Option<PostRevision>, DbError> {
+        let row = sqlx::query("SELECT * FROM post_revisions WHERE slug = $1 AND number = $2")
+            .bind(slug)
+            .bind(number as i64)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(revision_from_row).transpose()?)
+    }
+
+    async fn delete_revisions(&self, slug: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM post_revisions WHERE slug = $1")
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl SeriesDb for SqlDBHandler {
+    async fn create_series(&self, series: &Series) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("INSERT INTO series (slug, title, description) VALUES ($1, $2, $3)")
+            .bind(&series.slug)
+            .bind(&series.title)
+            .bind(&series.description)
+            .execute(&mut *tx)
+            .await?;
+        insert_series_posts(&mut tx, series).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_series(&self, series: &Series) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("UPDATE series SET title = $1, description = $2 WHERE slug = $3")
+            .bind(&series.title)
+            .bind(&series.description)
+            .bind(&series.slug)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            sqlx::query("DELETE FROM series_posts WHERE series_slug = $1")
+                .bind(&series.slug)
+                .execute(&mut *tx)
+                .await?;
+            insert_series_posts(&mut tx, series).await?;
+        }
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_series(&self, slug: &str) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM series_posts WHERE series_slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM series WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_series(&self, slug: &str) -> Result<Option<Series>, DbError> {
+        let row = sqlx::query("SELECT * FROM series WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.series_from_row(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_all_series(&self) -> Result<Vec<Series>, DbError> {
+        let rows = sqlx::query("SELECT * FROM series ORDER BY title")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut series = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            series.push(self.series_from_row(row).await?);
+        }
+
+        Ok(series)
+    }
+
+    async fn find_series_by_post(&self, post_slug: &str) -> Result<Option<Series>, DbError> {
+        let row = sqlx::query(
+            "SELECT series.* FROM series \
+            JOIN series_posts ON series_posts.series_slug = series.slug \
+            WHERE series_posts.post_slug = $1",
+        )
+        .bind(post_slug)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.series_from_row(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn remove_post_from_series(&self, post_slug: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM series_posts WHERE post_slug = $1")
+            .bind(post_slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl CommentDb for SqlDBHandler {
+    async fn create_comment(&self, comment: &Comment) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO comments \
+            (id, post_slug, parent_id, author, content, created_at, edited_at, status) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(&comment.id)
+        .bind(&comment.post_slug)
+        .bind(&comment.parent_id)
+        .bind(&comment.author)
+        .bind(&comment.content)
+        .bind(comment.created_at.timestamp_millis())
+        .bind(
+            comment
+                .edited_at
+                .map(|edited_at| edited_at.timestamp_millis()),
+        )
+        .bind(status_to_column(comment.status))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_comment(&self, id: &str) -> Result<Option<Comment>, DbError> {
+        let row = sqlx::query("SELECT * FROM comments WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(comment_from_row).transpose()?)
+    }
+
+    async fn get_comments(&self, post_slug: &str) -> Result<Vec<Comment>, DbError> {
+        let rows = sqlx::query("SELECT * FROM comments WHERE post_slug = $1 ORDER BY created_at")
+            .bind(post_slug)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(comment_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_comments_by_status(&self, status: CommentStatus) -> Result<Vec<Comment>, DbError> {
+        let rows = sqlx::query("SELECT * FROM comments WHERE status = $1 ORDER BY created_at")
+            .bind(status_to_column(status))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(comment_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn update_comment(
+        &self,
+        id: &str,
+        content: &str,
+        edited_at: DateTime<Utc>,
+        status: CommentStatus,
+    ) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "UPDATE comments SET content = $1, edited_at = $2, status = $3 WHERE id = $4",
+        )
+        .bind(content)
+        .bind(edited_at.timestamp_millis())
+        .bind(status_to_column(status))
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn set_comment_status(&self, id: &str, status: CommentStatus) -> Result<u64, DbError> {
+        let result = sqlx::query("UPDATE comments SET status = $1 WHERE id = $2 AND status <> $1")
+            .bind(status_to_column(status))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_comment(&self, id: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM comments WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_post_comments(&self, post_slug: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM comments WHERE post_slug = $1")
+            .bind(post_slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl SlugDb for SqlDBHandler {
+    async fn rename_post(&self, old_slug: &str, new_slug: &str) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        if sqlx::query("SELECT slug FROM posts WHERE slug = $1")
+            .bind(new_slug)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some()
+        {
+            return Err(DbError::Conflict("post already exists".into()));
+        }
+
+        let result = sqlx::query("UPDATE posts SET slug = $1 WHERE slug = $2")
+            .bind(new_slug)
+            .bind(old_slug)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(0);
+        }
+
+        for sql in [
+            "UPDATE post_revisions SET slug = $1 WHERE slug = $2",
+            "UPDATE series_posts SET post_slug = $1 WHERE post_slug = $2",
+            "UPDATE comments SET post_slug = $1 WHERE post_slug = $2",
+            // Older slugs point straight to the new one
+            "UPDATE slug_history SET slug = $1 WHERE slug = $2",
+        ] {
+            sqlx::query(sql)
+                .bind(new_slug)
+                .bind(old_slug)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // A slug that is taken back stops redirecting
+        sqlx::query("DELETE FROM slug_history WHERE old_slug = $1")
+            .bind(new_slug)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO slug_history (old_slug, slug) VALUES ($1, $2)")
+            .bind(old_slug)
+            .bind(new_slug)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn add_slug_redirect(&self, old_slug: &str, slug: &str) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO slug_history (old_slug, slug) VALUES ($1, $2)")
+            .bind(old_slug)
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_slug_redirect(&self, old_slug: &str) -> Result<Option<String>, DbError> {
+        let row = sqlx::query("SELECT slug FROM slug_history WHERE old_slug = $1")
+            .bind(old_slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.try_get("slug")).transpose()?)
+    }
+
+    async fn delete_slug_history(&self, slug: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM slug_history WHERE slug = $1")
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl TrashDb for SqlDBHandler {
+    async fn trash_post(
+        &self,
+        slug: &str,
+        deleted_by: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO trashed_posts \
+            (slug, title, content, summary, author, published_at, is_public, tags, preview, \
+            deleted_at, deleted_by) \
+            SELECT slug, title, content, summary, author, published_at, is_public, tags, preview, \
+            $1, $2 FROM posts WHERE slug = $3",
+        )
+        .bind(deleted_at.timestamp_millis())
+        .bind(deleted_by)
+        .bind(slug)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM posts WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_trashed_posts(&self) -> Result<Vec<TrashedPost>, DbError> {
+        let rows = sqlx::query("SELECT * FROM trashed_posts ORDER BY deleted_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(trashed_post_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_trashed_post(&self, slug: &str) -> Result<Option<TrashedPost>, DbError> {
+        let row = sqlx::query("SELECT * FROM trashed_posts WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(trashed_post_from_row).transpose()?)
+    }
+
+    async fn restore_post(&self, slug: &str) -> Result<u64, DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        if sqlx::query("SELECT slug FROM posts WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some()
+        {
+            return Err(DbError::Conflict("post already exists".into()));
+        }
+
+        sqlx::query(
+            "INSERT INTO posts \
+            (slug, title, content, summary, author, published_at, is_public, tags, preview) \
+            SELECT slug, title, content, summary, author, published_at, is_public, tags, preview \
+            FROM trashed_posts WHERE slug = $1",
+        )
+        .bind(slug)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM trashed_posts WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn purge_post(&self, slug: &str) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM trashed_posts WHERE slug = $1")
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_expired_trash(&self, threshold: DateTime<Utc>) -> Result<Vec<String>, DbError> {
+        let rows = sqlx::query("SELECT slug FROM trashed_posts WHERE deleted_at <= $1")
+            .bind(threshold.timestamp_millis())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.try_get("slug"))
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+impl SettingsDb for SqlDBHandler {
+    async fn get_settings(&self) -> Result<SiteSettings, DbError> {
+        let row = sqlx::query("SELECT require_two_factor FROM settings WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(match row {
+            Some(row) => SiteSettings {
+                require_two_factor: row.try_get::<i64, _>("require_two_factor")? != 0,
+            },
+            None => SiteSettings::default(),
+        })
+    }
+
+    async fn save_settings(&self, settings: &SiteSettings) -> Result<(), DbError> {
+        sqlx::query("UPDATE settings SET require_two_factor = $1 WHERE id = 1")
+            .bind(settings.require_two_factor as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
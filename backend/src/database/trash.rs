@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+
+use super::DbError;
+use common::TrashedPost;
+
+pub trait TrashDb {
+    /// Moves the post at `slug` out of the posts and into the trash.
+    async fn trash_post(
+        &self,
+        slug: &str,
+        deleted_by: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, DbError>;
+    /// Every trashed post, most recently deleted first.
+    async fn get_trashed_posts(&self) -> Result<Vec<TrashedPost>, DbError>;
+    async fn get_trashed_post(&self, slug: &str) -> Result<Option<TrashedPost>, DbError>;
+    /// Moves the post at `slug` back from the trash to the posts.
+    async fn restore_post(&self, slug: &str) -> Result<u64, DbError>;
+    /// Drops the post at `slug` from the trash for good.
+    async fn purge_post(&self, slug: &str) -> Result<u64, DbError>;
+    /// Slugs of the posts trashed at or before `threshold`.
+    async fn get_expired_trash(&self, threshold: DateTime<Utc>) -> Result<Vec<String>, DbError>;
+}
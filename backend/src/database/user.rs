@@ -1,17 +1,96 @@
-use crate::models::{UnconfirmedUser, User};
+use chrono::{DateTime, Utc};
+
+use super::DbError;
+use crate::models::{ApiTokenModel, PasswordReset, TwoFactor, UnconfirmedUser, User};
+use common::Role;
 
 pub trait UserDb {
-    async fn find_user(&self, username: &str) -> Result<Option<User>, ()>;
-    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, ()>;
-    async fn insert_user(&self, user: &User) -> Result<(), ()>;
+    async fn find_user(&self, username: &str) -> Result<Option<User>, DbError>;
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DbError>;
+    async fn insert_user(&self, user: &User) -> Result<(), DbError>;
+    /// Every user, ordered by username.
+    async fn get_users(&self) -> Result<Vec<User>, DbError>;
+    async fn update_user_role(&self, username: &str, role: Role) -> Result<u64, DbError>;
+    /// Replaces the password hash of a user, logging out their sessions.
+    async fn update_user_password(&self, username: &str, password: &str) -> Result<u64, DbError>;
 }
 
+/// Unconfirmed users expire after `EXPIRE_AFTER_SECS` but are only removed
+/// after `UNCONFIRMED_USER_RETENTION_SECS`. Unless stated otherwise, methods
+/// only see the ones that haven't expired.
 pub trait UnconfirmedUserDb {
-    async fn find_unconfirmed_user(&self, username: &str) -> Result<Option<UnconfirmedUser>, ()>;
+    async fn find_unconfirmed_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError>;
     async fn find_unconfirmed_user_user_by_email(
         &self,
         email: &str,
-    ) -> Result<Option<UnconfirmedUser>, ()>;
-    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), ()>;
-    async fn confirm_user(&self, confirmation_token: &str) -> Result<Option<UnconfirmedUser>, ()>;
+    ) -> Result<Option<UnconfirmedUser>, DbError>;
+    /// The most recent user registered with `email` that hasn't confirmed
+    /// it yet, expired or not.
+    async fn find_latest_unconfirmed_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError>;
+    /// The user with `confirmation_token`, whether it expired or was
+    /// confirmed already.
+    async fn find_confirmation(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError>;
+    async fn insert_unconfirmed_user(&self, user: &UnconfirmedUser) -> Result<(), DbError>;
+    /// Users that haven't confirmed their email yet and whose confirmation
+    /// hasn't expired, oldest first.
+    async fn get_unconfirmed_users(&self) -> Result<Vec<UnconfirmedUser>, DbError>;
+    async fn confirm_user(
+        &self,
+        confirmation_token: &str,
+    ) -> Result<Option<UnconfirmedUser>, DbError>;
+    /// Replaces the token of a user that hasn't confirmed their email yet,
+    /// starting its lifetime over from `created_at`.
+    async fn renew_confirmation(
+        &self,
+        confirmation_token: &str,
+        new_token: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<u64, DbError>;
+}
+
+pub trait PasswordResetDb {
+    async fn insert_password_reset(&self, reset: &PasswordReset) -> Result<(), DbError>;
+    /// Removes the reset with `token_hash`, unless it expired, along with
+    /// every other reset of the same user, and returns it.
+    async fn take_password_reset(&self, token_hash: &str)
+        -> Result<Option<PasswordReset>, DbError>;
+}
+
+pub trait TwoFactorDb {
+    async fn find_two_factor(&self, username: &str) -> Result<Option<TwoFactor>, DbError>;
+    /// Inserts the second factor of `two_factor.username`, replacing the one
+    /// they had, recovery codes included.
+    async fn save_two_factor(&self, two_factor: &TwoFactor) -> Result<(), DbError>;
+    async fn delete_two_factor(&self, username: &str) -> Result<u64, DbError>;
+    /// Records a code for `step` as used on an enabled second factor. Returns
+    /// 0 when a code for that step or a later one was used already.
+    async fn use_totp_step(&self, username: &str, step: i64) -> Result<u64, DbError>;
+    /// Removes a recovery code from an enabled second factor. Returns 0 when
+    /// it isn't one of its codes.
+    async fn use_recovery_code(&self, username: &str, code_hash: &str) -> Result<u64, DbError>;
+}
+
+pub trait ApiTokenDb {
+    async fn insert_api_token(&self, token: &ApiTokenModel) -> Result<(), DbError>;
+    /// The tokens of `username`, expired ones included, oldest first.
+    async fn get_api_tokens(&self, username: &str) -> Result<Vec<ApiTokenModel>, DbError>;
+    /// The token with `token_hash`, whether it expired or not.
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiTokenModel>, DbError>;
+    async fn update_api_token_last_used(
+        &self,
+        id: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<u64, DbError>;
+    /// Revokes a token of `username`. Returns 0 when they have no token with
+    /// `id`.
+    async fn delete_api_token(&self, username: &str, id: &str) -> Result<u64, DbError>;
 }
@@ -0,0 +1,99 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+
+use crate::{
+    database::{DBHandler, DbError},
+    models::ApiTokenModel,
+    utils::{
+        generate_api_token, generate_api_token_id, hash_token, CurrentUser,
+        MAX_API_TOKEN_NAME_LENGTH,
+    },
+};
+use common::{ApiToken, ApiTokenCreated, ApiTokenList, CreateApiTokenRequest};
+
+pub async fn get_api_tokens<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    let tokens = db_handler
+        .get_api_tokens(&user.username)
+        .await?
+        .into_iter()
+        .map(ApiToken::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiTokenList {
+        tokens,
+        available_scopes: user.role.capabilities().to_vec(),
+    }))
+}
+
+/// Mints a token for the logged in user, answering with the token itself,
+/// which isn't shown again. Its scopes have to be granted by their role.
+pub async fn create_api_token<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    request: web::Json<CreateApiTokenRequest>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_API_TOKEN_NAME_LENGTH {
+        return Ok(HttpResponse::BadRequest().body("name"));
+    }
+    if !request.scopes.iter().all(|scope| user.role.can(*scope)) {
+        return Ok(HttpResponse::BadRequest().body("scopes"));
+    }
+    let now = Utc::now();
+    if request
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return Ok(HttpResponse::BadRequest().body("expires_at"));
+    }
+
+    let mut scopes = request.scopes.clone();
+    scopes.sort_by_key(|scope| scope.as_str());
+    scopes.dedup();
+
+    let token = generate_api_token();
+    let api_token = ApiTokenModel {
+        id: generate_api_token_id(),
+        username: user.username,
+        name: name.to_string(),
+        token_hash: hash_token(&token),
+        scopes,
+        created_at: now,
+        expires_at: request.expires_at,
+        last_used_at: None,
+    };
+    db_handler.insert_api_token(&api_token).await?;
+
+    Ok(HttpResponse::Ok().json(ApiTokenCreated {
+        token,
+        api_token: api_token.into(),
+    }))
+}
+
+/// Revokes a token of the logged in user, requests made with it fail from
+/// then on.
+pub async fn revoke_api_token<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match db_handler.delete_api_token(&user.username, &id).await? {
+        0 => Err(DbError::NotFound),
+        _ => Ok(HttpResponse::Ok().finish()),
+    }
+}
@@ -1,64 +1,84 @@
 use actix_identity::Identity;
-use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::Utc;
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
 
 use crate::{
-    database::DBHandler,
-    models::{UnconfirmedUser, UserConfirmation, UserLogin as UserLoginForm, UserRegistration},
-    services::email::Emailer,
-    utils::generate_random_alphanumeric_str,
+    database::{DBHandler, DbError},
+    models::{
+        EmailLinkRequest, PasswordReset, PasswordResetForm, UnconfirmedUser, User,
+        UserConfirmation, UserLogin as UserLoginForm, UserRegistration,
+    },
+    services::{
+        email::Emailer,
+        login_limiter::{LoginLimiter, ResetRequest},
+    },
+    utils::{
+        generate_random_alphanumeric_str, hash_recovery_code, hash_token, is_two_factor_required,
+        pending_login, start_pending_login, start_session, verify_code, NewUserRole, SiteUrl,
+    },
     Config,
 };
-use common::utils::*;
+use common::{utils::*, LoginChallenge, TwoFactorCodeRequest};
 
 pub async fn register_user<T: DBHandler>(
     db_handler: web::Data<T>,
-    config: web::Data<Config>,
+    new_user_role: web::Data<NewUserRole>,
     emailer: web::Data<Emailer>,
+    site_url: web::Data<SiteUrl>,
     user_info: web::Json<UserRegistration>,
-) -> impl Responder {
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
     if !is_valid_email(&user_info.email) {
-        return HttpResponse::BadRequest().body("email");
+        return Ok(HttpResponse::BadRequest().body("email"));
     }
 
     let email = normalize_email(&user_info.email);
 
     if !is_valid_username(&user_info.username) {
-        return HttpResponse::BadRequest().body("username");
+        return Ok(HttpResponse::BadRequest().body("username"));
     }
 
     if !is_valid_password(&user_info.password) {
-        return HttpResponse::BadRequest().body("password");
+        return Ok(HttpResponse::BadRequest().body("password"));
     }
 
-    if let Ok(Some(_)) = db_handler.find_user(&user_info.username).await {
-        return HttpResponse::Conflict().body("username");
+    if db_handler.find_user(&user_info.username).await?.is_some() {
+        return Ok(HttpResponse::Conflict().body("username"));
     }
-    if let Ok(Some(_)) = db_handler.find_user_by_email(&email).await {
-        return HttpResponse::Conflict().body("email");
+    if db_handler.find_user_by_email(&email).await?.is_some() {
+        return Ok(HttpResponse::Conflict().body("email"));
     }
-    if let Ok(Some(_)) = db_handler.find_unconfirmed_user(&user_info.username).await {
-        return HttpResponse::Conflict().body("username");
+    if db_handler
+        .find_unconfirmed_user(&user_info.username)
+        .await?
+        .is_some()
+    {
+        return Ok(HttpResponse::Conflict().body("username"));
     }
-    if let Ok(Some(_)) = db_handler.find_unconfirmed_user_user_by_email(&email).await {
-        return HttpResponse::Conflict().body("email");
+    if db_handler
+        .find_unconfirmed_user_user_by_email(&email)
+        .await?
+        .is_some()
+    {
+        return Ok(HttpResponse::Conflict().body("email"));
     }
 
     // Hash the password
     let hashed_password = match hash(&user_info.password, DEFAULT_COST) {
         Ok(hashed) => hashed,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
 
     let confirmation_token = generate_random_alphanumeric_str(32);
-    let host = match &user_info.host {
-        Some(x) => x.clone(),
-        _ => String::from("http://localhost"),
-    };
+    let host = site_url.base_url(&request).to_string();
 
     // Create user document & insert it into the database
-    if db_handler
+    db_handler
         .insert_unconfirmed_user(&UnconfirmedUser {
             confirmation_token: confirmation_token.clone(),
             host: host.clone(),
@@ -67,60 +87,447 @@ pub async fn register_user<T: DBHandler>(
             username: user_info.username.clone(),
             email: email.clone(),
             password: hashed_password.clone(),
-            role: config.NEW_USER_DEFAULT_ROLE.clone(),
+            role: new_user_role.0,
         })
-        .await
-        .is_ok()
-    {
-        let link = format!("{}/confirm/{}", host, confirmation_token);
-        if emailer.send_confirmation_email(&email, &link).await.is_ok() {
-            return HttpResponse::Ok().body("User created successfully");
+        .await?;
+
+    let link = format!("{}/confirm/{}", host, confirmation_token);
+    match emailer.send_confirmation_email(&email, &link).await {
+        Ok(_) => Ok(HttpResponse::Ok().body("User created successfully")),
+        Err(err) => {
+            log::error!("Error sending confirmation email: {}", err);
+            Ok(HttpResponse::InternalServerError().finish())
         }
     }
-
-    HttpResponse::InternalServerError().finish()
 }
 
 pub async fn confirm_user<T: DBHandler>(
     db_handler: web::Data<T>,
     user_confirmation: web::Json<UserConfirmation>,
-) -> impl Responder {
+) -> Result<HttpResponse, DbError> {
     // Create user document & insert it into the database
-    if let Ok(user_option) = db_handler
+    match db_handler
         .confirm_user(&user_confirmation.confirmation_token)
+        .await?
+    {
+        Some(user) => {
+            db_handler.insert_user(&user.into()).await?;
+            Ok(HttpResponse::Ok().body("Confirmation successful!"))
+        }
+        // Tell apart why the link doesn't work, so the user knows what to do
+        None => match db_handler
+            .find_confirmation(&user_confirmation.confirmation_token)
+            .await?
+        {
+            Some(user) if user.confirmed => Ok(HttpResponse::Conflict().body("used")),
+            Some(_) => Ok(HttpResponse::Gone().body("expired")),
+            None => Err(DbError::NotFound),
+        },
+    }
+}
+
+pub async fn resend_confirmation<T: DBHandler>(
+    db_handler: web::Data<T>,
+    emailer: web::Data<Emailer>,
+    site_url: web::Data<SiteUrl>,
+    request: web::Json<EmailLinkRequest>,
+    http_request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    if !is_valid_email(&request.email) {
+        return Ok(HttpResponse::BadRequest().body("email"));
+    }
+
+    // The answer is the same whether there is something to resend or not
+    let email = normalize_email(&request.email);
+    let Some(pending) = db_handler
+        .find_latest_unconfirmed_user_by_email(&email)
+        .await?
+    else {
+        return Ok(HttpResponse::Ok().finish());
+    };
+
+    // Once the old link expired someone else may have taken the username or email
+    let taken_by_other = |user: Option<UnconfirmedUser>| {
+        user.is_some_and(|user| user.confirmation_token != pending.confirmation_token)
+    };
+    if db_handler.find_user(&pending.username).await?.is_some()
+        || db_handler.find_user_by_email(&email).await?.is_some()
+        || taken_by_other(db_handler.find_unconfirmed_user(&pending.username).await?)
+        || taken_by_other(
+            db_handler
+                .find_unconfirmed_user_user_by_email(&email)
+                .await?,
+        )
+    {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let confirmation_token = generate_random_alphanumeric_str(32);
+    if db_handler
+        .renew_confirmation(&pending.confirmation_token, &confirmation_token, Utc::now())
+        .await?
+        == 0
+    {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let link = format!(
+        "{}/confirm/{}",
+        site_url.base_url(&http_request),
+        confirmation_token
+    );
+    match emailer.send_confirmation_email(&email, &link).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(err) => {
+            log::error!("Error sending confirmation email: {}", err);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/// The hops listed in the `Forwarded` header, or else in `X-Forwarded-For`,
+/// the closest last.
+fn forwarded_for(request: &HttpRequest) -> Vec<String> {
+    let headers = request.headers();
+    let values = |name| {
+        headers
+            .get_all(name)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect::<Vec<_>>()
+    };
+
+    let forwarded = values(header::FORWARDED);
+    if !forwarded.is_empty() {
+        return forwarded
+            .into_iter()
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.trim().split_once('=')?;
+                    key.eq_ignore_ascii_case("for")
+                        .then(|| value.trim_matches('"').to_string())
+                })
+            })
+            .collect();
+    }
+
+    values(header::X_FORWARDED_FOR)
+        .into_iter()
+        .map(|hop| hop.trim().to_string())
+        .collect()
+}
+
+/// `ip`, `ip:port`, `[ipv6]` or `[ipv6]:port`.
+fn parse_ip(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .or_else(|_| hop.trim_start_matches('[').trim_end_matches(']').parse())
+        .ok()
+}
+
+/// The address logins and reset requests are counted against: the peer of
+/// the connection, unless it is one of the `TRUSTED_PROXIES`. Then it is the
+/// closest address the proxies forward that isn't one of them, as anything
+/// before it can be made up by the client.
+fn client_ip(request: &HttpRequest) -> String {
+    let Some(peer) = request.peer_addr().map(|addr| addr.ip()) else {
+        return String::new();
+    };
+    let trusted: Vec<IpAddr> = request
+        .app_data::<web::Data<Config>>()
+        .map(|config| {
+            config
+                .TRUSTED_PROXIES
+                .split(',')
+                .filter_map(|proxy| proxy.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !trusted.contains(&peer) {
+        return peer.to_string();
+    }
+
+    let mut client = peer;
+    for hop in forwarded_for(request).iter().rev() {
+        match parse_ip(hop) {
+            Some(ip) if trusted.contains(&ip) => client = ip,
+            Some(ip) => return ip.to_string(),
+            // Obfuscated or unknown hops can't be counted apart
+            None => break,
+        }
+    }
+    client.to_string()
+}
+
+/// Rounded up so that retrying right when told to doesn't fail again.
+fn retry_after_secs(wait: Duration) -> String {
+    wait.as_millis().div_ceil(1000).to_string()
+}
+
+fn too_many_attempts(wait: Duration) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after_secs(wait)))
+        .finish()
+}
+
+/// Counts a failed login to `username` and answers it, emailing the owner of
+/// the account when it failed enough times in a row.
+async fn failed_login(
+    login_limiter: &LoginLimiter,
+    emailer: &Emailer,
+    site_url: &SiteUrl,
+    request: &HttpRequest,
+    username: &str,
+    user: Option<User>,
+) -> HttpResponse {
+    let failure = match login_limiter
+        .record_failure(&client_ip(request), username)
         .await
     {
-        match user_option {
-            Some(user) => {
-                if db_handler.insert_user(&user.into()).await.is_ok() {
-                    return HttpResponse::Ok().body("Confirmation successful!");
+        Ok(failure) => failure,
+        Err(err) => {
+            log::error!("Error recording failed login: {}", err);
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if failure.notify_owner {
+        if let Some(user) = user {
+            let emailer = emailer.clone();
+            let link = format!("{}/forgot-password", site_url.base_url(request));
+            // Sent in the background so that failures take as long to
+            // answer whether they are notified or not
+            actix_web::rt::spawn(async move {
+                if let Err(err) = emailer
+                    .send_failed_logins_email(&user.email, failure.account_failures, &link)
+                    .await
+                {
+                    log::error!("Error sending failed logins email: {}", err);
                 }
-            }
-            None => return HttpResponse::NotFound().finish(),
+            });
         }
     }
-    HttpResponse::InternalServerError().finish()
+
+    let mut response = HttpResponse::Unauthorized();
+    if let Some(wait) = failure.retry_after {
+        response.insert_header((header::RETRY_AFTER, retry_after_secs(wait)));
+    }
+    response.finish()
 }
 
+/// How long a login to `username` from the client has to wait, logging and
+/// letting it through when Redis can't be reached, as it wouldn't get a
+/// session anyway.
+async fn login_wait(
+    login_limiter: &LoginLimiter,
+    request: &HttpRequest,
+    username: &str,
+) -> Option<Duration> {
+    match login_limiter
+        .retry_after(&client_ip(request), username)
+        .await
+    {
+        Ok(wait) => wait,
+        Err(err) => {
+            log::error!("Error checking login attempts: {}", err);
+            None
+        }
+    }
+}
+
+/// Logs the user in once nothing else is asked of them.
+pub(super) async fn finish_login(
+    login_limiter: &LoginLimiter,
+    request: &HttpRequest,
+    username: &str,
+) -> HttpResponse {
+    if let Err(err) = login_limiter.record_success(username).await {
+        log::error!("Error clearing failed logins: {}", err);
+    }
+
+    if start_session(request, username).is_ok() {
+        HttpResponse::Ok().finish()
+    } else {
+        HttpResponse::InternalServerError().finish()
+    }
+}
+
+/// Checks the password. Users with two-factor authentication, or whose role
+/// requires it, get a `LoginChallenge` with a 202 and are only logged in
+/// once they meet it.
 pub async fn login_user<T: DBHandler>(
     db_handler: web::Data<T>,
+    login_limiter: web::Data<LoginLimiter>,
+    emailer: web::Data<Emailer>,
+    site_url: web::Data<SiteUrl>,
     login_info: web::Json<UserLoginForm>,
     request: HttpRequest,
-) -> impl Responder {
-    match db_handler.find_user(&login_info.username).await {
-        Ok(Some(user)) => {
-            if verify(&login_info.password, &user.password).unwrap_or(false) {
-                if Identity::login(&request.extensions(), login_info.username.clone()).is_ok() {
-                    HttpResponse::Ok()
-                } else {
-                    HttpResponse::InternalServerError()
-                }
-            } else {
-                HttpResponse::Unauthorized()
-            }
+) -> Result<HttpResponse, DbError> {
+    if let Some(wait) = login_wait(&login_limiter, &request, &login_info.username).await {
+        return Ok(too_many_attempts(wait));
+    }
+
+    let user = match db_handler.find_user(&login_info.username).await? {
+        Some(user) if verify(&login_info.password, &user.password).unwrap_or(false) => user,
+        user => {
+            return Ok(failed_login(
+                &login_limiter,
+                &emailer,
+                &site_url,
+                &request,
+                &login_info.username,
+                user,
+            )
+            .await);
+        }
+    };
+
+    // Failed logins are only forgotten after the second factor, or knowing the
+    // password would allow guessing codes forever
+    let two_factor = db_handler.find_two_factor(&user.username).await?;
+    let challenge = if two_factor.is_some_and(|two_factor| two_factor.enabled) {
+        LoginChallenge::TwoFactor
+    } else if is_two_factor_required(db_handler.get_ref(), user.role).await? {
+        LoginChallenge::TwoFactorSetup
+    } else {
+        return Ok(finish_login(&login_limiter, &request, &user.username).await);
+    };
+
+    let setup = challenge == LoginChallenge::TwoFactorSetup;
+    if start_pending_login(&request, &user.username, setup).is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+
+    Ok(HttpResponse::Accepted().json(challenge))
+}
+
+/// The second step of a login, with a TOTP code or a recovery code.
+pub async fn login_two_factor<T: DBHandler>(
+    db_handler: web::Data<T>,
+    login_limiter: web::Data<LoginLimiter>,
+    emailer: web::Data<Emailer>,
+    site_url: web::Data<SiteUrl>,
+    code: web::Json<TwoFactorCodeRequest>,
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    let Some(pending) = pending_login(&request).filter(|pending| !pending.setup) else {
+        return Ok(HttpResponse::Unauthorized().body("login"));
+    };
+
+    if let Some(wait) = login_wait(&login_limiter, &request, &pending.username).await {
+        return Ok(too_many_attempts(wait));
+    }
+
+    let Some(two_factor) = db_handler
+        .find_two_factor(&pending.username)
+        .await?
+        .filter(|two_factor| two_factor.enabled)
+    else {
+        return Ok(HttpResponse::Unauthorized().body("login"));
+    };
+
+    let accepted = match verify_code(&two_factor.secret, &code.code, Utc::now()) {
+        Some(step) => db_handler.use_totp_step(&pending.username, step).await? > 0,
+        None => {
+            db_handler
+                .use_recovery_code(&pending.username, &hash_recovery_code(&code.code))
+                .await?
+                > 0
         }
-        Ok(None) => HttpResponse::Unauthorized(),
-        Err(_) => HttpResponse::InternalServerError(),
+    };
+    if !accepted {
+        let user = db_handler.find_user(&pending.username).await?;
+        return Ok(failed_login(
+            &login_limiter,
+            &emailer,
+            &site_url,
+            &request,
+            &pending.username,
+            user,
+        )
+        .await);
+    }
+
+    Ok(finish_login(&login_limiter, &request, &pending.username).await)
+}
+
+pub async fn forgot_password<T: DBHandler>(
+    db_handler: web::Data<T>,
+    emailer: web::Data<Emailer>,
+    site_url: web::Data<SiteUrl>,
+    login_limiter: web::Data<LoginLimiter>,
+    request: web::Json<EmailLinkRequest>,
+    http_request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    if !is_valid_email(&request.email) {
+        return Ok(HttpResponse::BadRequest().body("email"));
+    }
+
+    // The answer is the same whether the email belongs to someone or not
+    let email = normalize_email(&request.email);
+    match login_limiter
+        .record_reset_request(&client_ip(&http_request), &email)
+        .await
+    {
+        Ok(ResetRequest::Allowed) => {}
+        Ok(ResetRequest::IpLimited(wait)) => return Ok(too_many_attempts(wait)),
+        Ok(ResetRequest::EmailThrottled) => return Ok(HttpResponse::Ok().finish()),
+        Err(err) => log::error!("Error counting password reset requests: {}", err),
+    }
+    let Some(user) = db_handler.find_user_by_email(&email).await? else {
+        return Ok(HttpResponse::Ok().finish());
+    };
+
+    let token = generate_random_alphanumeric_str(32);
+    db_handler
+        .insert_password_reset(&PasswordReset {
+            token_hash: hash_token(&token),
+            username: user.username,
+            created_at: Utc::now(),
+        })
+        .await?;
+
+    let link = format!(
+        "{}/reset-password/{}",
+        site_url.base_url(&http_request),
+        token
+    );
+    match emailer.send_password_reset_email(&user.email, &link).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(err) => {
+            log::error!("Error sending password reset email: {}", err);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+pub async fn reset_password<T: DBHandler>(
+    db_handler: web::Data<T>,
+    form: web::Json<PasswordResetForm>,
+) -> Result<HttpResponse, DbError> {
+    if !is_valid_password(&form.password) {
+        return Ok(HttpResponse::BadRequest().body("password"));
+    }
+
+    let Some(reset) = db_handler
+        .take_password_reset(&hash_token(&form.token))
+        .await?
+    else {
+        return Err(DbError::NotFound);
+    };
+
+    let hashed_password = match hash(&form.password, DEFAULT_COST) {
+        Ok(hashed) => hashed,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+
+    // Also logs out every session of the user
+    match db_handler
+        .update_user_password(&reset.username, &hashed_password)
+        .await?
+    {
+        0 => Err(DbError::NotFound),
+        _ => Ok(HttpResponse::Ok().body("Password changed")),
     }
 }
 
@@ -0,0 +1,82 @@
+use std::io::{Seek, SeekFrom};
+
+use actix_files::NamedFile;
+use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+use actix_web::{
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use chrono::Utc;
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{capability, export_archive, import_archive, ImportError, RequireCapability},
+    Config,
+};
+use common::ImportQuery;
+
+#[derive(MultipartForm, Debug)]
+pub struct ImportForm {
+    #[multipart(limit = "50MB")]
+    archive: TempFile,
+}
+
+/// Sends the archive of the whole blog. It is written to a temporary file
+/// first, which is streamed from disk rather than held in memory.
+pub async fn export_posts<T: DBHandler>(
+    db_handler: web::Data<T>,
+    config: web::Data<Config>,
+    _: RequireCapability<T, capability::ManageBackups>,
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    let file_error = |err: std::io::Error| DbError::Other(err.into());
+    let file = tempfile::tempfile().map_err(file_error)?;
+
+    let mut file = export_archive(
+        db_handler.as_ref(),
+        &config.FILE_UPLOAD_PATH,
+        &config.FILE_UPLOAD_URL,
+        file,
+    )
+    .await?;
+    file.seek(SeekFrom::Start(0)).map_err(file_error)?;
+
+    let filename = format!("blog-{}.tar", Utc::now().format("%Y-%m-%d"));
+    let archive = NamedFile::from_file(file, &filename)
+        .map_err(file_error)?
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        });
+
+    Ok(archive.respond_to(&request))
+}
+
+pub async fn import_posts<T: DBHandler>(
+    db_handler: web::Data<T>,
+    config: web::Data<Config>,
+    query: web::Query<ImportQuery>,
+    MultipartForm(form): MultipartForm<ImportForm>,
+    RequireCapability { user, .. }: RequireCapability<T, capability::ManageBackups>,
+) -> Result<HttpResponse, DbError> {
+    let Ok(archive) = form.archive.file.reopen() else {
+        return Ok(HttpResponse::InternalServerError().finish());
+    };
+
+    match import_archive(
+        db_handler.as_ref(),
+        archive,
+        &config.FILE_UPLOAD_PATH,
+        query.overwrite,
+        &user.username,
+    )
+    .await
+    {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(ImportError::Db(err)) => Err(err),
+        Err(ImportError::Archive(err)) => {
+            log::warn!("Error reading an imported archive: {}", err);
+            Ok(HttpResponse::BadRequest().body("archive"))
+        }
+    }
+}
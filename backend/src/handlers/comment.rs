@@ -0,0 +1,311 @@
+use actix_web::{web, HttpResponse};
+use chrono::{SubsecRound, Utc};
+
+use crate::{
+    database::{DBHandler, DbError},
+    models::{CommentForm, User},
+    utils::{
+        capability, generate_random_alphanumeric_str, get_visible_comments, CurrentUser,
+        RequireCapability,
+    },
+};
+use common::{
+    utils::is_valid_comment, Capability, Comment, CommentQueueQuery, CommentStatus,
+    CreateCommentRequest, ModerateCommentRequest, UpdateCommentRequest,
+};
+
+const COMMENT_ID_LENGTH: usize = 24;
+
+enum CommentError {
+    Db(DbError),
+    /// The content is empty or too long, or the parent can't be replied to.
+    Invalid,
+    /// The user isn't allowed to touch the comment.
+    Forbidden,
+}
+
+impl From<DbError> for CommentError {
+    fn from(value: DbError) -> Self {
+        CommentError::Db(value)
+    }
+}
+
+fn error_response(err: CommentError) -> Result<HttpResponse, DbError> {
+    match err {
+        CommentError::Db(err) => Err(err),
+        CommentError::Invalid => Ok(HttpResponse::BadRequest().body("content")),
+        CommentError::Forbidden => Ok(HttpResponse::Forbidden().finish()),
+    }
+}
+
+fn is_moderator(user: &User) -> bool {
+    user.can(Capability::ModerateComments)
+}
+
+fn redirect_to_comments(post_slug: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .append_header(("location", format!("/post/{}#comments", post_slug)))
+        .finish()
+}
+
+async fn add_comment<T: DBHandler>(
+    db_handler: &T,
+    user: &User,
+    request: &CreateCommentRequest,
+) -> Result<Comment, CommentError> {
+    if !is_valid_comment(&request.content) {
+        return Err(CommentError::Invalid);
+    }
+
+    if db_handler
+        .get_post(&request.post_slug, is_moderator(user))
+        .await?
+        .is_none()
+    {
+        return Err(DbError::NotFound.into());
+    }
+
+    // Empty values come from the reply forms
+    let parent_id = request.parent_id.clone().filter(|id| !id.is_empty());
+    if let Some(ref parent_id) = parent_id {
+        let siblings = get_visible_comments(
+            db_handler,
+            &request.post_slug,
+            Some(&user.username),
+            is_moderator(user),
+        )
+        .await?;
+
+        if !siblings.iter().any(|comment| &comment.id == parent_id) {
+            return Err(CommentError::Invalid);
+        }
+    }
+
+    let comment = Comment {
+        id: generate_random_alphanumeric_str(COMMENT_ID_LENGTH),
+        post_slug: request.post_slug.clone(),
+        parent_id,
+        author: user.username.clone(),
+        content: request.content.clone(),
+        // The databases keep milliseconds, so the comment returned here is the
+        // same as the one listed later
+        created_at: Utc::now().trunc_subsecs(3),
+        edited_at: None,
+        status: if is_moderator(user) {
+            CommentStatus::Approved
+        } else {
+            CommentStatus::Pending
+        },
+    };
+    db_handler.create_comment(&comment).await?;
+
+    Ok(comment)
+}
+
+async fn edit_comment<T: DBHandler>(
+    db_handler: &T,
+    user: &User,
+    id: &str,
+    content: &str,
+) -> Result<Comment, CommentError> {
+    let Some(comment) = db_handler.get_comment(id).await? else {
+        return Err(DbError::NotFound.into());
+    };
+
+    if comment.author != user.username {
+        return Err(CommentError::Forbidden);
+    }
+    if !is_valid_comment(content) {
+        return Err(CommentError::Invalid);
+    }
+
+    // Edits by readers go through moderation again
+    let status = if is_moderator(user) {
+        CommentStatus::Approved
+    } else {
+        CommentStatus::Pending
+    };
+    let edited_at = Utc::now().trunc_subsecs(3);
+    db_handler
+        .update_comment(id, content, edited_at, status)
+        .await?;
+
+    Ok(Comment {
+        content: content.to_string(),
+        edited_at: Some(edited_at),
+        status,
+        ..comment
+    })
+}
+
+/// Deletes the comment with all its replies and returns the slug of its post.
+async fn remove_comment<T: DBHandler>(
+    db_handler: &T,
+    user: &User,
+    id: &str,
+) -> Result<String, CommentError> {
+    let Some(comment) = db_handler.get_comment(id).await? else {
+        return Err(DbError::NotFound.into());
+    };
+
+    if comment.author != user.username && !is_moderator(user) {
+        return Err(CommentError::Forbidden);
+    }
+
+    let comments = db_handler.get_comments(&comment.post_slug).await?;
+    let mut to_delete = vec![comment.id];
+    let mut index = 0;
+    while index < to_delete.len() {
+        for reply in comments.iter() {
+            if reply.parent_id.as_ref() == Some(&to_delete[index]) {
+                to_delete.push(reply.id.clone());
+            }
+        }
+        index += 1;
+    }
+
+    for id in to_delete.iter() {
+        db_handler.delete_comment(id).await?;
+    }
+
+    Ok(comment.post_slug)
+}
+
+/// Comments are written from a session. API tokens are scoped to
+/// capabilities, and none of them covers commenting, so they can't.
+pub async fn create_comment<T: DBHandler>(
+    db_handler: web::Data<T>,
+    request: web::Json<CreateCommentRequest>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match add_comment(db_handler.as_ref(), &db_user, &request).await {
+        Ok(comment) => Ok(HttpResponse::Ok().json(comment)),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn get_comments<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    CurrentUser { user: db_user, .. }: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let moderator = db_user.as_ref().is_some_and(is_moderator);
+
+    if db_handler.get_post(&slug, moderator).await?.is_none() {
+        return Err(DbError::NotFound);
+    }
+
+    let comments = get_visible_comments(
+        db_handler.as_ref(),
+        &slug,
+        db_user.as_ref().map(|user| user.username.as_str()),
+        moderator,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(comments))
+}
+
+pub async fn update_comment<T: DBHandler>(
+    db_handler: web::Data<T>,
+    request: web::Json<UpdateCommentRequest>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match edit_comment(db_handler.as_ref(), &db_user, &request.id, &request.content).await {
+        Ok(comment) => Ok(HttpResponse::Ok().json(comment)),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn delete_comment<T: DBHandler>(
+    db_handler: web::Data<T>,
+    id: web::Path<String>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match remove_comment(db_handler.as_ref(), &db_user, &id).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn get_comment_queue<T: DBHandler>(
+    db_handler: web::Data<T>,
+    query: web::Query<CommentQueueQuery>,
+    _: RequireCapability<T, capability::ModerateComments>,
+) -> Result<HttpResponse, DbError> {
+    let status = query.status.unwrap_or(CommentStatus::Pending);
+    Ok(HttpResponse::Ok().json(db_handler.get_comments_by_status(status).await?))
+}
+
+pub async fn moderate_comment<T: DBHandler>(
+    db_handler: web::Data<T>,
+    request: web::Json<ModerateCommentRequest>,
+    _: RequireCapability<T, capability::ModerateComments>,
+) -> Result<HttpResponse, DbError> {
+    if db_handler.get_comment(&request.id).await?.is_none() {
+        return Err(DbError::NotFound);
+    }
+
+    db_handler
+        .set_comment_status(&request.id, request.status)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn create_comment_and_redirect<T: DBHandler>(
+    db_handler: web::Data<T>,
+    request: web::Form<CreateCommentRequest>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match add_comment(db_handler.as_ref(), &db_user, &request).await {
+        Ok(comment) => Ok(redirect_to_comments(&comment.post_slug)),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn update_comment_and_redirect<T: DBHandler>(
+    db_handler: web::Data<T>,
+    id: web::Path<String>,
+    form: web::Form<CommentForm>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match edit_comment(db_handler.as_ref(), &db_user, &id, &form.content).await {
+        Ok(comment) => Ok(redirect_to_comments(&comment.post_slug)),
+        Err(err) => error_response(err),
+    }
+}
+
+pub async fn delete_comment_and_redirect<T: DBHandler>(
+    db_handler: web::Data<T>,
+    id: web::Path<String>,
+    current_user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let Some(db_user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    match remove_comment(db_handler.as_ref(), &db_user, &id).await {
+        Ok(post_slug) => Ok(redirect_to_comments(&post_slug)),
+        Err(err) => error_response(err),
+    }
+}
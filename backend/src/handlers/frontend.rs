@@ -1,40 +1,40 @@
 use std::sync::Arc;
 
-use actix_identity::Identity;
 use actix_web::{web, HttpResponse, Responder};
-use common::{utils::get_summary, Post, PostsQueryParams};
-use yew::ServerRenderer;
+use common::{
+    utils::{get_summary, title_to_slug},
+    Capability, GetPostsResponse, Post, PostSummary, PostsQueryParams, SearchHit,
+    SearchQueryParams, SearchResponse, Series, SeriesOverview,
+};
+use yew::{AttrValue, ServerRenderer};
 
 use crate::{
-    database::DBHandler,
-    utils::{parse_markdown, Highlighter},
+    database::{DBHandler, DbError},
+    models::PageCursor,
+    utils::{
+        find_redirect, get_posts_page, get_search_results, get_series_overview, get_series_posts,
+        get_visible_comments, parse_markdown, CurrentUser, Highlighter,
+    },
+};
+use frontend::{
+    Blog, BlogProps, Layout, LayoutProps, PostPage, PostProps, SearchPage, SearchPageProps,
+    SeriesPage, SeriesPageProps, UsernameAndRole,
 };
-use frontend::{Blog, BlogProps, Layout, LayoutProps, PostPage, PostProps, UsernameAndRole};
 
 const MAX_SUMMARY_SIZE: usize = 200;
+pub const SITE_TITLE: &str = "Studentenherz's Blog";
+pub const SITE_DESCRIPTION: &str = "A blogging website made with Rust, using Yew and Actix Web.";
 
-async fn get_full_html<T: DBHandler>(
+/// Fills the page template with `content`, under the layout `user` sees.
+pub async fn render_page(
     content: &str,
-    user_iden: &Option<Identity>,
-    db_handler: &T,
+    user: Option<UsernameAndRole>,
     title: &str,
     description: &str,
     slug: &str,
 ) -> String {
     let index_html_string = include_str!("../../index.html");
 
-    let mut user = None;
-    if let Some(iden) = user_iden {
-        if let Ok(username) = iden.id() {
-            if let Ok(Some(full_user)) = db_handler.find_user(&username).await {
-                user = Some(UsernameAndRole {
-                    username: full_user.username,
-                    role: full_user.role,
-                });
-            }
-        }
-    }
-
     let layout = ServerRenderer::<Layout>::with_props(|| LayoutProps {
         user,
         ..LayoutProps::default()
@@ -61,45 +61,220 @@ async fn get_full_html<T: DBHandler>(
         .replace("</main>", &format!("{}</main>", content))
 }
 
+/// The user as the frontend components see it.
+fn username_and_role<T>(user: &CurrentUser<T>) -> Option<UsernameAndRole> {
+    user.user.as_ref().map(|user| UsernameAndRole {
+        username: user.username.clone(),
+        role: user.role,
+    })
+}
+
+async fn get_full_html<T>(
+    content: &str,
+    user: &CurrentUser<T>,
+    title: &str,
+    description: &str,
+    slug: &str,
+) -> String {
+    render_page(content, username_and_role(user), title, description, slug).await
+}
+
+/// Renders a page of posts as listed by `get_posts_page`.
+pub async fn render_posts_list(
+    response: GetPostsResponse,
+    page: u64,
+    tag: Option<String>,
+    static_pages: bool,
+) -> String {
+    let GetPostsResponse {
+        posts,
+        pages,
+        next,
+        prev,
+    } = response;
+    let posts: Vec<Arc<PostSummary>> = posts.into_iter().map(Arc::new).collect();
+
+    ServerRenderer::<Blog>::with_props(move || BlogProps {
+        page,
+        pages: pages.unwrap_or_default(),
+        posts,
+        tag: tag.map(AttrValue::from),
+        next: next.map(AttrValue::from),
+        prev: prev.map(AttrValue::from),
+        static_pages,
+    })
+    .hydratable(false)
+    .render()
+    .await
+}
+
+async fn render_blog<T: DBHandler>(
+    db_handler: &T,
+    query: PostsQueryParams,
+    user: &CurrentUser<T>,
+) -> String {
+    let mut content = String::from("Sorry something went wrong");
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    // Malformed cursors start over from the newest posts
+    let mut cursor = PageCursor::from_query(&query).unwrap_or(PageCursor::First);
+    let mut result = get_posts_page(db_handler, &query, &cursor, is_admin).await;
+    if let Err(DbError::InvalidCursor) = result {
+        cursor = PageCursor::First;
+        result = get_posts_page(db_handler, &query, &cursor, is_admin).await;
+    }
+    // Page numbers are only known when paginating by offset
+    let page = match cursor {
+        PageCursor::First => query.page.unwrap_or(1),
+        _ => 0,
+    };
+
+    match result {
+        Ok(response) => content = render_posts_list(response, page, query.tag, false).await,
+        Err(err) => log::error!("Error loading the blog: {}", err),
+    }
+
+    content
+}
+
 pub async fn yew_blog<T: DBHandler>(
     db_handler: web::Data<T>,
     query: web::Query<PostsQueryParams>,
-    user: Option<Identity>,
+    user: CurrentUser<T>,
+) -> impl Responder {
+    let title = SITE_TITLE;
+    let description = SITE_DESCRIPTION;
+    let content = render_blog(db_handler.as_ref(), query.into_inner(), &user).await;
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(get_full_html(&content, &user, title, description, "blog").await)
+}
+
+pub async fn yew_tag<T: DBHandler>(
+    db_handler: web::Data<T>,
+    tag: web::Path<String>,
+    query: web::Query<PostsQueryParams>,
+    user: CurrentUser<T>,
+) -> impl Responder {
+    let tag = title_to_slug(&tag);
+    let title = format!("#{} | {}", tag, SITE_TITLE);
+    let description = format!("Posts tagged {}.", tag);
+    let content = render_blog(
+        db_handler.as_ref(),
+        PostsQueryParams {
+            tag: Some(tag.clone()),
+            ..query.into_inner()
+        },
+        &user,
+    )
+    .await;
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            get_full_html(
+                &content,
+                &user,
+                &title,
+                &description,
+                &format!("tag/{}", tag),
+            )
+            .await,
+        )
+}
+
+pub async fn yew_search<T: DBHandler>(
+    db_handler: web::Data<T>,
+    query: web::Query<SearchQueryParams>,
+    user: CurrentUser<T>,
 ) -> impl Responder {
     let mut content = String::from("Sorry something went wrong");
-    let title = "Studentenherz's Blog";
-    let description = "A blogging website made with Rust, using Yew and Actix Web.";
-
-    let mut is_admin = false;
-    if let Some(ref iden) = user {
-        if let Ok(user_id) = iden.id() {
-            if let Ok(db_result) = db_handler.find_user(&user_id).await {
-                match db_result {
-                    Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                        is_admin = true;
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
+    let title = format!("Search | {}", SITE_TITLE);
+    let description = "Search the posts of the blog.";
+
+    let is_admin = user.can(Capability::ViewDrafts);
 
-    if let Ok(posts) = db_handler.get_posts(&query, is_admin).await {
-        println!("Posts: {:?}", posts);
-        let posts: Vec<Arc<Post>> = posts.into_iter().map(Arc::new).collect();
-        if let Ok(pages) = db_handler
-            .calculate_total_pages(query.per_page.unwrap_or(10))
-            .await
-        {
-            content = ServerRenderer::<Blog>::with_props(move || BlogProps {
+    match get_search_results(db_handler.as_ref(), &query, is_admin).await {
+        Ok(SearchResponse { hits, pages }) => {
+            let query = query.into_inner();
+            let hits: Vec<Arc<SearchHit>> = hits.into_iter().map(Arc::new).collect();
+            content = ServerRenderer::<SearchPage>::with_props(move || SearchPageProps {
+                query: query.q.into(),
                 page: query.page.unwrap_or(1),
                 pages,
-                posts,
+                hits,
             })
             .hydratable(false)
             .render()
             .await;
         }
+        Err(err) => log::error!("Error searching for {:?}: {}", query.q, err),
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(get_full_html(&content, &user, &title, description, "search").await)
+}
+
+async fn find_series_overview<T: DBHandler>(
+    db_handler: &T,
+    post_slug: &str,
+    is_admin: bool,
+) -> Result<Option<SeriesOverview>, DbError> {
+    match db_handler.find_series_by_post(post_slug).await? {
+        Some(series) => Ok(Some(
+            get_series_overview(db_handler, series, is_admin).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Renders the page of `series` with the posts in it.
+pub async fn render_series<T: DBHandler>(
+    db_handler: &T,
+    series: Series,
+    is_admin: bool,
+) -> Result<String, DbError> {
+    let posts: Vec<Arc<PostSummary>> = get_series_posts(db_handler, &series, is_admin)
+        .await?
+        .iter()
+        .map(|post| Arc::new(PostSummary::from(post)))
+        .collect();
+
+    Ok(
+        ServerRenderer::<SeriesPage>::with_props(move || SeriesPageProps {
+            series: Arc::new(series),
+            posts,
+        })
+        .hydratable(false)
+        .render()
+        .await,
+    )
+}
+
+pub async fn yew_series<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    user: CurrentUser<T>,
+) -> impl Responder {
+    let mut content = String::from("Sorry something went wrong");
+    let mut title = String::from(SITE_TITLE);
+    let mut description = String::from(SITE_DESCRIPTION);
+
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    match db_handler.get_series(&slug).await {
+        Ok(Some(series)) => {
+            title = series.title.clone();
+            description = series.description.clone();
+            match render_series(db_handler.as_ref(), series, is_admin).await {
+                Ok(series_html) => content = series_html,
+                Err(err) => log::error!("Error loading series {}: {}", slug, err),
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(err) => log::error!("Error loading series {}: {}", slug, err),
     }
 
     HttpResponse::Ok()
@@ -108,67 +283,115 @@ pub async fn yew_blog<T: DBHandler>(
             get_full_html(
                 &content,
                 &user,
-                db_handler.as_ref(),
-                title,
-                description,
-                "blog",
+                &title,
+                &description,
+                &format!("series/{}", slug),
             )
             .await,
         )
 }
 
+/// Meta description of the page of `post`.
+pub fn post_description(post: &Post) -> String {
+    match &post.summary {
+        Some(summary) => {
+            // Cut on a character boundary so multibyte text isn't split
+            let mut end = std::cmp::min(MAX_SUMMARY_SIZE, summary.len());
+            while !summary.is_char_boundary(end) {
+                end -= 1;
+            }
+            summary[..end].to_string()
+        }
+        None => get_summary(&post.content, MAX_SUMMARY_SIZE),
+    }
+}
+
+/// Renders the page of `post` with its series and the comments `user` may
+/// see.
+pub async fn render_post<T: DBHandler>(
+    db_handler: &T,
+    post: Post,
+    highlighter: &Highlighter,
+    user: Option<UsernameAndRole>,
+    is_admin: bool,
+) -> String {
+    let slug = post.slug.clone();
+    let series = match find_series_overview(db_handler, &slug, is_admin).await {
+        Ok(series) => series.map(Arc::new),
+        Err(err) => {
+            log::error!("Error loading the series of {}: {}", slug, err);
+            None
+        }
+    };
+
+    let comments = match get_visible_comments(
+        db_handler,
+        &slug,
+        user.as_ref().map(|user| user.username.as_str()),
+        is_admin,
+    )
+    .await
+    {
+        Ok(comments) => comments,
+        Err(err) => {
+            log::error!("Error loading the comments of {}: {}", slug, err);
+            vec![]
+        }
+    };
+
+    let (headers, html_string) = parse_markdown(&post.content, highlighter);
+    ServerRenderer::<PostPage>::with_props(move || PostProps {
+        slug: slug.into(),
+        post: Arc::new(post),
+        post_content: html_string.into(),
+        headers,
+        user,
+        series,
+        comments,
+    })
+    .hydratable(false)
+    .render()
+    .await
+}
+
 pub async fn yew_post<T: DBHandler>(
     db_handler: web::Data<T>,
     slug: web::Path<String>,
     highlighter: web::Data<Highlighter>,
-    user_iden: Option<Identity>,
+    user: CurrentUser<T>,
 ) -> impl Responder {
     let mut content = String::from("Sorry something went wrong");
-    let mut title = String::from("Studentenherz's Blog");
-    let mut description =
-        String::from("A blogging website made with Rust, using Yew and Actix Web.");
-
-    let mut is_admin = false;
-    let mut user = None;
-    if let Some(iden) = &user_iden {
-        if let Ok(username) = iden.id() {
-            if let Ok(Some(full_user)) = db_handler.find_user(&username).await {
-                if full_user.role == "Admin" || full_user.role == "Editor" {
-                    is_admin = true;
-                }
-                user = Some(UsernameAndRole {
-                    username: full_user.username,
-                    role: full_user.role,
-                });
-            }
-        }
-    }
+    let mut title = String::from(SITE_TITLE);
+    let mut description = String::from(SITE_DESCRIPTION);
+
+    let is_admin = user.can(Capability::ViewDrafts);
 
-    if let Ok(post) = db_handler.get_post(&slug, is_admin).await {
-        if let Some(post) = post {
-            let (headers, html_string) = parse_markdown(&post.content, &highlighter);
+    match db_handler.get_post(&slug, is_admin).await {
+        Ok(Some(post)) => {
             title = post.title.clone();
-            if let Some(summary) = &post.summary {
-                description = summary[..std::cmp::min(MAX_SUMMARY_SIZE, summary.len())].to_string();
-            } else {
-                description = get_summary(&post.content, MAX_SUMMARY_SIZE);
-            }
-            {
-                let slug = slug.clone();
-                content = ServerRenderer::<PostPage>::with_props(move || PostProps {
-                    slug: slug.into(),
-                    post: Arc::new(post),
-                    post_content: html_string.into(),
-                    headers,
-                    user,
-                })
-                .hydratable(false)
-                .render()
-                .await;
+            description = post_description(&post);
+            content = render_post(
+                db_handler.as_ref(),
+                post,
+                &highlighter,
+                username_and_role(&user),
+                is_admin,
+            )
+            .await;
+        }
+        Ok(None) => {
+            return match find_redirect(db_handler.as_ref(), &slug, is_admin).await {
+                Ok(Some(new_slug)) => HttpResponse::MovedPermanently()
+                    .append_header(("location", format!("/post/{}", new_slug)))
+                    .finish(),
+                Ok(None) => HttpResponse::NotFound().finish(),
+                Err(err) => {
+                    log::error!("Error loading the redirect of {}: {}", slug, err);
+                    HttpResponse::NotFound().finish()
+                }
             }
-        } else {
-            return HttpResponse::NotFound().finish();
         }
+        Err(err) => log::error!("Error loading post {}: {}", slug, err),
     }
 
     HttpResponse::Ok()
@@ -176,8 +399,7 @@ pub async fn yew_post<T: DBHandler>(
         .body(
             get_full_html(
                 &content,
-                &user_iden,
-                db_handler.as_ref(),
+                &user,
                 &title,
                 &description,
                 &format!("post/{}", slug),
@@ -1,13 +1,142 @@
+use actix_web::web;
+
+use crate::database::DBHandler;
+
+mod api_token;
 mod auth;
+mod backup;
+mod comment;
 mod frontend;
 mod post;
+mod revision;
 mod rss;
+mod series;
+mod settings;
 mod syntax_highlight;
+mod trash;
+mod two_factor;
 mod uploads;
 
+pub use api_token::*;
 pub use auth::*;
+pub use backup::*;
+pub use comment::*;
 pub use frontend::*;
 pub use post::*;
+pub use revision::*;
 pub use rss::*;
+pub use series::*;
+pub use settings::*;
 pub use syntax_highlight::*;
+pub use trash::*;
+pub use two_factor::*;
 pub use uploads::*;
+
+/// Every route of the api and of the server rendered pages. The static files
+/// are left to the caller, as they depend on where it is deployed.
+pub fn routes<T: DBHandler + Clone + Send + Sync + 'static>(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/rss").get(rss_feed_handler::<T>))
+        .service(web::resource("/sitemap").get(rss_sitemap_handler::<T>))
+        .service(
+            web::scope("/api")
+                .service(
+                    web::scope("/auth")
+                        .service(web::resource("/register").post(register_user::<T>))
+                        .service(web::resource("/confirm").post(confirm_user::<T>))
+                        .service(
+                            web::resource("/resend-confirmation").post(resend_confirmation::<T>),
+                        )
+                        .service(web::resource("/login").post(login_user::<T>))
+                        .service(web::resource("/login/two-factor").post(login_two_factor::<T>))
+                        .service(
+                            web::scope("/two-factor")
+                                .service(web::resource("").get(get_two_factor_status::<T>))
+                                .service(web::resource("/setup").post(setup_two_factor::<T>))
+                                .service(web::resource("/enable").post(enable_two_factor::<T>))
+                                .service(web::resource("/disable").post(disable_two_factor::<T>))
+                                .service(
+                                    web::resource("/recovery-codes")
+                                        .post(renew_two_factor_recovery_codes::<T>),
+                                ),
+                        )
+                        .service(
+                            web::scope("/tokens")
+                                .service(
+                                    web::resource("")
+                                        .get(get_api_tokens::<T>)
+                                        .post(create_api_token::<T>),
+                                )
+                                .service(web::resource("/{id}").delete(revoke_api_token::<T>)),
+                        )
+                        .service(web::resource("/forgot-password").post(forgot_password::<T>))
+                        .service(web::resource("/reset-password").post(reset_password::<T>)),
+                )
+                .service(
+                    web::scope("/post")
+                        .service(web::resource("/get-list").get(get_posts::<T>))
+                        .service(web::resource("/read/{slug}").get(get_post::<T>))
+                        .service(web::resource("/tags").get(get_tags::<T>))
+                        .service(web::resource("/search").get(search_posts::<T>))
+                        .service(web::resource("/create").post(create_post::<T>))
+                        .service(web::resource("/update").post(update_post::<T>))
+                        .service(web::resource("/delete/{slug}").delete(delete_post::<T>))
+                        .service(web::resource("/transfer").post(transfer_post::<T>))
+                        .service(web::resource("/revisions/{slug}").get(get_revisions::<T>))
+                        .service(
+                            web::resource("/revisions/{slug}/diff").get(get_revision_diff::<T>),
+                        )
+                        .service(web::resource("/revisions/{slug}/{number}").get(get_revision::<T>))
+                        .service(
+                            web::resource("/revisions/{slug}/{number}/restore")
+                                .post(restore_revision::<T>),
+                        ),
+                )
+                .service(
+                    web::scope("/series")
+                        .service(web::resource("/get-list").get(get_series_list::<T>))
+                        .service(web::resource("/read/{slug}").get(get_series::<T>))
+                        .service(web::resource("/create").post(create_series::<T>))
+                        .service(web::resource("/update").post(update_series::<T>))
+                        .service(web::resource("/delete/{slug}").delete(delete_series::<T>)),
+                )
+                .service(
+                    web::scope("/comment")
+                        .service(web::resource("/list/{slug}").get(get_comments::<T>))
+                        .service(web::resource("/create").post(create_comment::<T>))
+                        .service(web::resource("/update").post(update_comment::<T>))
+                        .service(web::resource("/delete/{id}").delete(delete_comment::<T>))
+                        .service(web::resource("/queue").get(get_comment_queue::<T>))
+                        .service(web::resource("/moderate").post(moderate_comment::<T>)),
+                )
+                .service(
+                    web::scope("/trash")
+                        .service(web::resource("/get-list").get(get_trash::<T>))
+                        .service(web::resource("/restore/{slug}").post(restore_post::<T>))
+                        .service(web::resource("/purge/{slug}").delete(purge_post::<T>)),
+                )
+                .service(
+                    web::scope("/settings")
+                        .service(web::resource("").get(get_settings::<T>))
+                        .service(web::resource("/update").post(update_settings::<T>)),
+                )
+                .service(
+                    web::scope("/backup")
+                        .service(web::resource("/export").get(export_posts::<T>))
+                        .service(web::resource("/import").post(import_posts::<T>)),
+                )
+                .service(web::resource("/upload").post(upload::<T>))
+                .service(web::resource("/highlight").post(highlight_code)),
+        )
+        .service(web::redirect("/", "/blog"))
+        .service(web::resource("/blog").get(yew_blog::<T>))
+        .service(web::resource("/post/{slug}").get(yew_post::<T>))
+        .service(web::resource("/tag/{tag}").get(yew_tag::<T>))
+        .service(web::resource("/search").get(yew_search::<T>))
+        .service(web::resource("/series/{slug}").get(yew_series::<T>))
+        .service(web::resource("/comment").post(create_comment_and_redirect::<T>))
+        .service(web::resource("/comment/{id}/edit").post(update_comment_and_redirect::<T>))
+        .service(web::resource("/comment/{id}/delete").post(delete_comment_and_redirect::<T>))
+        .service(web::resource("/logout").get(logout_user))
+        .service(web::resource("/delete/{slug}").post(delete_post_and_redirect::<T>))
+        .service(web::resource("/robots.txt").get(robots));
+}
@@ -1,174 +1,218 @@
-use actix_identity::Identity;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse};
 use chrono::Utc;
 
-use crate::{database::DBHandler, models::PostsQueryParams, utils::generate_unique_slug};
-use common::{CreatePostRequest, GetPostsResponse, Post, PostCreatedResponse, UpdatePostRequest};
+use crate::{
+    database::{DBHandler, DbError},
+    models::{PageCursor, PostsQueryParams},
+    utils::{
+        capability, find_redirect, generate_unique_slug, get_posts_page, get_search_results,
+        save_revision, CurrentUser, RequireCapability,
+    },
+};
+use common::{
+    utils::{normalize_tags, title_to_slug},
+    Capability, CreatePostRequest, Post, PostCreatedResponse, SearchQueryParams,
+    TransferPostRequest, UpdatePostRequest,
+};
 
 pub async fn create_post<T: DBHandler>(
     db_handler: web::Data<T>,
     post: web::Json<CreatePostRequest>,
-    user: Identity,
-) -> impl Responder {
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    if let Ok(slug) = generate_unique_slug(db_handler.as_ref(), &post.title).await {
-                        if db_handler
-                            .create_post(&Post {
-                                slug: slug.clone(),
-                                title: post.title.clone(),
-                                content: post.content.clone(),
-                                summary: post.summary.clone(),
-                                author: user_id,
-                                published_at: Utc::now(),
-                                public: post.public,
-                            })
-                            .await
-                            .is_ok()
-                        {
-                            return HttpResponse::Ok().json(PostCreatedResponse { slug });
-                        }
-                    }
-                }
-                _ => {
-                    return HttpResponse::Unauthorized().finish();
-                }
-            }
-        }
-    }
+    RequireCapability { user, .. }: RequireCapability<T, capability::CreatePost>,
+) -> Result<HttpResponse, DbError> {
+    let slug = generate_unique_slug(db_handler.as_ref(), &post.title).await?;
+    let new_post = Post {
+        slug: slug.clone(),
+        title: post.title.clone(),
+        content: post.content.clone(),
+        summary: post.summary.clone(),
+        author: user.username,
+        published_at: post.published_at.unwrap_or_else(Utc::now),
+        public: post.public,
+        tags: normalize_tags(&post.tags),
+    };
+    db_handler.create_post(&new_post).await?;
+    save_revision(db_handler.as_ref(), &new_post, &new_post.author).await?;
 
-    HttpResponse::InternalServerError().finish()
+    Ok(HttpResponse::Ok().json(PostCreatedResponse { slug }))
 }
 
 pub async fn update_post<T: DBHandler>(
     db_handler: web::Data<T>,
     post: web::Json<UpdatePostRequest>,
-    user: Identity,
-) -> impl Responder {
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    if db_handler
-                        .update_post(
-                            &post.slug,
-                            &post.content,
-                            &post.title,
-                            post.summary.as_deref(),
-                            post.public,
-                        )
-                        .await
-                        .is_ok()
-                    {
-                        return HttpResponse::Ok().json(PostCreatedResponse {
-                            slug: post.slug.clone(),
-                        });
-                    }
-                }
-                _ => {
-                    return HttpResponse::Unauthorized().finish();
-                }
-            }
+    RequireCapability { user, .. }: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let Some(current) = db_handler.get_post(&post.slug, true).await? else {
+        return Err(DbError::NotFound);
+    };
+    if !user.can_edit_post(&current) {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    let new_slug = post
+        .new_slug
+        .as_deref()
+        .map(title_to_slug)
+        .filter(|slug| *slug != current.slug);
+    if let Some(ref new_slug) = new_slug {
+        if new_slug.is_empty() {
+            return Ok(HttpResponse::BadRequest().body("slug"));
         }
+
+        // Taking back one of the post's own old slugs is fine
+        let redirect = db_handler.find_slug_redirect(new_slug).await?;
+        if db_handler.get_post(new_slug, true).await?.is_some()
+            || db_handler.get_trashed_post(new_slug).await?.is_some()
+            || redirect.is_some_and(|slug| slug != current.slug)
+        {
+            return Ok(HttpResponse::Conflict().body("slug"));
+        }
+    }
+
+    // Posts written before revisions existed get their original text saved first
+    if db_handler.get_revisions(&post.slug).await?.is_empty() {
+        save_revision(db_handler.as_ref(), &current, &current.author).await?;
     }
 
-    HttpResponse::InternalServerError().finish()
+    let updated = Post {
+        title: post.title.clone(),
+        content: post.content.clone(),
+        summary: post.summary.clone(),
+        public: post.public,
+        published_at: post.published_at.unwrap_or(current.published_at),
+        tags: normalize_tags(&post.tags),
+        ..current
+    };
+    db_handler.update_post(&updated).await?;
+    save_revision(db_handler.as_ref(), &updated, &user.username).await?;
+
+    let slug = match new_slug {
+        Some(new_slug) => {
+            db_handler.rename_post(&updated.slug, &new_slug).await?;
+            new_slug
+        }
+        None => updated.slug,
+    };
+
+    Ok(HttpResponse::Ok().json(PostCreatedResponse { slug }))
 }
 
 pub async fn delete_post<T: DBHandler>(
     db_handler: web::Data<T>,
     slug: web::Path<String>,
-    user: Identity,
-) -> impl Responder {
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    if let Ok(deleted_count) = db_handler.delete_post(&slug).await {
-                        return HttpResponse::Ok().json(deleted_count);
-                    }
-                }
-                _ => return HttpResponse::Unauthorized().finish(),
-            }
-        }
+    RequireCapability { user, .. }: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let Some(post) = db_handler.get_post(&slug, true).await? else {
+        return Err(DbError::NotFound);
+    };
+    if !user.can_edit_post(&post) {
+        return Ok(HttpResponse::Forbidden().finish());
     }
 
-    HttpResponse::InternalServerError().finish()
+    // Revisions, comments, old slugs and its place in a series stay until the
+    // post is purged
+    let deleted_count = db_handler
+        .trash_post(&slug, &user.username, Utc::now())
+        .await?;
+    Ok(HttpResponse::Ok().json(deleted_count))
 }
 
 pub async fn delete_post_and_redirect<T: DBHandler>(
     db_handler: web::Data<T>,
     slug: web::Path<String>,
-    user: Identity,
-) -> impl Responder {
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    if let Ok(_deleted_count) = db_handler.delete_post(&slug).await {
-                        return HttpResponse::Found()
-                            .append_header(("location", "/"))
-                            .finish();
-                    }
-                }
-                _ => return HttpResponse::Unauthorized().finish(),
-            }
-        }
+    RequireCapability { user, .. }: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let Some(post) = db_handler.get_post(&slug, true).await? else {
+        return Err(DbError::NotFound);
+    };
+    if !user.can_edit_post(&post) {
+        return Ok(HttpResponse::Forbidden().finish());
     }
 
-    HttpResponse::InternalServerError().finish()
+    db_handler
+        .trash_post(&slug, &user.username, Utc::now())
+        .await?;
+    Ok(HttpResponse::SeeOther()
+        .append_header(("location", "/"))
+        .finish())
+}
+
+pub async fn transfer_post<T: DBHandler>(
+    db_handler: web::Data<T>,
+    request: web::Json<TransferPostRequest>,
+    RequireCapability { user, .. }: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let Some(post) = db_handler.get_post(&request.slug, true).await? else {
+        return Err(DbError::NotFound);
+    };
+    if !user.can_edit_post(&post) {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    // The new author has to be someone who could have written the post
+    match db_handler.find_user(&request.author).await? {
+        Some(author) if author.role.can(Capability::EditOwnPost) => {}
+        _ => return Ok(HttpResponse::BadRequest().body("author")),
+    }
+
+    let updated_count = db_handler
+        .set_post_author(&post.slug, &request.author)
+        .await?;
+    Ok(HttpResponse::Ok().json(updated_count))
 }
 
 pub async fn get_post<T: DBHandler>(
     db_handler: web::Data<T>,
     slug: web::Path<String>,
-    user: Identity,
-) -> impl Responder {
-    let mut is_admin = false;
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    is_admin = true;
-                }
-                _ => {}
-            }
-        }
-    }
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
 
-    match db_handler.get_post(&slug, is_admin).await {
-        Ok(Some(post)) => HttpResponse::Ok().json(post),
-        Ok(None) => HttpResponse::NotFound().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    match db_handler.get_post(&slug, is_admin).await? {
+        Some(post) => Ok(HttpResponse::Ok().json(post)),
+        None => match find_redirect(db_handler.as_ref(), &slug, is_admin).await? {
+            Some(new_slug) => Ok(HttpResponse::MovedPermanently()
+                .append_header(("location", format!("/api/post/read/{}", new_slug)))
+                .finish()),
+            None => Err(DbError::NotFound),
+        },
     }
 }
 
 pub async fn get_posts<T: DBHandler>(
     db_handler: web::Data<T>,
     query: web::Query<PostsQueryParams>,
-    user: Identity,
-) -> impl Responder {
-    let mut is_admin = false;
-    if let Ok(user_id) = user.id() {
-        if let Ok(db_result) = db_handler.find_user(&user_id).await {
-            match db_result {
-                Some(db_user) if db_user.role == "Admin" || db_user.role == "Editor" => {
-                    is_admin = true;
-                }
-                _ => {}
-            }
-        }
-    }
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    let Some(cursor) = PageCursor::from_query(&query) else {
+        return Ok(HttpResponse::BadRequest().body("cursor"));
+    };
 
-    match db_handler.get_posts(&query, is_admin).await {
-        Ok(posts) => HttpResponse::Ok().json(GetPostsResponse {
-            posts,
-            pages: db_handler
-                .calculate_total_pages(query.per_page.unwrap_or(10))
-                .await,
-        }),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    match get_posts_page(db_handler.as_ref(), &query, &cursor, is_admin).await {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        // Well-formed cursors can still hold an id the database can't use
+        Err(DbError::InvalidCursor) => Ok(HttpResponse::BadRequest().body("cursor")),
+        Err(err) => Err(err),
     }
 }
+
+pub async fn search_posts<T: DBHandler>(
+    db_handler: web::Data<T>,
+    query: web::Query<SearchQueryParams>,
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    Ok(HttpResponse::Ok().json(get_search_results(db_handler.as_ref(), &query, is_admin).await?))
+}
+
+pub async fn get_tags<T: DBHandler>(
+    db_handler: web::Data<T>,
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    Ok(HttpResponse::Ok().json(db_handler.get_tags(is_admin).await?))
+}
@@ -0,0 +1,99 @@
+use actix_web::{web, HttpResponse};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{capability, save_revision, RequireCapability},
+};
+use common::{DiffLine, DiffTag, PostCreatedResponse, RevisionDiff, RevisionDiffQuery};
+
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => DiffTag::Equal,
+                ChangeTag::Insert => DiffTag::Insert,
+                ChangeTag::Delete => DiffTag::Delete,
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect()
+}
+
+pub async fn get_revisions<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    _: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    Ok(HttpResponse::Ok().json(db_handler.get_revisions(&slug).await?))
+}
+
+pub async fn get_revision<T: DBHandler>(
+    db_handler: web::Data<T>,
+    path: web::Path<(String, u64)>,
+    _: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let (slug, number) = path.into_inner();
+    match db_handler.get_revision(&slug, number).await? {
+        Some(revision) => Ok(HttpResponse::Ok().json(revision)),
+        None => Err(DbError::NotFound),
+    }
+}
+
+pub async fn get_revision_diff<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    query: web::Query<RevisionDiffQuery>,
+    _: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let from = db_handler.get_revision(&slug, query.from).await?;
+    let to = db_handler.get_revision(&slug, query.to).await?;
+
+    match (from, to) {
+        (Some(from), Some(to)) => Ok(HttpResponse::Ok().json(RevisionDiff {
+            from: from.number,
+            to: to.number,
+            title: diff_lines(&from.title, &to.title),
+            summary: diff_lines(
+                from.summary.as_deref().unwrap_or_default(),
+                to.summary.as_deref().unwrap_or_default(),
+            ),
+            content: diff_lines(&from.content, &to.content),
+        })),
+        _ => Err(DbError::NotFound),
+    }
+}
+
+pub async fn restore_revision<T: DBHandler>(
+    db_handler: web::Data<T>,
+    path: web::Path<(String, u64)>,
+    RequireCapability { user, .. }: RequireCapability<T, capability::EditOwnPost>,
+) -> Result<HttpResponse, DbError> {
+    let (slug, number) = path.into_inner();
+    let Some(revision) = db_handler.get_revision(&slug, number).await? else {
+        return Err(DbError::NotFound);
+    };
+    let Some(mut post) = db_handler.get_post(&slug, true).await? else {
+        return Err(DbError::NotFound);
+    };
+    if !user.can_edit_post(&post) {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    post.title = revision.title;
+    post.content = revision.content;
+    post.summary = revision.summary;
+    post.public = revision.public;
+    // Older revisions didn't keep these, so they are left as they are
+    if let Some(published_at) = revision.published_at {
+        post.published_at = published_at;
+    }
+    if let Some(tags) = revision.tags {
+        post.tags = tags;
+    }
+    db_handler.update_post(&post).await?;
+    save_revision(db_handler.as_ref(), &post, &user.username).await?;
+
+    Ok(HttpResponse::Ok().json(PostCreatedResponse { slug }))
+}
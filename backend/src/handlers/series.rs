@@ -0,0 +1,125 @@
+use actix_web::{web, HttpResponse};
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{
+        capability, generate_unique_series_slug, get_series_overview, CurrentUser,
+        RequireCapability,
+    },
+};
+use common::{Capability, CreateSeriesRequest, PostCreatedResponse, Series, UpdateSeriesRequest};
+
+/// Checks that every part exists, is listed once and isn't already in
+/// another series, returning the response to send otherwise. Parts in the
+/// trash can only stay in the series they were in.
+async fn invalid_parts<T: DBHandler>(
+    db_handler: &T,
+    series_slug: Option<&str>,
+    posts: &[String],
+) -> Result<Option<HttpResponse>, DbError> {
+    for (index, slug) in posts.iter().enumerate() {
+        let series = db_handler.find_series_by_post(slug).await?;
+        let in_this_series = series_slug.is_some()
+            && series.as_ref().map(|series| series.slug.as_str()) == series_slug;
+
+        let exists = db_handler.get_post(slug, true).await?.is_some()
+            || (in_this_series && db_handler.get_trashed_post(slug).await?.is_some());
+        if posts[..index].contains(slug) || !exists {
+            return Ok(Some(HttpResponse::BadRequest().body("posts")));
+        }
+
+        if series.is_some() && !in_this_series {
+            return Ok(Some(HttpResponse::Conflict().body("posts")));
+        }
+    }
+
+    Ok(None)
+}
+
+pub async fn create_series<T: DBHandler>(
+    db_handler: web::Data<T>,
+    series: web::Json<CreateSeriesRequest>,
+    _: RequireCapability<T, capability::ManageSeries>,
+) -> Result<HttpResponse, DbError> {
+    if let Some(response) = invalid_parts(db_handler.as_ref(), None, &series.posts).await? {
+        return Ok(response);
+    }
+
+    let slug = generate_unique_series_slug(db_handler.as_ref(), &series.title).await?;
+    db_handler
+        .create_series(&Series {
+            slug: slug.clone(),
+            title: series.title.clone(),
+            description: series.description.clone(),
+            posts: series.posts.clone(),
+        })
+        .await?;
+
+    Ok(HttpResponse::Ok().json(PostCreatedResponse { slug }))
+}
+
+pub async fn update_series<T: DBHandler>(
+    db_handler: web::Data<T>,
+    series: web::Json<UpdateSeriesRequest>,
+    _: RequireCapability<T, capability::ManageSeries>,
+) -> Result<HttpResponse, DbError> {
+    if db_handler.get_series(&series.slug).await?.is_none() {
+        return Err(DbError::NotFound);
+    }
+
+    if let Some(response) =
+        invalid_parts(db_handler.as_ref(), Some(&series.slug), &series.posts).await?
+    {
+        return Ok(response);
+    }
+
+    db_handler
+        .update_series(&Series {
+            slug: series.slug.clone(),
+            title: series.title.clone(),
+            description: series.description.clone(),
+            posts: series.posts.clone(),
+        })
+        .await?;
+
+    Ok(HttpResponse::Ok().json(PostCreatedResponse {
+        slug: series.slug.clone(),
+    }))
+}
+
+pub async fn delete_series<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    _: RequireCapability<T, capability::ManageSeries>,
+) -> Result<HttpResponse, DbError> {
+    let deleted_count = db_handler.delete_series(&slug).await?;
+    Ok(HttpResponse::Ok().json(deleted_count))
+}
+
+pub async fn get_series<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    match db_handler.get_series(&slug).await? {
+        Some(series) => Ok(HttpResponse::Ok()
+            .json(get_series_overview(db_handler.as_ref(), series, is_admin).await?)),
+        None => Err(DbError::NotFound),
+    }
+}
+
+pub async fn get_series_list<T: DBHandler>(
+    db_handler: web::Data<T>,
+    user: CurrentUser<T>,
+) -> Result<HttpResponse, DbError> {
+    let is_admin = user.can(Capability::ViewDrafts);
+
+    let mut overviews = Vec::new();
+    for series in db_handler.get_all_series().await? {
+        overviews.push(get_series_overview(db_handler.as_ref(), series, is_admin).await?);
+    }
+
+    Ok(HttpResponse::Ok().json(overviews))
+}
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpResponse};
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{capability, CurrentUser, RequireCapability},
+};
+use common::{Capability, SiteSettings};
+
+pub async fn get_settings<T: DBHandler>(
+    db_handler: web::Data<T>,
+    _: RequireCapability<T, capability::ManageUsers>,
+) -> Result<HttpResponse, DbError> {
+    Ok(HttpResponse::Ok().json(db_handler.get_settings().await?))
+}
+
+/// Like other security settings, only changed from a session.
+pub async fn update_settings<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    settings: web::Json<SiteSettings>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+    if !user.can(Capability::ManageUsers) {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    db_handler.save_settings(&settings).await?;
+
+    Ok(HttpResponse::Ok().json(settings.into_inner()))
+}
@@ -0,0 +1,41 @@
+use actix_web::{web, HttpResponse};
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{capability, purge_trashed_post, RequireCapability},
+};
+use common::PostCreatedResponse;
+
+pub async fn get_trash<T: DBHandler>(
+    db_handler: web::Data<T>,
+    _: RequireCapability<T, capability::ManageTrash>,
+) -> Result<HttpResponse, DbError> {
+    Ok(HttpResponse::Ok().json(db_handler.get_trashed_posts().await?))
+}
+
+pub async fn restore_post<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    _: RequireCapability<T, capability::ManageTrash>,
+) -> Result<HttpResponse, DbError> {
+    if db_handler.get_trashed_post(&slug).await?.is_none() {
+        return Err(DbError::NotFound);
+    }
+    if db_handler.get_post(&slug, true).await?.is_some() {
+        return Ok(HttpResponse::Conflict().body("slug"));
+    }
+
+    db_handler.restore_post(&slug).await?;
+
+    Ok(HttpResponse::Ok().json(PostCreatedResponse {
+        slug: slug.into_inner(),
+    }))
+}
+
+pub async fn purge_post<T: DBHandler>(
+    db_handler: web::Data<T>,
+    slug: web::Path<String>,
+    _: RequireCapability<T, capability::ManageTrash>,
+) -> Result<HttpResponse, DbError> {
+    Ok(HttpResponse::Ok().json(purge_trashed_post(db_handler.as_ref(), &slug).await?))
+}
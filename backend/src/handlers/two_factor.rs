@@ -0,0 +1,203 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use bcrypt::verify;
+use chrono::Utc;
+
+use super::auth::finish_login;
+use crate::{
+    database::{DBHandler, DbError},
+    models::{TwoFactor, User},
+    services::login_limiter::LoginLimiter,
+    utils::{
+        generate_recovery_codes, generate_secret, hash_recovery_code, is_two_factor_required,
+        pending_login, provisioning_uri, verify_code, CurrentUser,
+    },
+    Config,
+};
+use common::{
+    PasswordConfirmation, RecoveryCodes, TwoFactorCodeRequest, TwoFactorSetup, TwoFactorStatus,
+};
+
+/// Whose two-factor authentication is being managed: the logged in user, or
+/// one whose role requires it and who is setting it up to finish logging in.
+/// The flag tells the second case apart.
+async fn two_factor_user<T: DBHandler>(
+    db_handler: &T,
+    current_user: CurrentUser<T>,
+    request: &HttpRequest,
+) -> Result<Option<(User, bool)>, DbError> {
+    if let Some(user) = current_user.session_user() {
+        return Ok(Some((user, false)));
+    }
+
+    let Some(pending) = pending_login(request).filter(|pending| pending.setup) else {
+        return Ok(None);
+    };
+    Ok(db_handler
+        .find_user(&pending.username)
+        .await?
+        .map(|user| (user, true)))
+}
+
+/// New recovery codes for `two_factor`, which only keeps their hashes.
+fn renew_recovery_codes(two_factor: &mut TwoFactor) -> RecoveryCodes {
+    let codes = generate_recovery_codes();
+    two_factor.recovery_codes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+
+    RecoveryCodes { codes }
+}
+
+pub async fn get_two_factor_status<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    let Some((user, _)) = two_factor_user(db_handler.get_ref(), current_user, &request).await?
+    else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    let two_factor = db_handler
+        .find_two_factor(&user.username)
+        .await?
+        .filter(|two_factor| two_factor.enabled);
+
+    Ok(HttpResponse::Ok().json(TwoFactorStatus {
+        enabled: two_factor.is_some(),
+        required: is_two_factor_required(db_handler.get_ref(), user.role).await?,
+        recovery_codes_left: two_factor.map_or(0, |two_factor| two_factor.recovery_codes.len()),
+    }))
+}
+
+/// Starts enrolling with a new secret, which replaces any earlier one that
+/// wasn't enabled. Takes the password, so that a session left open can't be
+/// tied to someone else's authenticator app.
+pub async fn setup_two_factor<T: DBHandler>(
+    db_handler: web::Data<T>,
+    config: web::Data<Config>,
+    current_user: CurrentUser<T>,
+    confirmation: web::Json<PasswordConfirmation>,
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    let Some((user, _)) = two_factor_user(db_handler.get_ref(), current_user, &request).await?
+    else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    if !verify(&confirmation.password, &user.password).unwrap_or(false) {
+        return Ok(HttpResponse::BadRequest().body("password"));
+    }
+
+    if let Some(two_factor) = db_handler.find_two_factor(&user.username).await? {
+        if two_factor.enabled {
+            return Ok(HttpResponse::Conflict().finish());
+        }
+    }
+
+    let secret = generate_secret();
+    db_handler
+        .save_two_factor(&TwoFactor {
+            username: user.username.clone(),
+            secret: secret.clone(),
+            enabled: false,
+            recovery_codes: Vec::new(),
+            last_used_step: None,
+        })
+        .await?;
+
+    Ok(HttpResponse::Ok().json(TwoFactorSetup {
+        provisioning_uri: provisioning_uri(&config.RSS_TITLE, &user.username, &secret),
+        secret,
+    }))
+}
+
+/// Enables the secret from `setup_two_factor` once a code from it is entered,
+/// answering with the recovery codes. Users who were setting it up to log in
+/// are logged in.
+pub async fn enable_two_factor<T: DBHandler>(
+    db_handler: web::Data<T>,
+    login_limiter: web::Data<LoginLimiter>,
+    current_user: CurrentUser<T>,
+    code: web::Json<TwoFactorCodeRequest>,
+    request: HttpRequest,
+) -> Result<HttpResponse, DbError> {
+    let Some((user, logging_in)) =
+        two_factor_user(db_handler.get_ref(), current_user, &request).await?
+    else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    let Some(mut two_factor) = db_handler.find_two_factor(&user.username).await? else {
+        return Err(DbError::NotFound);
+    };
+    if two_factor.enabled {
+        return Ok(HttpResponse::Conflict().finish());
+    }
+
+    let Some(step) = verify_code(&two_factor.secret, &code.code, Utc::now()) else {
+        return Ok(HttpResponse::BadRequest().body("code"));
+    };
+
+    let recovery_codes = renew_recovery_codes(&mut two_factor);
+    two_factor.enabled = true;
+    two_factor.last_used_step = Some(step);
+    db_handler.save_two_factor(&two_factor).await?;
+
+    if logging_in {
+        let response = finish_login(&login_limiter, &request, &user.username).await;
+        if !response.status().is_success() {
+            return Ok(response);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(recovery_codes))
+}
+
+pub async fn disable_two_factor<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    confirmation: web::Json<PasswordConfirmation>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    if is_two_factor_required(db_handler.get_ref(), user.role).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    if !verify(&confirmation.password, &user.password).unwrap_or(false) {
+        return Ok(HttpResponse::BadRequest().body("password"));
+    }
+
+    match db_handler.delete_two_factor(&user.username).await? {
+        0 => Err(DbError::NotFound),
+        _ => Ok(HttpResponse::Ok().finish()),
+    }
+}
+
+/// Replaces the recovery codes, the old ones stop working.
+pub async fn renew_two_factor_recovery_codes<T: DBHandler>(
+    db_handler: web::Data<T>,
+    current_user: CurrentUser<T>,
+    confirmation: web::Json<PasswordConfirmation>,
+) -> Result<HttpResponse, DbError> {
+    let Some(user) = current_user.session_user() else {
+        return Ok(HttpResponse::Unauthorized().finish());
+    };
+
+    if !verify(&confirmation.password, &user.password).unwrap_or(false) {
+        return Ok(HttpResponse::BadRequest().body("password"));
+    }
+
+    let Some(mut two_factor) = db_handler
+        .find_two_factor(&user.username)
+        .await?
+        .filter(|two_factor| two_factor.enabled)
+    else {
+        return Err(DbError::NotFound);
+    };
+
+    let recovery_codes = renew_recovery_codes(&mut two_factor);
+    db_handler.save_two_factor(&two_factor).await?;
+
+    Ok(HttpResponse::Ok().json(recovery_codes))
+}
@@ -1,5 +1,5 @@
 use actix_multipart::form::{tempfile::TempFile, MultipartForm};
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder, ResponseError};
 use bson::uuid::Uuid;
 
 use common::UploadResponse;
@@ -26,12 +26,13 @@ pub async fn upload<T: DBHandler>(
     );
 
     if let Ok((_, path)) = file.file.keep() {
-        if db_handler.create_temp_file(&path, &filename).await.is_ok() {
-            return HttpResponse::Ok().json(UploadResponse {
+        return match db_handler.create_temp_file(&path, &filename).await {
+            Ok(_) => HttpResponse::Ok().json(UploadResponse {
                 parent_path: config.FILE_UPLOAD_URL.clone(),
                 filename,
-            });
-        }
+            }),
+            Err(err) => err.error_response(),
+        };
     };
 
     HttpResponse::BadRequest().finish()
@@ -0,0 +1,45 @@
+// The database traits are only implemented inside this crate, so their
+// futures never need extra `Send` bounds spelled out
+#![allow(async_fn_in_trait)]
+
+pub mod database;
+pub mod handlers;
+pub mod models;
+pub mod services;
+pub mod utils;
+
+create_env_struct! {
+    Config {
+        DATABASE_URL,
+        SMTP_SERVER,
+        SMTP_USERNAME,
+        SMTP_PASSWORD,
+        NEW_USER_DEFAULT_ROLE,
+        WEBSITE_URL,
+        RSS_TITLE,
+        RSS_DESCRIPTION,
+        REDIS_URL,
+        FILE_UPLOAD_PATH,
+        FILE_UPLOAD_URL,
+        // Other origins the site is served from, comma separated
+        ALLOWED_ORIGINS = "",
+        // How long deleted posts stay in the trash before they are purged
+        TRASH_RETENTION_DAYS = "30",
+        // Addresses of the reverse proxies whose forwarded client IPs are
+        // believed, comma separated
+        TRUSTED_PROXIES = "",
+        // See `LoginLimits`
+        LOGIN_MAX_IP_FAILURES = "50",
+        LOGIN_MAX_ACCOUNT_FAILURES = "10",
+        LOGIN_FAILURE_WINDOW_SECS = "3600",
+        LOGIN_BACKOFF_SECS = "1",
+        LOGIN_LOCKOUT_SECS = "900",
+        LOGIN_NOTIFY_OWNER_AFTER = "0",
+        // Password reset requests from one IP before it has to wait
+        PASSWORD_RESET_MAX_IP_REQUESTS = "10",
+        // Reset emails sent to one address, later requests are ignored
+        PASSWORD_RESET_MAX_EMAIL_REQUESTS = "3",
+        // How long reset requests are counted after the last one
+        PASSWORD_RESET_WINDOW_SECS = "3600",
+    }
+}
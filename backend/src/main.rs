@@ -5,37 +5,25 @@ use actix_web::{
     cookie::Key,
     http::header,
     middleware::{Logger, NormalizePath},
-    web::{self, Data},
+    web::Data,
     App, HttpServer,
 };
 use actix_web_lab::web::spa;
 
-mod database;
-mod handlers;
-mod models;
-mod services;
-mod utils;
-
-use database::mongo::MongoDBHandler;
+use backend::{
+    database::{memory::InMemoryDBHandler, mongo::MongoDBHandler, sql::SqlDBHandler, DBHandler},
+    handlers,
+    services::{
+        email::Emailer,
+        login_limiter::{LoginLimiter, LoginLimits},
+    },
+    utils::{purge_expired_trash, Highlighter, NewUserRole, SiteUrl},
+    Config,
+};
 use dotenv::dotenv;
-use services::email::Emailer;
-use utils::Highlighter;
 
-create_env_struct! {
-    Config {
-        DATABASE_URL,
-        SMTP_SERVER,
-        SMTP_USERNAME,
-        SMTP_PASSWORD,
-        NEW_USER_DEFAULT_ROLE,
-        WEBSITE_URL,
-        RSS_TITLE,
-        RSS_DESCRIPTION,
-        REDIS_URL,
-        FILE_UPLOAD_PATH,
-        FILE_UPLOAD_URL
-    }
-}
+/// How often trashed posts past their retention are looked for.
+const TRASH_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -44,10 +32,32 @@ async fn main() -> std::io::Result<()> {
 
     let config = Config::new();
 
-    let db_handler = database::mongo::MongoDBHandler::new(&config.DATABASE_URL, "rust_blog")
-        .await
-        .expect("Error creating database handler");
+    match config.DATABASE_URL.split(':').next() {
+        Some("memory") => {
+            log::warn!("Using the in-memory database, nothing will be persisted");
+            run(InMemoryDBHandler::new(), config).await
+        }
+        Some("sqlite" | "postgres" | "postgresql") => {
+            let db_handler = SqlDBHandler::new(&config.DATABASE_URL)
+                .await
+                .expect("Error creating database handler");
+
+            run(db_handler, config).await
+        }
+        _ => {
+            let db_handler = MongoDBHandler::new(&config.DATABASE_URL, "rust_blog")
+                .await
+                .expect("Error creating database handler");
+
+            run(db_handler, config).await
+        }
+    }
+}
 
+async fn run<T: DBHandler + Clone + Send + Sync + 'static>(
+    db_handler: T,
+    config: Config,
+) -> std::io::Result<()> {
     let emailer = Emailer::new(
         &config.SMTP_SERVER,
         &config.SMTP_USERNAME,
@@ -62,12 +72,38 @@ async fn main() -> std::io::Result<()> {
             .expect("Connection test with SMTP server failed");
     }
 
+    let new_user_role = NewUserRole::from_config(&config);
+    let trash_retention: i64 = config
+        .TRASH_RETENTION_DAYS
+        .parse()
+        .expect("`TRASH_RETENTION_DAYS` has to be a number of days");
+    {
+        let db_handler = db_handler.clone();
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(TRASH_PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match purge_expired_trash(&db_handler, chrono::Duration::days(trash_retention))
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(purged) => log::info!("Purged {} posts from the trash", purged),
+                    Err(err) => log::error!("Error purging the trash: {}", err),
+                }
+            }
+        });
+    }
+
     let highlighter = Highlighter::new();
+    let site_url = SiteUrl::new(&config.WEBSITE_URL, &config.ALLOWED_ORIGINS);
 
     let key = Key::generate();
     let redis_store = RedisSessionStore::new(&config.REDIS_URL)
         .await
         .expect("Can't connect to Redis");
+    let login_limiter = LoginLimiter::new(&config.REDIS_URL, LoginLimits::from_config(&config))
+        .await
+        .expect("Can't connect to Redis");
 
     HttpServer::new(move || {
         App::new()
@@ -85,64 +121,14 @@ async fn main() -> std::io::Result<()> {
             .wrap(actix_web::middleware::Compress::default())
             .wrap(IdentityMiddleware::default())
             .wrap(SessionMiddleware::new(redis_store.clone(), key.clone()))
-            .app_data(Data::new(db_handler.clone())) // MongoDB client
+            .app_data(Data::new(db_handler.clone())) // Database handler
             .app_data(Data::new(emailer.clone())) // Emailer service
             .app_data(Data::new(config.clone())) // Config env variables
             .app_data(Data::new(highlighter.clone()))
-            .service(web::resource("/rss").get(handlers::rss_feed_handler::<MongoDBHandler>))
-            .service(web::resource("/sitemap").get(handlers::rss_sitemap_handler::<MongoDBHandler>))
-            .service(
-                web::scope("/api")
-                    .service(
-                        web::scope("/auth")
-                            .service(
-                                web::resource("/register")
-                                    .post(handlers::register_user::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/confirm")
-                                    .post(handlers::confirm_user::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/login")
-                                    .post(handlers::login_user::<MongoDBHandler>),
-                            ),
-                    )
-                    .service(
-                        web::scope("/post")
-                            .service(
-                                web::resource("/get-list")
-                                    .get(handlers::get_posts::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/read/{slug}")
-                                    .get(handlers::get_post::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/create")
-                                    .post(handlers::create_post::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/update")
-                                    .post(handlers::update_post::<MongoDBHandler>),
-                            )
-                            .service(
-                                web::resource("/delete/{slug}")
-                                    .delete(handlers::delete_post::<MongoDBHandler>),
-                            ),
-                    )
-                    .service(web::resource("/upload").post(handlers::upload::<MongoDBHandler>))
-                    .service(web::resource("/highlight").post(handlers::highlight_code)),
-            )
-            .service(web::redirect("/", "/blog"))
-            .service(web::resource("/blog").get(handlers::yew_blog::<MongoDBHandler>))
-            .service(web::resource("/post/{slug}").get(handlers::yew_post::<MongoDBHandler>))
-            .service(web::resource("/logout").get(handlers::logout_user))
-            .service(
-                web::resource("/delete/{slug}")
-                    .get(handlers::delete_post_and_redirect::<MongoDBHandler>),
-            )
-            .service(web::resource("/robots.txt").get(handlers::robots))
+            .app_data(Data::new(site_url.clone()))
+            .app_data(Data::new(login_limiter.clone()))
+            .app_data(Data::new(new_user_role))
+            .configure(handlers::routes::<T>)
             .service(actix_files::Files::new(
                 &config.FILE_UPLOAD_URL,
                 &config.FILE_UPLOAD_PATH,
@@ -1,11 +1,67 @@
 use std::convert::From;
 
-use bson::serde_helpers::chrono_datetime_as_bson_datetime;
-use chrono::{DateTime, Utc};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bson::serde_helpers::{
+    chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional,
+};
+use chrono::{DateTime, Duration, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
+use crate::database::EXPIRE_AFTER_SECS;
+
 pub use common::PostsQueryParams;
+use common::{Capability, CommentStatus, Role};
+
+/// Position of a post in the listing, newest first. Posts published at the
+/// same time are ordered by `id`, the `_id` on MongoDB and the slug elsewhere.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PostCursor {
+    pub published_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl PostCursor {
+    /// Opaque form handed to clients.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.published_at.timestamp_millis(),
+            self.id
+        ))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (millis, id) = decoded.split_once(':')?;
+
+        Some(Self {
+            published_at: DateTime::from_timestamp_millis(millis.parse().ok()?)?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Where a page of the post listing starts.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PageCursor {
+    First,
+    /// The posts older than the cursor.
+    After(PostCursor),
+    /// The posts newer than the cursor.
+    Before(PostCursor),
+}
+
+impl PageCursor {
+    /// `None` when the cursor in `query` is malformed.
+    pub fn from_query(query: &PostsQueryParams) -> Option<Self> {
+        match (&query.after, &query.before) {
+            (Some(after), _) => PostCursor::decode(after).map(PageCursor::After),
+            (None, Some(before)) => PostCursor::decode(before).map(PageCursor::Before),
+            (None, None) => Some(PageCursor::First),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
 pub struct PostModel {
@@ -20,6 +76,18 @@ pub struct PostModel {
     pub published_at: DateTime<Utc>,
     #[serde(default)]
     pub public: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `Post::preview`, kept up to date on every save so listings can skip the content.
+    #[serde(default)]
+    pub preview: String,
+}
+
+impl PostModel {
+    /// Whether readers without an editor role may see the post right now.
+    pub fn is_visible(&self) -> bool {
+        self.public && self.published_at <= Utc::now()
+    }
 }
 
 impl From<PostModel> for common::Post {
@@ -32,6 +100,7 @@ impl From<PostModel> for common::Post {
             author: value.author,
             published_at: value.published_at,
             public: value.public,
+            tags: value.tags,
         }
     }
 }
@@ -40,6 +109,7 @@ impl From<common::Post> for PostModel {
     fn from(value: common::Post) -> Self {
         Self {
             id: None,
+            preview: value.preview(),
             slug: value.slug,
             title: value.title,
             content: value.content,
@@ -47,16 +117,188 @@ impl From<common::Post> for PostModel {
             author: value.author,
             published_at: value.published_at,
             public: value.public,
+            tags: value.tags,
+        }
+    }
+}
+
+/// The fields of `PostModel` read by listings.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+pub struct PostSummaryModel {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub slug: String,
+    pub title: String,
+    pub author: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub published_at: DateTime<Utc>,
+    #[serde(default)]
+    pub public: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub preview: String,
+}
+
+impl From<PostSummaryModel> for common::PostSummary {
+    fn from(value: PostSummaryModel) -> Self {
+        Self {
+            slug: value.slug,
+            title: value.title,
+            summary: value.preview,
+            author: value.author,
+            published_at: value.published_at,
+            public: value.public,
+            tags: value.tags,
+        }
+    }
+}
+
+impl From<&PostModel> for common::PostSummary {
+    fn from(value: &PostModel) -> Self {
+        Self {
+            slug: value.slug.clone(),
+            title: value.title.clone(),
+            summary: value.preview.clone(),
+            author: value.author.clone(),
+            published_at: value.published_at,
+            public: value.public,
+            tags: value.tags.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TrashedPostModel {
+    pub post: PostModel,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: String,
+}
+
+impl From<TrashedPostModel> for common::TrashedPost {
+    fn from(value: TrashedPostModel) -> Self {
+        Self {
+            post: value.post.into(),
+            deleted_at: value.deleted_at,
+            deleted_by: value.deleted_by,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PostRevisionModel {
+    pub slug: String,
+    pub number: u64,
+    pub author: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    pub title: String,
+    pub content: String,
+    pub summary: Option<String>,
+    pub public: bool,
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+impl From<PostRevisionModel> for common::PostRevision {
+    fn from(value: PostRevisionModel) -> Self {
+        Self {
+            slug: value.slug,
+            number: value.number,
+            author: value.author,
+            created_at: value.created_at,
+            title: value.title,
+            content: value.content,
+            summary: value.summary,
+            public: value.public,
+            published_at: value.published_at,
+            tags: value.tags,
+        }
+    }
+}
+
+impl From<common::PostRevision> for PostRevisionModel {
+    fn from(value: common::PostRevision) -> Self {
+        Self {
+            slug: value.slug,
+            number: value.number,
+            author: value.author,
+            created_at: value.created_at,
+            title: value.title,
+            content: value.content,
+            summary: value.summary,
+            public: value.public,
+            published_at: value.published_at,
+            tags: value.tags,
         }
     }
 }
 
+/// A slug that used to point to the post now at `slug`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SlugHistoryModel {
+    pub old_slug: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CommentModel {
+    pub id: String,
+    pub post_slug: String,
+    pub parent_id: Option<String>,
+    pub author: String,
+    pub content: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    pub edited_at: Option<DateTime<Utc>>,
+    pub status: CommentStatus,
+}
+
+impl From<CommentModel> for common::Comment {
+    fn from(value: CommentModel) -> Self {
+        Self {
+            id: value.id,
+            post_slug: value.post_slug,
+            parent_id: value.parent_id,
+            author: value.author,
+            content: value.content,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+            status: value.status,
+        }
+    }
+}
+
+impl From<common::Comment> for CommentModel {
+    fn from(value: common::Comment) -> Self {
+        Self {
+            id: value.id,
+            post_slug: value.post_slug,
+            parent_id: value.parent_id,
+            author: value.author,
+            content: value.content,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+            status: value.status,
+        }
+    }
+}
+
+/// Body of the comment forms rendered under `PostPage`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentForm {
+    pub content: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserRegistration {
     pub username: String,
     pub email: String,
     pub password: String,
-    pub host: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,23 +306,60 @@ pub struct UserConfirmation {
     pub confirmation_token: String,
 }
 
+/// Where to email a link to, for password resets and new confirmation links.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailLinkRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordResetForm {
+    pub token: String,
+    pub password: String,
+}
+
 #[derive(Default, Deserialize, Serialize, Clone)]
 pub struct UserLogin {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
     pub username: String,
     pub email: String,
     pub password: String, // This will be hashed
-    pub role: String,
+    pub role: Role,
+    /// Sessions started before this are no longer valid.
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    pub password_changed_at: Option<DateTime<Utc>>,
+    /// What the request is limited to when it was made with an API token
+    /// rather than a session. Never stored.
+    #[serde(skip)]
+    pub token_scopes: Option<Vec<Capability>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl User {
+    /// Whether the role grants `capability`, and the API token the request
+    /// was made with, if any, has it in its scopes.
+    pub fn can(&self, capability: Capability) -> bool {
+        self.role.can(capability)
+            && self
+                .token_scopes
+                .as_ref()
+                .is_none_or(|scopes| scopes.contains(&capability))
+    }
+
+    /// Admins may change any post, Editors only the ones they wrote.
+    pub fn can_edit_post(&self, post: &common::Post) -> bool {
+        self.can(Capability::EditAnyPost)
+            || (post.author == self.username && self.can(Capability::EditOwnPost))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UnconfirmedUser {
     pub confirmation_token: String,
     pub host: String,
@@ -90,7 +369,14 @@ pub struct UnconfirmedUser {
     pub username: String,
     pub email: String,
     pub password: String, // This will be hashed
-    pub role: String,
+    pub role: Role,
+}
+
+impl UnconfirmedUser {
+    /// Whether the confirmation link stopped working, confirmed or not.
+    pub fn is_expired(&self) -> bool {
+        self.created_at <= Utc::now() - Duration::seconds(EXPIRE_AFTER_SECS as i64)
+    }
 }
 
 impl From<UnconfirmedUser> for User {
@@ -101,11 +387,76 @@ impl From<UnconfirmedUser> for User {
             email: value.email,
             password: value.password,
             role: value.role,
+            password_changed_at: None,
+            token_scopes: None,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A pending password reset, usable once until it expires.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordReset {
+    /// SHA-256 hash of the token, which is only sent in the email.
+    pub token_hash: String,
+    pub username: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// The TOTP second factor of a user. It only applies once `enabled`, after
+/// the user entered a first code from their authenticator app.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TwoFactor {
+    pub username: String,
+    /// Base32 encoded, as in the provisioning URI.
+    pub secret: String,
+    pub enabled: bool,
+    /// SHA-256 hashes of the recovery codes that weren't used yet.
+    pub recovery_codes: Vec<String>,
+    /// The time step of the last code accepted, so that each code only works
+    /// once.
+    pub last_used_step: Option<i64>,
+}
+
+/// A personal API token, which logs requests in as `username` through an
+/// `Authorization: Bearer` header.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiTokenModel {
+    pub id: String,
+    pub username: String,
+    pub name: String,
+    /// SHA-256 hash of the token, which is only shown when it is created.
+    pub token_hash: String,
+    pub scopes: Vec<Capability>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiTokenModel {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
+
+impl From<ApiTokenModel> for common::ApiToken {
+    fn from(value: ApiTokenModel) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            scopes: value.scopes,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TempFileModel {
     pub filename: String,
     pub path: String,
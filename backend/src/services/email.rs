@@ -1,13 +1,19 @@
 use lettre::{
     message::{header::ContentType, MultiPart, SinglePart},
-    transport::smtp::authentication::Credentials,
+    transport::{smtp::authentication::Credentials, stub::AsyncStubTransport},
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
 use std::error::Error;
 
+#[derive(Clone)]
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    Stub(AsyncStubTransport),
+}
+
 #[derive(Clone)]
 pub struct Emailer {
-    smtp_client: AsyncSmtpTransport<Tokio1Executor>,
+    transport: Transport,
     from_email: String,
 }
 
@@ -29,13 +35,43 @@ impl Emailer {
             .build();
 
         Ok(Self {
-            smtp_client,
+            transport: Transport::Smtp(smtp_client),
             from_email,
         })
     }
 
+    /// An emailer that keeps what it is asked to send instead of sending it,
+    /// for tests to read back with [`Emailer::sent_emails`].
+    pub fn stub(from_email: &str) -> Self {
+        Self {
+            transport: Transport::Stub(AsyncStubTransport::new_ok()),
+            from_email: from_email.to_string(),
+        }
+    }
+
+    /// The recipients and raw contents of the emails sent through a stub
+    /// emailer, oldest first. Always empty for one that uses SMTP.
+    pub async fn sent_emails(&self) -> Vec<(Vec<String>, String)> {
+        let Transport::Stub(ref stub) = self.transport else {
+            return Vec::new();
+        };
+
+        stub.messages()
+            .await
+            .into_iter()
+            .map(|(envelope, email)| {
+                let to = envelope.to().iter().map(ToString::to_string).collect();
+                (to, email)
+            })
+            .collect()
+    }
+
     pub async fn test_connection(&self) -> Result<(), SmtpConnectionError> {
-        match self.smtp_client.test_connection().await {
+        let Transport::Smtp(ref smtp_client) = self.transport else {
+            return Ok(());
+        };
+
+        match smtp_client.test_connection().await {
             Ok(true) => Ok(()),
             something => {
                 println!("{:?}", something);
@@ -69,7 +105,12 @@ impl Emailer {
                     ),
             )?;
 
-        self.smtp_client.send(email).await?;
+        match self.transport {
+            Transport::Smtp(ref smtp_client) => {
+                smtp_client.send(email).await?;
+            }
+            Transport::Stub(ref stub) => stub.send(email).await?,
+        }
         Ok(())
     }
 
@@ -84,4 +125,37 @@ impl Emailer {
         self.send_email(to, "Registration confirmation", &plain_text, &html)
             .await
     }
+
+    pub async fn send_password_reset_email(
+        &self,
+        to: &str,
+        link: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let plain_text = format!(include_str!("templates/password_reset.txt"), link = link);
+        let html = format!(include_str!("templates/password_reset.html"), link = link);
+
+        self.send_email(to, "Password reset", &plain_text, &html)
+            .await
+    }
+
+    pub async fn send_failed_logins_email(
+        &self,
+        to: &str,
+        failures: u64,
+        link: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let plain_text = format!(
+            include_str!("templates/failed_logins.txt"),
+            failures = failures,
+            link = link
+        );
+        let html = format!(
+            include_str!("templates/failed_logins.html"),
+            failures = failures,
+            link = link
+        );
+
+        self.send_email(to, "Failed logins to your account", &plain_text, &html)
+            .await
+    }
 }
@@ -0,0 +1,391 @@
+use redis::{aio::ConnectionManager, Client, RedisResult};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::Config;
+
+/// How many failed logins and password reset requests are tolerated and what
+/// happens after them. Each value comes from the `Config` field named on it.
+#[derive(Clone, Debug)]
+pub struct LoginLimits {
+    /// `LOGIN_MAX_IP_FAILURES`, failed logins from one IP, whatever the
+    /// account, before the IP is locked out.
+    pub max_ip_failures: u64,
+    /// `LOGIN_MAX_ACCOUNT_FAILURES`, failed logins on one account before it
+    /// is locked out.
+    pub max_account_failures: u64,
+    /// `LOGIN_FAILURE_WINDOW_SECS`, how long failures are remembered after
+    /// the last one.
+    pub failure_window: Duration,
+    /// `LOGIN_BACKOFF_SECS`, the wait after the first failure on an account,
+    /// doubled with every failure after it.
+    pub backoff: Duration,
+    /// `LOGIN_LOCKOUT_SECS`, how long a lockout lasts, and the longest the
+    /// backoff gets.
+    pub lockout: Duration,
+    /// `LOGIN_NOTIFY_OWNER_AFTER`, the number of failures after which the
+    /// owner of the account gets an email. No email is sent when it is 0.
+    pub notify_owner_after: Option<u64>,
+    /// `PASSWORD_RESET_MAX_IP_REQUESTS`, password reset requests from one IP,
+    /// whatever the address, before the IP has to wait.
+    pub max_ip_reset_requests: u64,
+    /// `PASSWORD_RESET_MAX_EMAIL_REQUESTS`, reset emails sent to one address.
+    /// Requests past it are answered as usual, without sending one.
+    pub max_email_reset_requests: u64,
+    /// `PASSWORD_RESET_WINDOW_SECS`, how long reset requests are remembered
+    /// after the last one.
+    pub reset_window: Duration,
+}
+
+impl LoginLimits {
+    /// The limits set in `config`, which has a default for each of them.
+    pub fn from_config(config: &Config) -> Self {
+        let number = |value: &str, name: &str| -> u64 {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("`{}` has to be a number", name))
+        };
+        let secs = |value: &str, name: &str| Duration::from_secs(number(value, name));
+
+        Self {
+            max_ip_failures: number(&config.LOGIN_MAX_IP_FAILURES, "LOGIN_MAX_IP_FAILURES"),
+            max_account_failures: number(
+                &config.LOGIN_MAX_ACCOUNT_FAILURES,
+                "LOGIN_MAX_ACCOUNT_FAILURES",
+            ),
+            failure_window: secs(
+                &config.LOGIN_FAILURE_WINDOW_SECS,
+                "LOGIN_FAILURE_WINDOW_SECS",
+            ),
+            backoff: secs(&config.LOGIN_BACKOFF_SECS, "LOGIN_BACKOFF_SECS"),
+            lockout: secs(&config.LOGIN_LOCKOUT_SECS, "LOGIN_LOCKOUT_SECS"),
+            notify_owner_after: Some(number(
+                &config.LOGIN_NOTIFY_OWNER_AFTER,
+                "LOGIN_NOTIFY_OWNER_AFTER",
+            ))
+            .filter(|after| *after > 0),
+            max_ip_reset_requests: number(
+                &config.PASSWORD_RESET_MAX_IP_REQUESTS,
+                "PASSWORD_RESET_MAX_IP_REQUESTS",
+            ),
+            max_email_reset_requests: number(
+                &config.PASSWORD_RESET_MAX_EMAIL_REQUESTS,
+                "PASSWORD_RESET_MAX_EMAIL_REQUESTS",
+            ),
+            reset_window: secs(
+                &config.PASSWORD_RESET_WINDOW_SECS,
+                "PASSWORD_RESET_WINDOW_SECS",
+            ),
+        }
+    }
+
+    /// How long an account has to wait after its `failures`th failed login.
+    fn account_wait(&self, failures: u64) -> Duration {
+        if failures >= self.max_account_failures {
+            return self.lockout;
+        }
+
+        let factor = 1u32.checked_shl(failures.saturating_sub(1) as u32);
+        factor
+            .and_then(|factor| self.backoff.checked_mul(factor))
+            .map_or(self.lockout, |wait| wait.min(self.lockout))
+    }
+}
+
+/// What a failed login led to.
+pub struct LoginFailure {
+    /// Failed logins on the account within the window, this one included.
+    pub account_failures: u64,
+    /// How long the next attempt has to wait, if it has to.
+    pub retry_after: Option<Duration>,
+    /// Whether this failure is the one the owner of the account should hear
+    /// about.
+    pub notify_owner: bool,
+}
+
+/// Whether a password reset email may be sent.
+#[derive(Debug, PartialEq)]
+pub enum ResetRequest {
+    Allowed,
+    /// The IP asked for too many resets and has to wait this long.
+    IpLimited(Duration),
+    /// The address got enough emails for now. The client isn't told, or it
+    /// would learn that the address has an account.
+    EmailThrottled,
+}
+
+/// Where the counts and waits are kept. Every value expires on its own.
+#[derive(Clone)]
+enum Store {
+    Redis(ConnectionManager),
+    /// Values and when they expire, for a single process that has no Redis,
+    /// like the tests. Expired values are dropped on each write.
+    Memory(Arc<Mutex<HashMap<String, (u64, Instant)>>>),
+}
+
+/// Drops the values of the memory store that expired by `now`, otherwise
+/// every IP and username ever tried would stay around.
+fn prune(values: &mut HashMap<String, (u64, Instant)>, now: Instant) {
+    values.retain(|_, (_, expires)| *expires > now);
+}
+
+impl Store {
+    /// Adds one to each of `keys` and has them expire `window` after this.
+    async fn incr(&self, keys: &[&str], window: Duration) -> RedisResult<Vec<u64>> {
+        match self {
+            Self::Redis(redis) => {
+                let window = window.as_secs().max(1) as i64;
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+                for key in keys {
+                    pipe.incr(*key, 1).expire(*key, window).ignore();
+                }
+                pipe.query_async(&mut redis.clone()).await
+            }
+            Self::Memory(values) => {
+                let now = Instant::now();
+                let mut values = values.lock().unwrap();
+                prune(&mut values, now);
+                Ok(keys
+                    .iter()
+                    .map(|key| {
+                        let value = values.entry(key.to_string()).or_insert((0, now));
+                        *value = (value.0 + 1, now + window);
+                        value.0
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// How long until each of `keys` expires, zero for the ones that are gone.
+    async fn ttl(&self, keys: &[&str]) -> RedisResult<Vec<Duration>> {
+        match self {
+            Self::Redis(redis) => {
+                let mut pipe = redis::pipe();
+                for key in keys {
+                    pipe.pttl(*key);
+                }
+                let ttls: Vec<i64> = pipe.query_async(&mut redis.clone()).await?;
+
+                // Keys that don't exist have a negative ttl
+                Ok(ttls
+                    .into_iter()
+                    .map(|ttl| Duration::from_millis(ttl.max(0) as u64))
+                    .collect())
+            }
+            Self::Memory(values) => {
+                let now = Instant::now();
+                let values = values.lock().unwrap();
+                Ok(keys
+                    .iter()
+                    .map(|key| {
+                        values.get(*key).map_or(Duration::ZERO, |(_, expires)| {
+                            expires.saturating_duration_since(now)
+                        })
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Sets each key to expire after the duration next to it.
+    async fn set(&self, keys: &[(&str, Duration)]) -> RedisResult<()> {
+        match self {
+            Self::Redis(redis) => {
+                let mut pipe = redis::pipe();
+                for (key, ttl) in keys {
+                    pipe.pset_ex(*key, 1, ttl.as_millis() as u64).ignore();
+                }
+                pipe.query_async(&mut redis.clone()).await
+            }
+            Self::Memory(values) => {
+                let now = Instant::now();
+                let mut values = values.lock().unwrap();
+                prune(&mut values, now);
+                for (key, ttl) in keys {
+                    values.insert(key.to_string(), (1, now + *ttl));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn del(&self, keys: &[&str]) -> RedisResult<()> {
+        match self {
+            Self::Redis(redis) => {
+                let mut pipe = redis::pipe();
+                for key in keys {
+                    pipe.del(*key).ignore();
+                }
+                pipe.query_async(&mut redis.clone()).await
+            }
+            Self::Memory(values) => {
+                let mut values = values.lock().unwrap();
+                for key in keys {
+                    values.remove(*key);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Counts failed logins per IP and per account in Redis, and makes them wait
+/// longer after each failure until they are locked out.
+#[derive(Clone)]
+pub struct LoginLimiter {
+    store: Store,
+    limits: LoginLimits,
+}
+
+const IP: &str = "ip";
+const ACCOUNT: &str = "account";
+const EMAIL: &str = "email";
+
+fn failures_key(kind: &str, id: &str) -> String {
+    format!("login:failures:{}:{}", kind, id)
+}
+
+fn wait_key(kind: &str, id: &str) -> String {
+    format!("login:wait:{}:{}", kind, id)
+}
+
+fn reset_key(kind: &str, id: &str) -> String {
+    format!("reset:requests:{}:{}", kind, id)
+}
+
+/// Accounts are counted case insensitively so that changing the case of the
+/// username doesn't start a new count.
+fn account_id(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+impl LoginLimiter {
+    pub async fn new(redis_url: &str, limits: LoginLimits) -> RedisResult<Self> {
+        let redis = ConnectionManager::new(Client::open(redis_url)?).await?;
+
+        Ok(Self {
+            store: Store::Redis(redis),
+            limits,
+        })
+    }
+
+    /// A limiter that keeps its counts in this process instead of Redis, so
+    /// they aren't shared with other instances and are lost on restart.
+    pub fn in_memory(limits: LoginLimits) -> Self {
+        Self {
+            store: Store::Memory(Arc::default()),
+            limits,
+        }
+    }
+
+    /// How long a login from `ip` to `username` has to wait before it can be
+    /// tried, `None` when it can be tried right away.
+    pub async fn retry_after(&self, ip: &str, username: &str) -> RedisResult<Option<Duration>> {
+        let waits = self
+            .store
+            .ttl(&[&wait_key(IP, ip), &wait_key(ACCOUNT, &account_id(username))])
+            .await?;
+
+        let wait = waits.into_iter().max().unwrap_or_default();
+        Ok((!wait.is_zero()).then_some(wait))
+    }
+
+    /// Counts a failed login from `ip` to `username`, which doesn't need to
+    /// be an existing account, and starts the wait that comes after it.
+    pub async fn record_failure(&self, ip: &str, username: &str) -> RedisResult<LoginFailure> {
+        let account = account_id(username);
+
+        let failures = self
+            .store
+            .incr(
+                &[&failures_key(IP, ip), &failures_key(ACCOUNT, &account)],
+                self.limits.failure_window,
+            )
+            .await?;
+        let (ip_failures, account_failures) = (failures[0], failures[1]);
+
+        let ip_wait_key = wait_key(IP, ip);
+        let account_wait_key = wait_key(ACCOUNT, &account);
+        let mut waits = Vec::new();
+        let mut retry_after = Duration::ZERO;
+        if ip_failures >= self.limits.max_ip_failures {
+            retry_after = self.limits.lockout;
+            waits.push((ip_wait_key.as_str(), retry_after));
+        }
+        let account_wait = self.limits.account_wait(account_failures);
+        if !account_wait.is_zero() {
+            retry_after = retry_after.max(account_wait);
+            waits.push((account_wait_key.as_str(), account_wait));
+        }
+        self.store.set(&waits).await?;
+
+        Ok(LoginFailure {
+            account_failures,
+            retry_after: (!retry_after.is_zero()).then_some(retry_after),
+            notify_owner: self.limits.notify_owner_after == Some(account_failures),
+        })
+    }
+
+    /// Forgets the failed logins on `username`. The ones from the IP are kept,
+    /// or logging into an account of one's own would clear them.
+    pub async fn record_success(&self, username: &str) -> RedisResult<()> {
+        let account = account_id(username);
+
+        self.store
+            .del(&[
+                &failures_key(ACCOUNT, &account),
+                &wait_key(ACCOUNT, &account),
+            ])
+            .await
+    }
+
+    /// Counts a request from `ip` for a password reset email to `email`,
+    /// which is counted whether it belongs to an account or not.
+    pub async fn record_reset_request(&self, ip: &str, email: &str) -> RedisResult<ResetRequest> {
+        let ip_key = reset_key(IP, ip);
+        let window = self.limits.reset_window;
+
+        let ip_requests = self.store.incr(&[&ip_key], window).await?[0];
+        if ip_requests > self.limits.max_ip_reset_requests {
+            let wait = self.store.ttl(&[&ip_key]).await?[0];
+            return Ok(ResetRequest::IpLimited(wait));
+        }
+
+        let email_requests = self.store.incr(&[&reset_key(EMAIL, email)], window).await?[0];
+        if email_requests > self.limits.max_email_reset_requests {
+            return Ok(ResetRequest::EmailThrottled);
+        }
+
+        Ok(ResetRequest::Allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn the_memory_store_forgets_expired_keys() {
+        let store = Store::Memory(Arc::default());
+        let Store::Memory(values) = &store else {
+            unreachable!()
+        };
+
+        store.incr(&["a", "b"], Duration::ZERO).await.unwrap();
+        assert_eq!(values.lock().unwrap().len(), 2);
+
+        // Counting again starts over, and the other expired key is gone
+        let minute = Duration::from_secs(60);
+        assert_eq!(store.incr(&["a"], minute).await.unwrap(), [1]);
+        assert_eq!(values.lock().unwrap().len(), 1);
+        store.set(&[("c", Duration::ZERO)]).await.unwrap();
+        assert_eq!(store.incr(&["a"], minute).await.unwrap(), [2]);
+        assert_eq!(values.lock().unwrap().len(), 1);
+        assert_eq!(store.ttl(&["b", "c"]).await.unwrap(), [Duration::ZERO; 2]);
+    }
+}
@@ -0,0 +1,22 @@
+use super::generate_random_alphanumeric_str;
+
+/// Starts every token, so that they are easy to recognize when leaked.
+const TOKEN_PREFIX: &str = "blog_";
+const TOKEN_LENGTH: usize = 40;
+const ID_LENGTH: usize = 16;
+/// Longest name a token may be given.
+pub const MAX_API_TOKEN_NAME_LENGTH: usize = 100;
+
+/// A new token, shown to its owner once and only stored hashed.
+pub fn generate_api_token() -> String {
+    format!(
+        "{}{}",
+        TOKEN_PREFIX,
+        generate_random_alphanumeric_str(TOKEN_LENGTH)
+    )
+}
+
+/// The public id of a token, used to list and revoke it.
+pub fn generate_api_token_id() -> String {
+    generate_random_alphanumeric_str(ID_LENGTH)
+}
@@ -0,0 +1,423 @@
+use std::io::{self, Read};
+use std::path::{Component, Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{DBHandler, DbError},
+    models::{PageCursor, PostsQueryParams},
+    utils::{is_slug_taken, save_revision},
+};
+use common::{
+    utils::{normalize_tags, title_to_slug},
+    Comment, ImportOutcome, ImportReport, ImportedFile, Post, Series,
+};
+
+const POSTS_DIR: &str = "posts";
+/// One YAML list of comments per post, named after the post.
+const COMMENTS_DIR: &str = "comments";
+const SERIES_DIR: &str = "series";
+const UPLOADS_DIR: &str = "uploads";
+/// Posts read from the database at a time while exporting.
+const EXPORT_BATCH_SIZE: u64 = 100;
+
+/// YAML header at the top of every exported post.
+#[derive(Serialize, Deserialize)]
+struct FrontMatter {
+    title: String,
+    slug: String,
+    #[serde(default)]
+    summary: Option<String>,
+    author: String,
+    published_at: DateTime<Utc>,
+    #[serde(default)]
+    public: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+pub enum ImportError {
+    Db(DbError),
+    /// The upload isn't a readable tar archive.
+    Archive(io::Error),
+}
+
+impl From<DbError> for ImportError {
+    fn from(value: DbError) -> Self {
+        ImportError::Db(value)
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(value: io::Error) -> Self {
+        ImportError::Archive(value)
+    }
+}
+
+/// Splits `text` into the front matter between two `delimiter` lines and
+/// the body after it.
+pub fn split_front_matter<'a>(text: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let rest = text
+        .trim_start_matches('\u{feff}')
+        .strip_prefix(delimiter)?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            let body = &rest[offset + line.len()..];
+            return Some((&rest[..offset], body.trim_start_matches(['\r', '\n'])));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn post_to_markdown(post: &Post) -> Result<String, serde_yaml::Error> {
+    let front_matter = serde_yaml::to_string(&FrontMatter {
+        title: post.title.clone(),
+        slug: post.slug.clone(),
+        summary: post.summary.clone(),
+        author: post.author.clone(),
+        published_at: post.published_at,
+        public: post.public,
+        tags: post.tags.clone(),
+    })?;
+
+    Ok(format!("---\n{}---\n\n{}", front_matter, post.content))
+}
+
+fn markdown_to_post(text: &str) -> Result<Post, String> {
+    let (front_matter, content) = split_front_matter(text, "---").ok_or("missing front matter")?;
+    let front_matter: FrontMatter =
+        serde_yaml::from_str(front_matter).map_err(|err| err.to_string())?;
+
+    let slug = match title_to_slug(&front_matter.slug) {
+        slug if slug.is_empty() => title_to_slug(&front_matter.title),
+        slug => slug,
+    };
+    if slug.is_empty() {
+        return Err(String::from("missing slug"));
+    }
+
+    Ok(Post {
+        slug,
+        title: front_matter.title,
+        content: content.to_string(),
+        summary: front_matter.summary,
+        author: front_matter.author,
+        published_at: front_matter.published_at,
+        public: front_matter.public,
+        tags: normalize_tags(&front_matter.tags),
+    })
+}
+
+/// Names of the uploaded files linked from `content` through `upload_url`.
+pub fn referenced_uploads(content: &str, upload_url: &str) -> Vec<String> {
+    let prefix = format!("{}/", upload_url.trim_end_matches('/'));
+    let mut filenames: Vec<String> = Vec::new();
+
+    for (index, _) in content.match_indices(&prefix) {
+        let filename: String = content[index + prefix.len()..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !"()[]<>\"'?#".contains(*c))
+            .collect();
+
+        if is_plain_filename(&filename) && !filenames.contains(&filename) {
+            filenames.push(filename);
+        }
+    }
+
+    filenames
+}
+
+/// Whether `filename` stays inside the directory it is joined to.
+fn is_plain_filename(filename: &str) -> bool {
+    let mut components = Path::new(filename).components();
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+fn append_file<W: io::Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+    modified: DateTime<Utc>,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(modified.timestamp().max(0) as u64);
+    header.set_cksum();
+
+    archive.append_data(&mut header, path, data)
+}
+
+fn to_yaml<T: Serialize>(value: &T) -> Result<String, DbError> {
+    serde_yaml::to_string(value).map_err(|err| DbError::Serialization(err.into()))
+}
+
+/// Writes every post as Markdown with YAML front matter into a tar archive
+/// streamed to `out`, along with their comments, the series and the uploaded
+/// files the posts link to.
+pub async fn export_archive<W: io::Write>(
+    db_handler: &impl DBHandler,
+    upload_path: &str,
+    upload_url: &str,
+    out: W,
+) -> Result<W, DbError> {
+    let archive_error = |err: io::Error| DbError::Other(err.into());
+    let mut archive = tar::Builder::new(out);
+    let mut uploads: Vec<String> = Vec::new();
+
+    let query = PostsQueryParams {
+        per_page: Some(EXPORT_BATCH_SIZE),
+        ..Default::default()
+    };
+    let mut cursor = PageCursor::First;
+    loop {
+        let page = db_handler
+            .get_posts_page(&query, &cursor, EXPORT_BATCH_SIZE, true)
+            .await?;
+
+        for (summary, _) in page.iter() {
+            let Some(post) = db_handler.get_post(&summary.slug, true).await? else {
+                continue;
+            };
+
+            let markdown =
+                post_to_markdown(&post).map_err(|err| DbError::Serialization(err.into()))?;
+            append_file(
+                &mut archive,
+                &format!("{}/{}.md", POSTS_DIR, post.slug),
+                markdown.as_bytes(),
+                post.published_at,
+            )
+            .map_err(archive_error)?;
+
+            let comments = db_handler.get_comments(&post.slug).await?;
+            if !comments.is_empty() {
+                append_file(
+                    &mut archive,
+                    &format!("{}/{}.yaml", COMMENTS_DIR, post.slug),
+                    to_yaml(&comments)?.as_bytes(),
+                    Utc::now(),
+                )
+                .map_err(archive_error)?;
+            }
+
+            for filename in referenced_uploads(&post.content, upload_url) {
+                if !uploads.contains(&filename) {
+                    uploads.push(filename);
+                }
+            }
+        }
+
+        match page.last() {
+            Some((_, last)) if page.len() as u64 == EXPORT_BATCH_SIZE => {
+                cursor = PageCursor::After(last.clone());
+            }
+            _ => break,
+        }
+    }
+
+    for series in db_handler.get_all_series().await? {
+        append_file(
+            &mut archive,
+            &format!("{}/{}.yaml", SERIES_DIR, series.slug),
+            to_yaml(&series)?.as_bytes(),
+            Utc::now(),
+        )
+        .map_err(archive_error)?;
+    }
+
+    for filename in uploads {
+        match std::fs::read(Path::new(upload_path).join(&filename)) {
+            Ok(data) => append_file(
+                &mut archive,
+                &format!("{}/{}", UPLOADS_DIR, filename),
+                &data,
+                Utc::now(),
+            )
+            .map_err(archive_error)?,
+            Err(err) => log::warn!("Uploaded file {} left out of the export: {}", filename, err),
+        }
+    }
+
+    archive.into_inner().map_err(archive_error)
+}
+
+/// Creates `post`, or replaces the post with its slug when `overwrite` is
+/// set, recording `user_id` as the author of the new revision.
+pub async fn save_imported_post(
+    db_handler: &impl DBHandler,
+    post: Post,
+    overwrite: bool,
+    user_id: &str,
+) -> Result<ImportOutcome, DbError> {
+    let Some(current) = db_handler.get_post(&post.slug, true).await? else {
+        if is_slug_taken(db_handler, &post.slug).await? {
+            return Ok(ImportOutcome::Skipped(String::from(
+                "the slug belongs to a trashed or renamed post",
+            )));
+        }
+
+        db_handler.create_post(&post).await?;
+        save_revision(db_handler, &post, &post.author).await?;
+        return Ok(ImportOutcome::Created);
+    };
+
+    if !overwrite {
+        return Ok(ImportOutcome::Skipped(String::from(
+            "the post already exists",
+        )));
+    }
+
+    // Same as editing the post, the text it had before stays in its history
+    if db_handler.get_revisions(&current.slug).await?.is_empty() {
+        save_revision(db_handler, &current, &current.author).await?;
+    }
+    let updated = Post {
+        author: current.author,
+        ..post
+    };
+    db_handler.update_post(&updated).await?;
+    save_revision(db_handler, &updated, user_id).await?;
+
+    Ok(ImportOutcome::Overwritten)
+}
+
+async fn import_series(
+    db_handler: &impl DBHandler,
+    series: Series,
+    overwrite: bool,
+) -> Result<ImportOutcome, DbError> {
+    if title_to_slug(&series.slug) != series.slug || series.slug.is_empty() {
+        return Ok(ImportOutcome::Failed(String::from("invalid slug")));
+    }
+
+    if db_handler.get_series(&series.slug).await?.is_none() {
+        db_handler.create_series(&series).await?;
+        Ok(ImportOutcome::Created)
+    } else if overwrite {
+        db_handler.update_series(&series).await?;
+        Ok(ImportOutcome::Overwritten)
+    } else {
+        Ok(ImportOutcome::Skipped(String::from(
+            "the series already exists",
+        )))
+    }
+}
+
+/// Adds the comments that don't exist yet, or replaces them all when
+/// `overwrite` is set.
+async fn import_comments(
+    db_handler: &impl DBHandler,
+    comments: Vec<Comment>,
+    overwrite: bool,
+) -> Result<ImportOutcome, DbError> {
+    let mut created = 0;
+    let mut overwritten = 0;
+    for comment in comments.iter() {
+        if db_handler.get_comment(&comment.id).await?.is_some() {
+            if !overwrite {
+                continue;
+            }
+            db_handler.delete_comment(&comment.id).await?;
+            overwritten += 1;
+        } else {
+            created += 1;
+        }
+        db_handler.create_comment(comment).await?;
+    }
+
+    Ok(if overwritten > 0 {
+        ImportOutcome::Overwritten
+    } else if created > 0 || comments.is_empty() {
+        ImportOutcome::Created
+    } else {
+        ImportOutcome::Skipped(String::from("the comments already exist"))
+    })
+}
+
+fn import_upload(upload_path: &str, filename: &str, data: &[u8], overwrite: bool) -> ImportOutcome {
+    if !is_plain_filename(filename) {
+        return ImportOutcome::Failed(String::from("invalid file name"));
+    }
+
+    let path = Path::new(upload_path).join(filename);
+    let exists = path.exists();
+    if exists && !overwrite {
+        return ImportOutcome::Skipped(String::from("the file already exists"));
+    }
+
+    match std::fs::write(&path, data) {
+        Ok(()) if exists => ImportOutcome::Overwritten,
+        Ok(()) => ImportOutcome::Created,
+        Err(err) => ImportOutcome::Failed(err.to_string()),
+    }
+}
+
+/// Recreates the posts, comments, series and uploaded files of an archive
+/// made by `export_archive`. Existing slugs, comments and files are skipped
+/// unless `overwrite` is set, in which case `user_id` is recorded as the
+/// author of the new revisions.
+pub async fn import_archive(
+    db_handler: &impl DBHandler,
+    archive: impl Read,
+    upload_path: &str,
+    overwrite: bool,
+    user_id: &str,
+) -> Result<ImportReport, ImportError> {
+    let mut archive = tar::Archive::new(archive);
+    let mut report = ImportReport::default();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+
+        let outcome = match path.split_once('/') {
+            Some((POSTS_DIR, name)) if name.ends_with(".md") => {
+                let post = String::from_utf8(data)
+                    .map_err(|_| String::from("not valid UTF-8"))
+                    .and_then(|text| markdown_to_post(&text));
+
+                match post {
+                    Ok(post) => save_imported_post(db_handler, post, overwrite, user_id).await?,
+                    Err(err) => ImportOutcome::Failed(err),
+                }
+            }
+            Some((COMMENTS_DIR, name)) if name.ends_with(".yaml") => {
+                match serde_yaml::from_slice::<Vec<Comment>>(&data) {
+                    Ok(comments) => import_comments(db_handler, comments, overwrite).await?,
+                    Err(err) => ImportOutcome::Failed(err.to_string()),
+                }
+            }
+            Some((SERIES_DIR, name)) if name.ends_with(".yaml") => {
+                match serde_yaml::from_slice::<Series>(&data) {
+                    Ok(series) => import_series(db_handler, series, overwrite).await?,
+                    Err(err) => ImportOutcome::Failed(err.to_string()),
+                }
+            }
+            Some((UPLOADS_DIR, filename)) => import_upload(upload_path, filename, &data, overwrite),
+            _ => ImportOutcome::Skipped(String::from("not a post or uploaded file")),
+        };
+
+        report.files.push(ImportedFile {
+            path,
+            outcome,
+            warnings: vec![],
+        });
+    }
+
+    Ok(report)
+}
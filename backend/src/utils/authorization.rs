@@ -0,0 +1,315 @@
+use std::marker::PhantomData;
+
+use actix_identity::Identity;
+use actix_session::SessionExt;
+use actix_web::{dev::Payload, error, http::header, web, FromRequest, HttpMessage, HttpRequest};
+use chrono::Utc;
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::{hash_token, is_two_factor_required};
+use crate::{database::DBHandler, models::User, Config};
+use common::{Capability, Role};
+
+/// The role users get when they register, from `NEW_USER_DEFAULT_ROLE`.
+#[derive(Clone, Copy)]
+pub struct NewUserRole(pub Role);
+
+impl NewUserRole {
+    /// Panics on an unknown role, a typo shouldn't quietly make every new
+    /// user a Reader.
+    pub fn from_config(config: &Config) -> Self {
+        match config.NEW_USER_DEFAULT_ROLE.parse() {
+            Ok(role) => Self(role),
+            Err(err) => panic!(
+                "`NEW_USER_DEFAULT_ROLE` has to be Admin, Editor or Reader: {}",
+                err
+            ),
+        }
+    }
+}
+
+/// A `Capability` named by a type, so handlers can require it through
+/// `RequireCapability`.
+pub trait RequiredCapability {
+    const CAPABILITY: Capability;
+}
+
+/// One type per `Capability`, e.g. `RequireCapability<T, capability::CreatePost>`.
+pub mod capability {
+    use super::{Capability, RequiredCapability};
+
+    macro_rules! capability_markers {
+        ($($name:ident),+ $(,)?) => {
+            $(
+                pub struct $name;
+
+                impl RequiredCapability for $name {
+                    const CAPABILITY: Capability = Capability::$name;
+                }
+            )+
+        };
+    }
+
+    capability_markers!(
+        CreatePost,
+        EditOwnPost,
+        EditAnyPost,
+        ViewDrafts,
+        ManageSeries,
+        ModerateComments,
+        ManageTrash,
+        ManageBackups,
+        ManageUsers,
+    );
+}
+
+/// Session key holding when the user logged in, in milliseconds.
+const LOGGED_IN_AT_KEY: &str = "logged_in_at";
+
+/// Session key holding a login that still needs its second factor.
+const PENDING_LOGIN_KEY: &str = "pending_login";
+/// How long the second step of a login may take, in milliseconds.
+const PENDING_LOGIN_EXPIRE_AFTER_MILLIS: i64 = 5 * 60 * 1000;
+
+/// A user who entered their password but isn't logged in yet.
+#[derive(Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub username: String,
+    /// Whether they have to set up two-factor authentication, rather than
+    /// enter a code.
+    pub setup: bool,
+    started_at: i64,
+}
+
+/// Starts a session for `username`, remembering when so that a later
+/// password change can end it.
+pub fn start_session(req: &HttpRequest, username: &str) -> Result<(), error::Error> {
+    Identity::login(&req.extensions(), username.to_string())
+        .map_err(error::ErrorInternalServerError)?;
+    let session = req.get_session();
+    session.remove(PENDING_LOGIN_KEY);
+    session
+        .insert(LOGGED_IN_AT_KEY, Utc::now().timestamp_millis())
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(())
+}
+
+/// Remembers that `username` got their password right, for the second step
+/// of the login.
+pub fn start_pending_login(
+    req: &HttpRequest,
+    username: &str,
+    setup: bool,
+) -> Result<(), error::Error> {
+    let session = req.get_session();
+    session.renew();
+    session
+        .insert(
+            PENDING_LOGIN_KEY,
+            PendingLogin {
+                username: username.to_string(),
+                setup,
+                started_at: Utc::now().timestamp_millis(),
+            },
+        )
+        .map_err(error::ErrorInternalServerError)
+}
+
+/// The login of this session waiting for a second factor, unless it took too
+/// long.
+pub fn pending_login(req: &HttpRequest) -> Option<PendingLogin> {
+    let session = req.get_session();
+    let pending = session
+        .get::<PendingLogin>(PENDING_LOGIN_KEY)
+        .ok()
+        .flatten()?;
+
+    if Utc::now().timestamp_millis() - pending.started_at > PENDING_LOGIN_EXPIRE_AFTER_MILLIS {
+        session.remove(PENDING_LOGIN_KEY);
+        return None;
+    }
+
+    Some(pending)
+}
+
+/// Whether the session was started after the user last changed their password.
+fn is_session_current(req: &HttpRequest, user: &User) -> bool {
+    let Some(password_changed_at) = user.password_changed_at else {
+        return true;
+    };
+
+    req.get_session()
+        .get::<i64>(LOGGED_IN_AT_KEY)
+        .ok()
+        .flatten()
+        .is_some_and(|logged_in_at| logged_in_at >= password_changed_at.timestamp_millis())
+}
+
+/// Whether the user has to use two-factor authentication but hasn't set it
+/// up, which happens when it becomes required after they logged in.
+async fn missing_two_factor<T: DBHandler + 'static>(
+    req: &HttpRequest,
+    user: &User,
+) -> Result<bool, error::Error> {
+    let Some(db_handler) = req.app_data::<web::Data<T>>() else {
+        return Err(error::ErrorInternalServerError("missing database handler"));
+    };
+    if !is_two_factor_required(db_handler.get_ref(), user.role).await? {
+        return Ok(false);
+    }
+
+    let two_factor = db_handler.find_two_factor(&user.username).await?;
+
+    Ok(!two_factor.is_some_and(|two_factor| two_factor.enabled))
+}
+
+/// The token in the `Authorization: Bearer` header, if there is one.
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// The owner of the API token `token`, limited to its scopes, unless it was
+/// revoked or expired. Records that the token was used.
+async fn token_user<T: DBHandler + 'static>(
+    req: &HttpRequest,
+    db_handler: &T,
+    token: &str,
+) -> Result<Option<User>, error::Error> {
+    let Some(api_token) = db_handler
+        .find_api_token(&hash_token(token))
+        .await?
+        .filter(|api_token| !api_token.is_expired())
+    else {
+        return Ok(None);
+    };
+    let Some(mut user) = db_handler.find_user(&api_token.username).await? else {
+        return Ok(None);
+    };
+    if missing_two_factor::<T>(req, &user).await? {
+        return Ok(None);
+    }
+
+    db_handler
+        .update_api_token_last_used(&api_token.id, Utc::now())
+        .await?;
+    user.token_scopes = Some(api_token.scopes);
+
+    Ok(Some(user))
+}
+
+/// The user behind the request, kept in the request extensions so it is
+/// read from the database only once.
+#[derive(Clone)]
+struct LoadedUser(Option<User>);
+
+async fn load_user<T: DBHandler + 'static>(
+    req: &HttpRequest,
+) -> Result<Option<User>, error::Error> {
+    if let Some(LoadedUser(user)) = req.extensions().get::<LoadedUser>() {
+        return Ok(user.clone());
+    }
+
+    let Some(db_handler) = req.app_data::<web::Data<T>>() else {
+        return Err(error::ErrorInternalServerError("missing database handler"));
+    };
+
+    // A request with a token is about that token only, even if it comes with
+    // a session cookie too.
+    if let Some(token) = bearer_token(req) {
+        let user = token_user::<T>(req, db_handler, token).await?;
+        req.extensions_mut().insert(LoadedUser(user.clone()));
+        return Ok(user);
+    }
+
+    let user = match Identity::extract(req).await {
+        Ok(identity) => {
+            let user = match identity.id() {
+                Ok(username) => db_handler.find_user(&username).await?,
+                Err(_) => None,
+            };
+            match user {
+                Some(user)
+                    if !is_session_current(req, &user)
+                        || missing_two_factor::<T>(req, &user).await? =>
+                {
+                    identity.logout();
+                    None
+                }
+                user => user,
+            }
+        }
+        Err(_) => None,
+    };
+    req.extensions_mut().insert(LoadedUser(user.clone()));
+
+    Ok(user)
+}
+
+/// The logged in user, if there is one, either from the session or from an
+/// API token.
+pub struct CurrentUser<T> {
+    pub user: Option<User>,
+    db_handler: PhantomData<fn() -> T>,
+}
+
+impl<T> CurrentUser<T> {
+    pub fn can(&self, capability: Capability) -> bool {
+        self.user.as_ref().is_some_and(|user| user.can(capability))
+    }
+
+    /// The user, unless the request was made with an API token. Security
+    /// settings, API tokens included, are only managed from a session.
+    pub fn session_user(self) -> Option<User> {
+        self.user.filter(|user| user.token_scopes.is_none())
+    }
+}
+
+impl<T: DBHandler + 'static> FromRequest for CurrentUser<T> {
+    type Error = error::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            Ok(CurrentUser {
+                user: load_user::<T>(&req).await?,
+                db_handler: PhantomData,
+            })
+        })
+    }
+}
+
+/// The logged in user, whose role grants `C`, as does the API token the
+/// request was made with. Rejects requests without a user with 401 and users
+/// without the capability with 403.
+pub struct RequireCapability<T, C> {
+    pub user: User,
+    marker: PhantomData<fn() -> (T, C)>,
+}
+
+impl<T: DBHandler + 'static, C: RequiredCapability> FromRequest for RequireCapability<T, C> {
+    type Error = error::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            match load_user::<T>(&req).await? {
+                Some(user) if user.can(C::CAPABILITY) => Ok(RequireCapability {
+                    user,
+                    marker: PhantomData,
+                }),
+                Some(_) => Err(error::ErrorForbidden("forbidden")),
+                None => Err(error::ErrorUnauthorized("unauthorized")),
+            }
+        })
+    }
+}
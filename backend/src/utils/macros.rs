@@ -1,18 +1,42 @@
+/// Declares a struct of `String` fields read from the environment variables of
+/// the same name. Fields with `= "default"` fall back to it when unset, the
+/// others are required.
 #[macro_export]
 macro_rules! create_env_struct {
-    ($struct_name:ident { $($field:ident),+ }) => {
+    ($struct_name:ident { $($field:ident $(= $default:expr)?),+ $(,)? }) => {
         #[allow(non_snake_case)]
         #[derive(Clone)]
-        struct $struct_name {
+        pub struct $struct_name {
             $(pub $field: String,)+
         }
 
+        #[allow(clippy::new_without_default)]
         impl $struct_name {
-            fn new() -> Self {
+            pub fn new() -> Self {
                 Self {
-                    $($field: std::env::var(stringify!($field)).expect(&format!("Environment variable `{}` is required", stringify!($field))),)+
+                    $($field: std::env::var(stringify!($field))
+                        .ok()
+                        $(.or_else(|| Some(String::from($default))))?
+                        .unwrap_or_else(|| panic!("Environment variable `{}` is required", stringify!($field))),)+
                 }
             }
+
+            /// Like `new`, but only the variables in `required` must be set,
+            /// the others are left empty when missing.
+            pub fn with_required(required: &[&str]) -> Result<Self, String> {
+                Ok(Self {
+                    $($field: match std::env::var(stringify!($field))
+                        .ok()
+                        $(.or_else(|| Some(String::from($default))))?
+                    {
+                        Some(value) => value,
+                        None if required.contains(&stringify!($field)) => {
+                            return Err(format!("Environment variable `{}` is required", stringify!($field)));
+                        }
+                        None => String::new(),
+                    },)+
+                })
+            }
         }
     };
 }
@@ -1,8 +1,12 @@
-use actix_web::{http::header, HttpRequest};
+use chrono::{Duration, Utc};
 use rand::{distr::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
 
-use crate::database::DBHandler;
-use common::utils::title_to_slug;
+use crate::database::{DBHandler, DbError};
+use common::{
+    utils::title_to_slug, Comment, CommentStatus, Post, PostRevision, Series, SeriesOverview,
+    SeriesPart,
+};
 
 pub fn generate_random_alphanumeric_str(len: usize) -> String {
     let rng = rand::rng();
@@ -12,31 +16,178 @@ pub fn generate_random_alphanumeric_str(len: usize) -> String {
         .collect()
 }
 
-pub async fn generate_unique_slug(db_handler: &impl DBHandler, title: &str) -> Result<String, ()> {
+/// What is stored of a token handed to a user, like API tokens and password
+/// reset links. Tokens are long and random, so a plain SHA-256 hash is enough
+/// and lets them be looked up by it.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Whether `slug` belongs to a post, now, in its slug history or in the trash.
+pub async fn is_slug_taken(db_handler: &impl DBHandler, slug: &str) -> Result<bool, DbError> {
+    Ok(db_handler.get_post(slug, true).await?.is_some()
+        || db_handler.find_slug_redirect(slug).await?.is_some()
+        || db_handler.get_trashed_post(slug).await?.is_some())
+}
+
+/// Current slug of the post once at `old_slug`, as long as `is_admin` can see it.
+pub async fn find_redirect(
+    db_handler: &impl DBHandler,
+    old_slug: &str,
+    is_admin: bool,
+) -> Result<Option<String>, DbError> {
+    match db_handler.find_slug_redirect(old_slug).await? {
+        Some(slug) if db_handler.get_post(&slug, is_admin).await?.is_some() => Ok(Some(slug)),
+        _ => Ok(None),
+    }
+}
+
+pub async fn generate_unique_slug(
+    db_handler: &impl DBHandler,
+    title: &str,
+) -> Result<String, DbError> {
     let original_slug = title_to_slug(title);
     let mut slug = original_slug.clone();
     let mut counter = 1;
 
-    loop {
-        match db_handler.get_post(&slug, true).await {
-            Ok(Some(_)) => {
-                slug = format!("{}-{}", title_to_slug(title), counter);
-                counter += 1;
-            }
-            Ok(None) => {
-                return Ok(slug);
-            }
-            Err(_) => return Err(()),
-        }
+    while is_slug_taken(db_handler, &slug).await? {
+        slug = format!("{}-{}", original_slug, counter);
+        counter += 1;
+    }
+
+    Ok(slug)
+}
+
+pub async fn generate_unique_series_slug(
+    db_handler: &impl DBHandler,
+    title: &str,
+) -> Result<String, DbError> {
+    let original_slug = title_to_slug(title);
+    let mut slug = original_slug.clone();
+    let mut counter = 1;
+
+    while db_handler.get_series(&slug).await?.is_some() {
+        slug = format!("{}-{}", original_slug, counter);
+        counter += 1;
     }
+
+    Ok(slug)
 }
 
-pub fn get_host_or<'a>(request: &'a HttpRequest, default: &'a str) -> &'a str {
-    if let Some(header) = request.headers().get(header::HOST) {
-        if let Ok(value) = header.to_str() {
-            return value;
+/// Posts of `series` in reading order, skipping the ones the reader can't see.
+pub async fn get_series_posts(
+    db_handler: &impl DBHandler,
+    series: &Series,
+    is_admin: bool,
+) -> Result<Vec<Post>, DbError> {
+    let mut posts = Vec::with_capacity(series.posts.len());
+    for slug in series.posts.iter() {
+        if let Some(post) = db_handler.get_post(slug, is_admin).await? {
+            posts.push(post);
         }
     }
 
-    return default;
+    Ok(posts)
+}
+
+pub async fn get_series_overview(
+    db_handler: &impl DBHandler,
+    series: Series,
+    is_admin: bool,
+) -> Result<SeriesOverview, DbError> {
+    let parts = get_series_posts(db_handler, &series, is_admin)
+        .await?
+        .into_iter()
+        .map(|post| SeriesPart {
+            slug: post.slug,
+            title: post.title,
+        })
+        .collect();
+
+    Ok(SeriesOverview {
+        slug: series.slug,
+        title: series.title,
+        description: series.description,
+        parts,
+    })
+}
+
+/// Comments of a post the reader may see: approved ones, plus their own
+/// pending ones, or all of them for moderators.
+pub async fn get_visible_comments(
+    db_handler: &impl DBHandler,
+    post_slug: &str,
+    username: Option<&str>,
+    is_moderator: bool,
+) -> Result<Vec<Comment>, DbError> {
+    let comments = db_handler.get_comments(post_slug).await?;
+
+    Ok(comments
+        .into_iter()
+        .filter(|comment| {
+            is_moderator
+                || comment.status == CommentStatus::Approved
+                || (comment.status == CommentStatus::Pending
+                    && Some(comment.author.as_str()) == username)
+        })
+        .collect())
+}
+
+/// Appends a snapshot of `post` to its revision history and returns the new
+/// revision number. It is dated when it is saved, not when the post is
+/// published, which is in the future for scheduled posts.
+pub async fn save_revision(
+    db_handler: &impl DBHandler,
+    post: &Post,
+    author: &str,
+) -> Result<u64, DbError> {
+    let number = db_handler
+        .get_revisions(&post.slug)
+        .await?
+        .last()
+        .map_or(1, |revision| revision.number + 1);
+
+    db_handler
+        .create_revision(&PostRevision {
+            slug: post.slug.clone(),
+            number,
+            author: author.to_string(),
+            created_at: Utc::now(),
+            title: post.title.clone(),
+            content: post.content.clone(),
+            summary: post.summary.clone(),
+            public: post.public,
+            published_at: Some(post.published_at),
+            tags: Some(post.tags.clone()),
+        })
+        .await?;
+
+    Ok(number)
+}
+
+/// Deletes a trashed post for good, along with the revisions, comments, old
+/// slugs and place in a series kept around in case it was restored.
+pub async fn purge_trashed_post(db_handler: &impl DBHandler, slug: &str) -> Result<u64, DbError> {
+    let purged = db_handler.purge_post(slug).await?;
+    if purged > 0 {
+        db_handler.remove_post_from_series(slug).await?;
+        db_handler.delete_revisions(slug).await?;
+        db_handler.delete_post_comments(slug).await?;
+        db_handler.delete_slug_history(slug).await?;
+    }
+
+    Ok(purged)
+}
+
+/// Purges the posts that have been in the trash for longer than `retention`.
+pub async fn purge_expired_trash(
+    db_handler: &impl DBHandler,
+    retention: Duration,
+) -> Result<u64, DbError> {
+    let mut purged = 0;
+    for slug in db_handler.get_expired_trash(Utc::now() - retention).await? {
+        purged += purge_trashed_post(db_handler, &slug).await?;
+    }
+
+    Ok(purged)
 }
@@ -1,10 +1,41 @@
+mod api_token;
+mod archive;
+mod authorization;
 mod macros;
 mod markdown;
 mod misc;
+mod pagination;
 mod rss;
+mod search;
+mod site_import;
+mod site_url;
+mod static_site;
 mod syntax_highlight;
+mod totp;
 
 pub use crate::utils::rss::*;
+pub use api_token::{generate_api_token, generate_api_token_id, MAX_API_TOKEN_NAME_LENGTH};
+pub use archive::{
+    export_archive, import_archive, referenced_uploads, save_imported_post, split_front_matter,
+    ImportError,
+};
+pub use authorization::{
+    capability, pending_login, start_pending_login, start_session, CurrentUser, NewUserRole,
+    PendingLogin, RequireCapability, RequiredCapability,
+};
 pub use markdown::*;
-pub use misc::{generate_random_alphanumeric_str, generate_unique_slug, get_host_or};
+pub use misc::{
+    find_redirect, generate_random_alphanumeric_str, generate_unique_series_slug,
+    generate_unique_slug, get_series_overview, get_series_posts, get_visible_comments, hash_token,
+    is_slug_taken, purge_expired_trash, purge_trashed_post, save_revision,
+};
+pub use pagination::{clamp_per_page, get_posts_page, MAX_PER_PAGE};
+pub use search::{get_search_results, rank_posts};
+pub use site_import::{import_site, SiteImport};
+pub use site_url::SiteUrl;
+pub use static_site::{export_site, StaticExport};
 pub use syntax_highlight::Highlighter;
+pub use totp::{
+    generate_recovery_codes, generate_secret, hash_recovery_code, is_two_factor_required,
+    provisioning_uri, verify_code,
+};
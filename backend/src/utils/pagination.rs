@@ -0,0 +1,86 @@
+use crate::{
+    database::{DBHandler, DbError},
+    models::{PageCursor, PostsQueryParams},
+};
+use common::{utils::title_to_slug, GetPostsResponse};
+
+/// Most posts listed on one page.
+pub const MAX_PER_PAGE: u64 = 100;
+
+/// The `per_page` of a query, 10 when unset and kept within `1..=MAX_PER_PAGE`.
+pub fn clamp_per_page(per_page: Option<u64>) -> u64 {
+    per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE)
+}
+
+/// Lists a page of posts starting at `cursor`. Requests with a custom sort
+/// order or a `page` past the first one keep the old offset pagination and
+/// get no cursors back.
+pub async fn get_posts_page(
+    db_handler: &impl DBHandler,
+    query: &PostsQueryParams,
+    cursor: &PageCursor,
+    is_admin: bool,
+) -> Result<GetPostsResponse, DbError> {
+    let per_page = clamp_per_page(query.per_page);
+    // Stored tags are slugs, so `?tag=Rust` lists the posts tagged `rust`
+    let query = &PostsQueryParams {
+        per_page: Some(per_page),
+        tag: query.tag.as_deref().map(title_to_slug),
+        ..query.clone()
+    };
+    let pages = db_handler.calculate_total_pages(query, is_admin).await?;
+
+    if query.sort_by.is_some() || (*cursor == PageCursor::First && query.page.unwrap_or(1) > 1) {
+        return Ok(GetPostsResponse {
+            posts: db_handler.get_posts(query, is_admin).await?,
+            pages: Ok(pages),
+            next: None,
+            prev: None,
+        });
+    }
+
+    // One more post than needed tells whether there is another page
+    let mut cursor = cursor.clone();
+    let mut posts = db_handler
+        .get_posts_page(query, &cursor, per_page + 1, is_admin)
+        .await?;
+
+    // Going back to the newest posts shows a full first page instead of a short one
+    if matches!(cursor, PageCursor::Before(_)) && posts.len() as u64 <= per_page {
+        cursor = PageCursor::First;
+        posts = db_handler
+            .get_posts_page(query, &cursor, per_page + 1, is_admin)
+            .await?;
+    }
+
+    let has_more = posts.len() as u64 > per_page;
+    if has_more {
+        match cursor {
+            PageCursor::Before(_) => {
+                posts.remove(0);
+            }
+            _ => {
+                posts.pop();
+            }
+        }
+    }
+
+    let (has_next, has_prev) = match cursor {
+        PageCursor::First => (has_more, false),
+        PageCursor::After(_) => (has_more, true),
+        PageCursor::Before(_) => (true, has_more),
+    };
+
+    Ok(GetPostsResponse {
+        next: posts
+            .last()
+            .filter(|_| has_next)
+            .map(|(_, cursor)| cursor.encode()),
+        prev: posts
+            .first()
+            .filter(|_| has_prev)
+            .map(|(_, cursor)| cursor.encode()),
+        posts: posts.into_iter().map(|(post, _)| post).collect(),
+        pages: Ok(pages),
+    })
+}
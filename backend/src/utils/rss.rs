@@ -1,18 +1,24 @@
 use rss::{Channel, ChannelBuilder, Item, ItemBuilder};
 
-use crate::Config;
-use common::{utils::get_summary, Post};
+use crate::{
+    database::{DBHandler, DbError},
+    Config,
+};
+use common::{PostSummary, PostsQueryParams};
 
-const MAX_SUMMARY_SIZE: usize = 200;
+/// Posts in the `/rss` feed.
+pub const RSS_FEED_SIZE: u64 = 10;
+/// The sitemap is a feed of every public post.
+pub const SITEMAP_SIZE: u64 = 9999999;
 
-pub fn create_rss_feed(latest_posts: &[Post], config: &Config, base_url: &str) -> Channel {
+pub fn create_rss_feed(latest_posts: &[PostSummary], config: &Config, base_url: &str) -> Channel {
     let items: Vec<Item> = latest_posts
         .iter()
         .map(|post| {
             ItemBuilder::default()
                 .title(post.title.clone())
                 .author(post.author.clone())
-                .description(get_summary(&post.content, MAX_SUMMARY_SIZE))
+                .description(post.summary.clone())
                 .link(format!("{}/post/{}", base_url, &post.slug))
                 .pub_date(post.published_at.clone().to_rfc2822())
                 .build()
@@ -26,3 +32,33 @@ pub fn create_rss_feed(latest_posts: &[Post], config: &Config, base_url: &str) -
         .items(items)
         .build()
 }
+
+/// Feed of the `per_page` most recently published public posts.
+pub async fn latest_posts_feed(
+    db_handler: &impl DBHandler,
+    config: &Config,
+    per_page: u64,
+    base_url: &str,
+) -> Result<Channel, DbError> {
+    let latest_posts = db_handler
+        .get_posts(
+            &PostsQueryParams {
+                page: Some(1),
+                per_page: Some(per_page),
+                sort_by: Some("published_at".to_string()),
+                sort_order: Some("desc".to_string()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await?;
+
+    Ok(create_rss_feed(&latest_posts, config, base_url))
+}
+
+/// The `robots.txt` shipped with the frontend, pointing crawlers to the
+/// sitemap under `base_url`.
+pub fn robots_txt(base_url: &str) -> std::io::Result<String> {
+    let robots_txt = std::fs::read_to_string("./dist/static/robots.txt")?;
+    Ok(robots_txt + &format!("Sitemap: {}/sitemap", base_url))
+}
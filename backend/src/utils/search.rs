@@ -0,0 +1,217 @@
+use pulldown_cmark::{Event, Options, Parser, TagEnd};
+
+use super::clamp_per_page;
+use crate::database::{DBHandler, DbError};
+use common::{Post, PostSummary, SearchHit, SearchQueryParams, SearchResponse, SnippetFragment};
+
+/// Words past this many are ignored.
+const MAX_TERMS: usize = 10;
+/// Approximate number of characters shown around the first match.
+const SNIPPET_LENGTH: usize = 200;
+
+// Same weights as the MongoDB text index
+const TITLE_WEIGHT: f64 = 10.0;
+const SUMMARY_WEIGHT: f64 = 5.0;
+const CONTENT_WEIGHT: f64 = 1.0;
+
+/// Splits a query into lowercase words without surrounding punctuation.
+fn search_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for term in query.split_whitespace().map(|term| {
+        term.trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    }) {
+        if !term.is_empty() && !terms.contains(&term) {
+            terms.push(term);
+        }
+        if terms.len() == MAX_TERMS {
+            break;
+        }
+    }
+
+    terms
+}
+
+// One lowercase char per char, so indexes stay the same as in the original text
+fn lowercase_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+fn matches_at(haystack: &[char], index: usize, term: &[char]) -> bool {
+    haystack.len() >= index + term.len() && haystack[index..index + term.len()] == *term
+}
+
+fn count_matches(haystack: &[char], term: &[char]) -> usize {
+    (0..haystack.len())
+        .filter(|&index| matches_at(haystack, index, term))
+        .count()
+}
+
+/// Relevance of `post` for `terms`, zero when none of them appear.
+fn search_score(post: &Post, terms: &[String]) -> f64 {
+    let title = lowercase_chars(&post.title);
+    let summary = lowercase_chars(post.summary.as_deref().unwrap_or_default());
+    let content = lowercase_chars(&post.content);
+
+    terms
+        .iter()
+        .map(|term| {
+            let term: Vec<char> = term.chars().collect();
+            count_matches(&title, &term) as f64 * TITLE_WEIGHT
+                + count_matches(&summary, &term) as f64 * SUMMARY_WEIGHT
+                + count_matches(&content, &term) as f64 * CONTENT_WEIGHT
+        })
+        .sum()
+}
+
+/// Drops the posts that don't match and keeps the `limit` most relevant of
+/// the rest, most relevant first.
+pub fn rank_posts(posts: Vec<Post>, terms: &[String], limit: usize) -> Vec<Post> {
+    let mut scored: Vec<(f64, Post)> = posts
+        .into_iter()
+        .map(|post| (search_score(&post, terms), post))
+        .filter(|(score, _)| *score > 0.0)
+        .collect();
+
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .total_cmp(a_score)
+            .then_with(|| b.published_at.cmp(&a.published_at))
+    });
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, post)| post)
+        .collect()
+}
+
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_MATH) {
+        match event {
+            Event::Text(value)
+            | Event::Code(value)
+            | Event::InlineMath(value)
+            | Event::DisplayMath(value) => text += &value,
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::CodeBlock) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.trim_end().to_string()
+}
+
+/// Cuts a piece of text around the first match of any term and marks every
+/// match inside it.
+fn highlight_snippet(post: &Post, terms: &[String]) -> Vec<SnippetFragment> {
+    let terms: Vec<Vec<char>> = terms.iter().map(|term| term.chars().collect()).collect();
+    let first_match = |lowercase: &[char]| {
+        (0..lowercase.len())
+            .find(|&index| terms.iter().any(|term| matches_at(lowercase, index, term)))
+    };
+
+    let content = plain_text(&post.content);
+    let sources = [post.summary.clone().unwrap_or_default(), content];
+    let (text, start) = sources
+        .iter()
+        .find_map(|source| {
+            first_match(&lowercase_chars(source))
+                .map(|index| (source, index.saturating_sub(SNIPPET_LENGTH / 3)))
+        })
+        .unwrap_or((&sources[1], 0));
+
+    let chars: Vec<char> = text.chars().collect();
+    let lowercase = lowercase_chars(text);
+    let end = std::cmp::min(start + SNIPPET_LENGTH, chars.len());
+
+    let mut fragments = Vec::new();
+    let mut plain = String::new();
+    if start > 0 {
+        plain += "...";
+    }
+
+    let mut index = start;
+    while index < end {
+        let matched = terms
+            .iter()
+            .filter(|term| index + term.len() <= end && matches_at(&lowercase, index, term))
+            .map(|term| term.len())
+            .max();
+
+        match matched {
+            Some(len) => {
+                if !plain.is_empty() {
+                    fragments.push(SnippetFragment {
+                        text: std::mem::take(&mut plain),
+                        highlight: false,
+                    });
+                }
+                fragments.push(SnippetFragment {
+                    text: chars[index..index + len].iter().collect(),
+                    highlight: true,
+                });
+                index += len;
+            }
+            None => {
+                plain.push(chars[index]);
+                index += 1;
+            }
+        }
+    }
+
+    if end < chars.len() {
+        plain += "...";
+    }
+    if !plain.is_empty() {
+        fragments.push(SnippetFragment {
+            text: plain,
+            highlight: false,
+        });
+    }
+
+    fragments
+}
+
+/// Runs a search and returns the requested page of hits.
+pub async fn get_search_results(
+    db_handler: &impl DBHandler,
+    query: &SearchQueryParams,
+    is_admin: bool,
+) -> Result<SearchResponse, DbError> {
+    let terms = search_terms(&query.q);
+    if terms.is_empty() {
+        return Ok(SearchResponse {
+            hits: vec![],
+            pages: 0,
+        });
+    }
+
+    let page = query.page.unwrap_or(1);
+    let per_page = clamp_per_page(query.per_page);
+    let offset = page.saturating_sub(1).saturating_mul(per_page);
+
+    let posts = db_handler.search_posts(&terms, is_admin).await?;
+    let pages = (posts.len() as f64 / per_page as f64).ceil() as u64;
+
+    let hits = posts
+        .into_iter()
+        .skip(offset as usize)
+        .take(per_page as usize)
+        .map(|post| SearchHit {
+            snippet: highlight_snippet(&post, &terms),
+            post: PostSummary::from(&post),
+        })
+        .collect();
+
+    Ok(SearchResponse { hits, pages })
+}
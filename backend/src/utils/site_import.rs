@@ -0,0 +1,593 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bson::uuid::Uuid;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use pulldown_cmark::{Event, Parser, Tag};
+use serde_json::Value;
+
+use crate::{
+    database::{DBHandler, DbError},
+    utils::{is_slug_taken, save_imported_post, split_front_matter},
+};
+use common::{
+    utils::{normalize_tags, title_to_slug},
+    ImportOutcome, ImportReport, ImportedFile, Post,
+};
+
+const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+/// Where to find a Hugo, Jekyll or Zola site and how to import it.
+pub struct SiteImport<'a> {
+    /// Root of the site, or directly the directory with the Markdown files.
+    pub root: &'a Path,
+    pub upload_path: &'a str,
+    pub upload_url: &'a str,
+    /// Username the imported posts are attributed to.
+    pub author: &'a str,
+    pub overwrite: bool,
+}
+
+/// Directories holding the posts, each with whether its posts are drafts.
+/// Hugo and Zola keep them in `content`, Jekyll in `_posts` and `_drafts`.
+fn content_dirs(root: &Path) -> Vec<(PathBuf, bool)> {
+    if root.join("content").is_dir() {
+        return vec![(root.join("content"), false)];
+    }
+
+    if root.join("_posts").is_dir() {
+        let mut dirs = vec![(root.join("_posts"), false)];
+        if root.join("_drafts").is_dir() {
+            dirs.push((root.join("_drafts"), true));
+        }
+        return dirs;
+    }
+
+    vec![(root.to_path_buf(), false)]
+}
+
+/// Directories that links starting with `/` are resolved against.
+fn static_dirs(root: &Path) -> Vec<PathBuf> {
+    [root.join("static"), root.to_path_buf()]
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+fn markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            markdown_files(&path, files)?;
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| MARKDOWN_EXTENSIONS.contains(&extension))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => Value::from(number),
+        toml::Value::Float(number) => Value::from(number),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses the TOML (`+++`) or YAML (`---`) front matter of `text`.
+fn parse_front_matter(text: &str) -> Result<(Value, &str), String> {
+    if let Some((front_matter, body)) = split_front_matter(text, "+++") {
+        let table = front_matter
+            .parse::<toml::Table>()
+            .map_err(|err| err.to_string())?;
+        return Ok((toml_to_json(toml::Value::Table(table)), body));
+    }
+
+    if let Some((front_matter, body)) = split_front_matter(text, "---") {
+        let value: Value = serde_yaml::from_str(front_matter).map_err(|err| err.to_string())?;
+        return Ok((value, body));
+    }
+
+    Err(String::from("missing TOML or YAML front matter"))
+}
+
+fn string_field<'a>(front_matter: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| front_matter.get(key).and_then(Value::as_str))
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// A list that may also be written as a single string, like Jekyll's
+/// space separated tags.
+fn list_field(front_matter: &Value, key: &str) -> Vec<String> {
+    match front_matter.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(String::from))
+            .collect(),
+        Some(Value::String(value)) => value
+            .split([',', ' '])
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.to_utc());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S %z", "%Y-%m-%d %H:%M %z"] {
+        if let Ok(date) = DateTime::parse_from_str(value, format) {
+            return Some(date.to_utc());
+        }
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc());
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
+
+/// Splits Jekyll's `2020-01-31-my-post` into its date and slug.
+fn dated_file_stem(stem: &str) -> Option<(DateTime<Utc>, &str)> {
+    let date = parse_date(stem.get(..10)?)?;
+    let slug = stem.get(10..)?.strip_prefix('-')?;
+    Some((date, slug))
+}
+
+/// Slug of a link like `/posts/old-name/` or `old-name.html`.
+fn alias_to_slug(alias: &str) -> String {
+    let name = alias
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    title_to_slug(name.strip_suffix(".html").unwrap_or(name))
+}
+
+/// Copies the local images of `content` to the upload directory and points
+/// the links to their new location.
+struct ImageRewriter<'a> {
+    import: &'a SiteImport<'a>,
+    /// The canonical root of the site, no image is copied from outside it.
+    root: PathBuf,
+    static_dirs: Vec<PathBuf>,
+    /// Upload URLs of the images copied so far, by their original path.
+    copied: HashMap<PathBuf, String>,
+}
+
+impl ImageRewriter<'_> {
+    /// The file `link` points to, canonical, or why it can't be used.
+    fn resolve(&self, link: &str, file_dir: &Path) -> Result<PathBuf, &'static str> {
+        let link = link.split(['?', '#']).next().unwrap_or_default();
+        let link = link.replace("%20", " ");
+
+        let candidates = match link.strip_prefix('/') {
+            Some(link) => self.static_dirs.iter().map(|dir| dir.join(link)).collect(),
+            None => vec![file_dir.join(link)],
+        };
+        let path = candidates
+            .into_iter()
+            .filter(|path| path.is_file())
+            .find_map(|path| path.canonicalize().ok())
+            .ok_or("not found")?;
+
+        // `..` and symbolic links could otherwise reach any file on the server
+        if !path.starts_with(&self.root) {
+            return Err("is outside the site");
+        }
+        Ok(path)
+    }
+
+    fn upload(&mut self, path: &Path) -> std::io::Result<String> {
+        let path = path.canonicalize()?;
+        if let Some(url) = self.copied.get(&path) {
+            return Ok(url.clone());
+        }
+
+        let filename = format!(
+            "{}{}",
+            Uuid::new(),
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(".tmp")
+        );
+        std::fs::copy(&path, Path::new(self.import.upload_path).join(&filename))?;
+
+        let url = format!(
+            "{}/{}",
+            self.import.upload_url.trim_end_matches('/'),
+            filename
+        );
+        self.copied.insert(path, url.clone());
+        Ok(url)
+    }
+
+    fn rewrite(&mut self, content: &str, file_dir: &Path, warnings: &mut Vec<String>) -> String {
+        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+        let mut has_html_images = false;
+
+        for (event, range) in Parser::new(content).into_offset_iter() {
+            let dest_url = match event {
+                Event::Start(Tag::Image { dest_url, .. }) => dest_url,
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    has_html_images |= html.contains("<img");
+                    continue;
+                }
+                _ => continue,
+            };
+
+            // Remote images and data URLs stay as they are
+            if dest_url.is_empty()
+                || dest_url.contains("://")
+                || dest_url.starts_with("//")
+                || dest_url.starts_with("data:")
+            {
+                continue;
+            }
+
+            let path = match self.resolve(&dest_url, file_dir) {
+                Ok(path) => path,
+                Err(err) => {
+                    warnings.push(format!("image {} {}", dest_url, err));
+                    continue;
+                }
+            };
+            let url = match self.upload(&path) {
+                Ok(url) => url,
+                Err(err) => {
+                    warnings.push(format!("image {} not copied: {}", dest_url, err));
+                    continue;
+                }
+            };
+
+            // Reference style images keep their link in a definition elsewhere
+            match content[range.clone()].rfind(dest_url.as_ref()) {
+                Some(offset) => replacements.push((
+                    range.start + offset,
+                    range.start + offset + dest_url.len(),
+                    url,
+                )),
+                None => warnings.push(format!("image {} is a reference, not rewritten", dest_url)),
+            }
+        }
+
+        if has_html_images {
+            warnings.push(String::from("HTML <img> tags are not rewritten"));
+        }
+
+        let mut content = content.to_string();
+        for (start, end, url) in replacements.into_iter().rev() {
+            content.replace_range(start..end, &url);
+        }
+        content
+    }
+}
+
+/// Builds the post described by the Markdown file at `path`.
+fn file_to_post(
+    path: &Path,
+    is_draft: bool,
+    author: &str,
+    warnings: &mut Vec<String>,
+) -> Result<(Post, Vec<String>), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let (front_matter, content) = parse_front_matter(&text)?;
+
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let dated_stem = dated_file_stem(stem);
+    // Page bundles are named after their directory
+    let name = match stem {
+        "index" => path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or_default(),
+        _ => dated_stem.map_or(stem, |(_, slug)| slug),
+    };
+
+    let title = string_field(&front_matter, &["title"]).unwrap_or(name);
+    let slug = title_to_slug(string_field(&front_matter, &["slug"]).unwrap_or(name));
+    if slug.is_empty() {
+        return Err(String::from(
+            "no slug could be made from the file name or title",
+        ));
+    }
+
+    let published_at = match string_field(&front_matter, &["date", "publishDate"]) {
+        Some(date) => parse_date(date).ok_or(format!("invalid date {}", date))?,
+        None => match dated_stem {
+            Some((date, _)) => date,
+            None => {
+                warnings.push(String::from("no date, using the current time"));
+                Utc::now()
+            }
+        },
+    };
+
+    let draft = is_draft
+        || front_matter.get("draft").and_then(Value::as_bool) == Some(true)
+        || front_matter.get("published").and_then(Value::as_bool) == Some(false);
+
+    let taxonomies = front_matter.get("taxonomies").cloned().unwrap_or_default();
+    let tags = [
+        list_field(&front_matter, "tags"),
+        list_field(&front_matter, "categories"),
+        list_field(&taxonomies, "tags"),
+        list_field(&taxonomies, "categories"),
+    ]
+    .concat();
+
+    let aliases = [
+        list_field(&front_matter, "aliases"),
+        list_field(&front_matter, "redirect_from"),
+    ]
+    .concat();
+
+    if content.contains("{{") || content.contains("{%") {
+        warnings.push(String::from(
+            "shortcodes and template tags are left as they are",
+        ));
+    }
+
+    let post = Post {
+        slug,
+        title: title.to_string(),
+        content: content.to_string(),
+        summary: string_field(&front_matter, &["summary", "description", "excerpt"])
+            .map(String::from),
+        author: author.to_string(),
+        published_at,
+        public: !draft,
+        tags: normalize_tags(&tags),
+    };
+
+    Ok((post, aliases))
+}
+
+async fn import_file(
+    db_handler: &impl DBHandler,
+    import: &SiteImport<'_>,
+    images: &mut ImageRewriter<'_>,
+    path: &Path,
+    is_draft: bool,
+) -> Result<(ImportOutcome, Vec<String>), DbError> {
+    let mut warnings = Vec::new();
+
+    // Section pages list other pages, they aren't posts
+    if path
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().starts_with('_'))
+    {
+        return Ok((
+            ImportOutcome::Skipped(String::from("section page")),
+            warnings,
+        ));
+    }
+
+    let (mut post, aliases) = match file_to_post(path, is_draft, import.author, &mut warnings) {
+        Ok(parsed) => parsed,
+        Err(err) => return Ok((ImportOutcome::Failed(err), warnings)),
+    };
+
+    let file_dir = path.parent().unwrap_or(import.root);
+    post.content = images.rewrite(&post.content, file_dir, &mut warnings);
+
+    let slug = post.slug.clone();
+    let outcome = save_imported_post(db_handler, post, import.overwrite, import.author).await?;
+    if matches!(outcome, ImportOutcome::Created | ImportOutcome::Overwritten) {
+        for alias in aliases {
+            let old_slug = alias_to_slug(&alias);
+            if old_slug.is_empty() || old_slug == slug {
+                continue;
+            }
+
+            if is_slug_taken(db_handler, &old_slug).await? {
+                warnings.push(format!("alias {} is taken by another post", alias));
+            } else {
+                db_handler.add_slug_redirect(&old_slug, &slug).await?;
+            }
+        }
+    }
+
+    Ok((outcome, warnings))
+}
+
+/// Imports every Markdown file of a Hugo, Jekyll or Zola site as a post.
+/// Dates, drafts, tags and aliases come from the front matter, and local
+/// images are copied to the upload directory.
+pub async fn import_site(
+    db_handler: &impl DBHandler,
+    import: &SiteImport<'_>,
+) -> Result<ImportReport, DbError> {
+    let mut report = ImportReport::default();
+    let mut images = ImageRewriter {
+        import,
+        root: import
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| import.root.to_path_buf()),
+        static_dirs: static_dirs(import.root),
+        copied: HashMap::new(),
+    };
+
+    for (dir, is_draft) in content_dirs(import.root) {
+        let mut files = Vec::new();
+        if let Err(err) = markdown_files(&dir, &mut files) {
+            report.files.push(ImportedFile {
+                path: dir.display().to_string(),
+                outcome: ImportOutcome::Failed(err.to_string()),
+                warnings: vec![],
+            });
+            continue;
+        }
+
+        for path in files {
+            let (outcome, warnings) =
+                import_file(db_handler, import, &mut images, &path, is_draft).await?;
+
+            report.files.push(ImportedFile {
+                path: path
+                    .strip_prefix(import.root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string(),
+                outcome,
+                warnings,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn front_matter_is_toml_or_yaml() {
+        let (value, body) = parse_front_matter("+++\ntitle = \"Hugo\"\n+++\nBody").unwrap();
+        assert_eq!(value["title"], "Hugo");
+        assert_eq!(body.trim(), "Body");
+
+        let (value, body) = parse_front_matter("---\ntitle: Jekyll\n---\nBody").unwrap();
+        assert_eq!(value["title"], "Jekyll");
+        assert_eq!(body.trim(), "Body");
+
+        assert!(parse_front_matter("+++\ntitle = \n+++\nBody").is_err());
+        assert!(parse_front_matter("Just a body").is_err());
+    }
+
+    #[test]
+    fn dates_in_the_formats_of_each_generator() {
+        let noon = Utc.with_ymd_and_hms(2020, 1, 31, 12, 0, 0).unwrap();
+        assert_eq!(parse_date("2020-01-31T12:00:00Z"), Some(noon));
+        assert_eq!(parse_date("2020-01-31T14:00:00+02:00"), Some(noon));
+        assert_eq!(parse_date("2020-01-31 14:00:00 +0200"), Some(noon));
+        assert_eq!(parse_date("2020-01-31 14:00 +0200"), Some(noon));
+        assert_eq!(parse_date("2020-01-31T12:00:00"), Some(noon));
+        assert_eq!(parse_date(" 2020-01-31 12:00 "), Some(noon));
+        assert_eq!(
+            parse_date("2020-01-31"),
+            Utc.with_ymd_and_hms(2020, 1, 31, 0, 0, 0).single()
+        );
+        assert_eq!(parse_date("31/01/2020"), None);
+        assert_eq!(parse_date("2020-02-30"), None);
+    }
+
+    #[test]
+    fn jekyll_file_names_carry_the_date() {
+        let (date, slug) = dated_file_stem("2020-01-31-my-post").unwrap();
+        assert_eq!(date, Utc.with_ymd_and_hms(2020, 1, 31, 0, 0, 0).unwrap());
+        assert_eq!(slug, "my-post");
+
+        assert_eq!(dated_file_stem("my-post"), None);
+        assert_eq!(dated_file_stem("2020-01-31my-post"), None);
+        assert_eq!(dated_file_stem("2020-01-3"), None);
+        // Not a panic when the tenth byte is inside a character
+        assert_eq!(dated_file_stem("2020-01-é-post"), None);
+    }
+
+    #[test]
+    fn aliases_become_slugs() {
+        assert_eq!(alias_to_slug("/posts/old-name/"), "old-name");
+        assert_eq!(alias_to_slug("/2020/01/Old-Name.html"), "old-name");
+        assert_eq!(alias_to_slug("old-name"), "old-name");
+    }
+
+    #[test]
+    fn lists_may_be_a_single_string() {
+        let front_matter = json!({
+            "tags": ["rust", "web"],
+            "categories": "rust, web",
+            "keywords": "rust web",
+            "title": 42,
+        });
+        assert_eq!(list_field(&front_matter, "tags"), ["rust", "web"]);
+        assert_eq!(list_field(&front_matter, "categories"), ["rust", "web"]);
+        assert_eq!(list_field(&front_matter, "keywords"), ["rust", "web"]);
+        assert!(list_field(&front_matter, "title").is_empty());
+        assert!(list_field(&front_matter, "missing").is_empty());
+    }
+
+    #[test]
+    fn images_outside_the_site_are_not_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("site");
+        std::fs::create_dir_all(root.join("static/images")).unwrap();
+        std::fs::create_dir_all(root.join("content/posts")).unwrap();
+        std::fs::write(root.join("static/images/cat.png"), b"cat").unwrap();
+        std::fs::write(dir.path().join("secret"), b"secret").unwrap();
+
+        let import = SiteImport {
+            root: &root,
+            upload_path: "",
+            upload_url: "",
+            author: "alice",
+            overwrite: false,
+        };
+        let images = ImageRewriter {
+            import: &import,
+            root: root.canonicalize().unwrap(),
+            static_dirs: static_dirs(&root),
+            copied: HashMap::new(),
+        };
+        let posts = root.join("content/posts");
+
+        let cat = root.join("static/images/cat.png").canonicalize().unwrap();
+        assert_eq!(
+            images.resolve("/images/cat.png?v=2", &posts),
+            Ok(cat.clone())
+        );
+        assert_eq!(
+            images.resolve("../../static/images/cat.png", &posts),
+            Ok(cat)
+        );
+        assert_eq!(images.resolve("missing.png", &posts), Err("not found"));
+        assert_eq!(
+            images.resolve("../../../secret", &posts),
+            Err("is outside the site")
+        );
+        assert_eq!(
+            images.resolve("/../secret", &posts),
+            Err("is outside the site")
+        );
+        assert_eq!(
+            images.resolve("/../../site/../secret", &posts),
+            Err("is outside the site")
+        );
+    }
+}
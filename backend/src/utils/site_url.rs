@@ -0,0 +1,60 @@
+use actix_web::HttpRequest;
+
+/// Builds absolute links to the site, for emails and feeds. Links only ever
+/// point to `WEBSITE_URL` or one of the origins in `ALLOWED_ORIGINS`, never
+/// to whatever host a request claims to come from.
+#[derive(Clone)]
+pub struct SiteUrl {
+    /// `WEBSITE_URL` first, then the other allowed origins, without a
+    /// trailing slash.
+    origins: Vec<String>,
+}
+
+/// `scheme://host[:port]`, with an `http` or `https` scheme and nothing after
+/// the host.
+fn normalize_origin(origin: &str) -> Option<String> {
+    let origin = origin.trim().trim_end_matches('/');
+    let (scheme, host) = origin.split_once("://")?;
+    let valid_scheme = scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https");
+    let valid_host = !host.is_empty() && !host.contains(['/', '?', '#', '@', ' ']);
+
+    (valid_scheme && valid_host).then(|| origin.to_ascii_lowercase())
+}
+
+impl SiteUrl {
+    /// `allowed_origins` is a comma separated list, entries that aren't a
+    /// valid origin are left out with a warning.
+    pub fn new(website_url: &str, allowed_origins: &str) -> Self {
+        let mut origins = vec![website_url.trim().trim_end_matches('/').to_string()];
+        for origin in allowed_origins
+            .split(',')
+            .filter(|origin| !origin.trim().is_empty())
+        {
+            match normalize_origin(origin) {
+                Some(origin) if !origins.contains(&origin) => origins.push(origin),
+                Some(_) => {}
+                None => log::warn!("Ignoring invalid origin in ALLOWED_ORIGINS: {}", origin),
+            }
+        }
+
+        Self { origins }
+    }
+
+    /// The `WEBSITE_URL`, used when a request doesn't come through an allowed
+    /// origin.
+    pub fn default_url(&self) -> &str {
+        &self.origins[0]
+    }
+
+    /// The origin the request came through if it is an allowed one, otherwise
+    /// `WEBSITE_URL`.
+    pub fn base_url(&self, request: &HttpRequest) -> &str {
+        let info = request.connection_info();
+        let origin = format!("{}://{}", info.scheme(), info.host()).to_ascii_lowercase();
+
+        self.origins
+            .iter()
+            .find(|allowed| allowed.to_ascii_lowercase() == origin)
+            .map_or(self.default_url(), String::as_str)
+    }
+}
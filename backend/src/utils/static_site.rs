@@ -0,0 +1,204 @@
+use std::io;
+use std::path::Path;
+
+use crate::{
+    database::{DBHandler, DbError},
+    handlers::{
+        post_description, render_page, render_post, render_posts_list, render_series,
+        SITE_DESCRIPTION, SITE_TITLE,
+    },
+    models::PageCursor,
+    utils::{
+        get_posts_page, latest_posts_feed, referenced_uploads, robots_txt, Highlighter,
+        RSS_FEED_SIZE, SITEMAP_SIZE,
+    },
+    Config,
+};
+use common::PostsQueryParams;
+
+/// Static files of the built frontend, served under `/static`.
+const STATIC_FILES_DIR: &str = "./dist/static";
+/// Redirects the root of the mirror to the blog, like the live server does.
+const ROOT_REDIRECT: &str = r#"<!DOCTYPE html>
+<html><head><meta http-equiv="refresh" content="0; url=/blog/"><link rel="canonical" href="/blog/"></head></html>
+"#;
+
+/// Where to write the static mirror of the blog.
+pub struct StaticExport<'a> {
+    pub out_dir: &'a Path,
+    /// Posts on every page of the blog index.
+    pub per_page: u64,
+}
+
+fn io_error(err: io::Error) -> DbError {
+    DbError::Other(err.into())
+}
+
+/// Writes `contents` to `path` under the output directory, creating the
+/// directories leading to it. Returns the number of files written.
+fn write_file(out_dir: &Path, path: &str, contents: &[u8]) -> Result<usize, DbError> {
+    let path = out_dir.join(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    std::fs::write(path, contents).map_err(io_error)?;
+
+    Ok(1)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<usize> {
+    std::fs::create_dir_all(to)?;
+
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+
+        if path.is_dir() {
+            copied += copy_dir(&path, &to.join(name))?;
+        } else {
+            std::fs::copy(&path, to.join(name))?;
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Writes every page of the public posts, optionally with a single tag, to
+/// `{base}/index.html` and `{base}/page/{n}/index.html`. Returns the slugs
+/// of the listed posts and the number of files written.
+async fn export_posts_list(
+    db_handler: &impl DBHandler,
+    export: &StaticExport<'_>,
+    tag: Option<&str>,
+) -> Result<(Vec<String>, usize), DbError> {
+    let (base, title, description) = match tag {
+        Some(tag) => (
+            format!("tag/{}", tag),
+            format!("#{} | {}", tag, SITE_TITLE),
+            format!("Posts tagged {}.", tag),
+        ),
+        None => (
+            String::from("blog"),
+            String::from(SITE_TITLE),
+            String::from(SITE_DESCRIPTION),
+        ),
+    };
+
+    let mut slugs = Vec::new();
+    let mut written = 0;
+    let mut page = 1;
+    loop {
+        let query = PostsQueryParams {
+            page: Some(page),
+            per_page: Some(export.per_page),
+            tag: tag.map(String::from),
+            ..Default::default()
+        };
+        let mut response = get_posts_page(db_handler, &query, &PageCursor::First, false).await?;
+        let pages = response.pages.unwrap_or_default();
+
+        // The pages link to each other by number, never by cursor
+        response.next = None;
+        response.prev = None;
+        slugs.extend(response.posts.iter().map(|post| post.slug.clone()));
+
+        let content = render_posts_list(response, page, tag.map(String::from), true).await;
+        let path = match page {
+            1 => format!("{}/index.html", base),
+            page => format!("{}/page/{}/index.html", base, page),
+        };
+        let html = render_page(&content, None, &title, &description, &base).await;
+        written += write_file(export.out_dir, &path, html.as_bytes())?;
+
+        if page >= pages {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok((slugs, written))
+}
+
+/// Pre-renders every public post, the pages of the blog index, of each tag
+/// and of each series, the feeds and the sitemap into plain files under
+/// `export.out_dir`, along with the frontend's static files and the uploads
+/// the posts link to. Returns the number of files written.
+pub async fn export_site(
+    db_handler: &impl DBHandler,
+    config: &Config,
+    highlighter: &Highlighter,
+    export: &StaticExport<'_>,
+) -> Result<usize, DbError> {
+    let out_dir = export.out_dir;
+    let base_url = config.WEBSITE_URL.trim_end_matches('/');
+
+    let (slugs, mut written) = export_posts_list(db_handler, export, None).await?;
+    written += write_file(out_dir, "index.html", ROOT_REDIRECT.as_bytes())?;
+
+    for tag in db_handler.get_tags(false).await? {
+        written += export_posts_list(db_handler, export, Some(&tag.tag))
+            .await?
+            .1;
+    }
+
+    let mut uploads: Vec<String> = Vec::new();
+    for slug in slugs {
+        let Some(post) = db_handler.get_post(&slug, false).await? else {
+            continue;
+        };
+        for filename in referenced_uploads(&post.content, &config.FILE_UPLOAD_URL) {
+            if !uploads.contains(&filename) {
+                uploads.push(filename);
+            }
+        }
+
+        let title = post.title.clone();
+        let description = post_description(&post);
+        let content = render_post(db_handler, post, highlighter, None, false).await;
+        let path = format!("post/{}", slug);
+        let html = render_page(&content, None, &title, &description, &path).await;
+        written += write_file(out_dir, &format!("{}/index.html", path), html.as_bytes())?;
+    }
+
+    for series in db_handler.get_all_series().await? {
+        let path = format!("series/{}", series.slug);
+        let title = series.title.clone();
+        let description = series.description.clone();
+        let content = render_series(db_handler, series, false).await?;
+        let html = render_page(&content, None, &title, &description, &path).await;
+        written += write_file(out_dir, &format!("{}/index.html", path), html.as_bytes())?;
+    }
+
+    // Same paths as the live server, so the footer and robots.txt links work
+    let feed = latest_posts_feed(db_handler, config, RSS_FEED_SIZE, base_url).await?;
+    written += write_file(out_dir, "rss", feed.to_string().as_bytes())?;
+    let sitemap = latest_posts_feed(db_handler, config, SITEMAP_SIZE, base_url).await?;
+    written += write_file(out_dir, "sitemap", sitemap.to_string().as_bytes())?;
+    match robots_txt(base_url) {
+        Ok(robots) => written += write_file(out_dir, "robots.txt", robots.as_bytes())?,
+        Err(err) => log::warn!("robots.txt left out of the export: {}", err),
+    }
+
+    match copy_dir(Path::new(STATIC_FILES_DIR), &out_dir.join("static")) {
+        Ok(copied) => written += copied,
+        Err(err) => log::warn!("Static files left out of the export: {}", err),
+    }
+
+    let upload_dir = out_dir.join(config.FILE_UPLOAD_URL.trim_matches('/'));
+    std::fs::create_dir_all(&upload_dir).map_err(io_error)?;
+    for filename in uploads {
+        match std::fs::copy(
+            Path::new(&config.FILE_UPLOAD_PATH).join(&filename),
+            upload_dir.join(&filename),
+        ) {
+            Ok(_) => written += 1,
+            Err(err) => log::warn!("Uploaded file {} left out of the export: {}", filename, err),
+        }
+    }
+
+    Ok(written)
+}
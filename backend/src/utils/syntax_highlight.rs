@@ -7,6 +7,12 @@ pub struct Highlighter {
     ss: SyntaxSet,
 }
 
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Highlighter {
     pub fn new() -> Self {
         let ss = SyntaxSet::load_defaults_newlines();
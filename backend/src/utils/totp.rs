@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::generate_random_alphanumeric_str;
+use crate::database::{DBHandler, DbError};
+use common::Role;
+
+// RFC 6238 codes with the parameters every authenticator app understands
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Steps on either side of the current one whose codes are still accepted,
+/// for clocks that are a bit off.
+const ALLOWED_DRIFT: i64 = 1;
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODES: usize = 10;
+
+/// Whether users with `role` have to log in with a second factor, which an
+/// admin can require of Admins and Editors in the site settings.
+pub async fn is_two_factor_required(
+    db_handler: &impl DBHandler,
+    role: Role,
+) -> Result<bool, DbError> {
+    if !matches!(role, Role::Admin | Role::Editor) {
+        return Ok(false);
+    }
+
+    Ok(db_handler.get_settings().await?.require_two_factor)
+}
+
+/// A new random secret, base32 encoded.
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_BYTES];
+    rand::rng().fill_bytes(&mut secret);
+
+    BASE32_NOPAD.encode(&secret)
+}
+
+/// The `otpauth://` URI authenticator apps read from a QR code.
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC);
+    let account = utf8_percent_encode(account, NON_ALPHANUMERIC);
+
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}\
+        &algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}"
+    )
+}
+
+fn code_at(key: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    binary % 10u32.pow(DIGITS)
+}
+
+/// The time step `code` was generated for, if it is valid around `now`.
+pub fn verify_code(secret: &str, code: &str, now: DateTime<Utc>) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+
+    let current = now.timestamp().div_euclid(STEP_SECS);
+    (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT).find(|step| code_at(&key, *step) == code)
+}
+
+/// New recovery codes, shown to the user once and only stored hashed.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let code = generate_random_alphanumeric_str(10).to_lowercase();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// What is stored of a recovery code. Dashes, spaces and case don't matter.
+pub fn hash_recovery_code(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    format!("{:x}", Sha256::digest(code.as_bytes()))
+}
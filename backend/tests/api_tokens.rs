@@ -0,0 +1,116 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{ApiTokenCreated, Capability, CreateApiTokenRequest, PostCreatedResponse, Role};
+use support::{db_tests, post, read_json, read_text, test_app, TestDb};
+
+fn new_token(scopes: Vec<Capability>) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/tokens")
+        .set_json(CreateApiTokenRequest {
+            name: "deploy".to_string(),
+            scopes,
+            expires_at: None,
+        })
+}
+
+fn bearer(request: TestRequest, token: &str) -> TestRequest {
+    request.insert_header(("authorization", format!("Bearer {}", token)))
+}
+
+async fn tokens_only_do_what_their_scopes_allow<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+
+    // Not beyond what the role grants
+    let response = app
+        .call_as(&editor, new_token(vec![Capability::ManageUsers]))
+        .await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(read_text(response).await, "scopes");
+
+    let response = app
+        .call_as(&editor, new_token(vec![Capability::CreatePost]))
+        .await;
+    assert_eq!(response.status(), 200);
+    let ApiTokenCreated { token, api_token } = read_json(response).await;
+
+    let response = app
+        .call(bearer(
+            TestRequest::post()
+                .uri("/api/post/create")
+                .set_json(post("From a script", true)),
+            &token,
+        ))
+        .await;
+    assert_eq!(response.status(), 200);
+    let PostCreatedResponse { slug } = read_json(response).await;
+
+    // The role could, the token can't
+    let response = app
+        .call(bearer(TestRequest::get().uri("/api/comment/queue"), &token))
+        .await;
+    assert_eq!(response.status(), 403);
+    let response = app
+        .call_as(&editor, TestRequest::get().uri("/api/comment/queue"))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // Neither can it comment, nor make more tokens
+    let response = app
+        .call(bearer(
+            TestRequest::post()
+                .uri("/api/comment/create")
+                .set_json(json!({
+                    "post_slug": slug,
+                    "parent_id": null,
+                    "content": "Posted by a script",
+                })),
+            &token,
+        ))
+        .await;
+    assert_eq!(response.status(), 401);
+    let response = app
+        .call(bearer(new_token(vec![Capability::CreatePost]), &token))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::delete().uri(&format!("/api/auth/tokens/{}", api_token.id)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = app
+        .call(bearer(
+            TestRequest::post()
+                .uri("/api/post/create")
+                .set_json(post("After revoking", true)),
+            &token,
+        ))
+        .await;
+    assert_eq!(response.status(), 401);
+}
+
+async fn unknown_tokens_are_rejected<D: TestDb>() {
+    let app = test_app::<D>().await;
+    app.add_user("alice", Role::Editor).await;
+
+    let response = app
+        .call(bearer(
+            TestRequest::post()
+                .uri("/api/post/create")
+                .set_json(post("Guessed", true)),
+            "not-a-token",
+        ))
+        .await;
+    assert_eq!(response.status(), 401);
+}
+
+db_tests!(
+    tokens_only_do_what_their_scopes_allow,
+    unknown_tokens_are_rejected,
+);
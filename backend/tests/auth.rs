@@ -0,0 +1,279 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use backend::{models::UnconfirmedUser, utils::NewUserRole, Config};
+use common::Role;
+use support::{config, db_tests, no_backoff, post, test_app, test_app_with, TestDb, PASSWORD};
+
+async fn register_confirm_and_login<D: TestDb>() {
+    let app = test_app::<D>().await;
+
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/register")
+                .set_json(json!({
+                    "username": "alice",
+                    "email": "Alice@Blog.test",
+                    "password": PASSWORD,
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // Not a user until the email is confirmed
+    let login = json!({ "username": "alice", "password": PASSWORD });
+    let response = app
+        .call(TestRequest::post().uri("/api/auth/login").set_json(&login))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    let token = app
+        .emailed_token("alice@blog.test", "/confirm/")
+        .await
+        .expect("no confirmation email");
+    let confirmation = json!({ "confirmation_token": token });
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/confirm")
+                .set_json(&confirmation),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/confirm")
+                .set_json(&confirmation),
+        )
+        .await;
+    assert_eq!(response.status(), 409);
+    assert_eq!(support::read_text(response).await, "used");
+
+    let response = app
+        .call(TestRequest::post().uri("/api/auth/login").set_json(&login))
+        .await;
+    assert_eq!(response.status(), 200);
+}
+
+async fn register_rejects_invalid_and_taken_details<D: TestDb>() {
+    let app = test_app::<D>().await;
+    app.add_user("alice", Role::Reader).await;
+
+    for (registration, status, field) in [
+        (
+            json!({ "username": "bob", "email": "not an email", "password": PASSWORD }),
+            400,
+            "email",
+        ),
+        (
+            json!({ "username": "bob", "email": "bob@blog.test", "password": "no" }),
+            400,
+            "password",
+        ),
+        (
+            json!({ "username": "alice", "email": "bob@blog.test", "password": PASSWORD }),
+            409,
+            "username",
+        ),
+        (
+            json!({ "username": "bob", "email": "ALICE@blog.test", "password": PASSWORD }),
+            409,
+            "email",
+        ),
+    ] {
+        let response = app
+            .call(
+                TestRequest::post()
+                    .uri("/api/auth/register")
+                    .set_json(&registration),
+            )
+            .await;
+        assert_eq!(response.status(), status, "{}", registration);
+        assert_eq!(support::read_text(response).await, field);
+    }
+    assert!(app.emailer.sent_emails().await.is_empty());
+}
+
+async fn confirm_unknown_token_is_not_found<D: TestDb>() {
+    let app = test_app::<D>().await;
+
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/confirm")
+                .set_json(json!({ "confirmation_token": "nope" })),
+        )
+        .await;
+    assert_eq!(response.status(), 404);
+}
+
+async fn login_with_wrong_password_fails<D: TestDb>() {
+    let app = test_app::<D>().await;
+    app.add_user("alice", Role::Editor).await;
+
+    for username in ["alice", "nobody"] {
+        let response = app
+            .call(
+                TestRequest::post()
+                    .uri("/api/auth/login")
+                    .set_json(json!({ "username": username, "password": "wrong password" })),
+            )
+            .await;
+        assert_eq!(response.status(), 401);
+    }
+}
+
+async fn logout_ends_the_session<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let cookie = app.user("alice", Role::Editor).await;
+
+    let create = || {
+        TestRequest::post()
+            .uri("/api/post/create")
+            .set_json(post("Hello", true))
+    };
+    assert_eq!(app.call_as(&cookie, create()).await.status(), 200);
+
+    let response = app
+        .call_as(&cookie, TestRequest::get().uri("/logout"))
+        .await;
+    assert_eq!(response.status(), 302);
+    let cookie = support::session_cookie(&response);
+
+    assert_eq!(app.call_as(&cookie, create()).await.status(), 401);
+    assert_eq!(app.call(create()).await.status(), 401);
+}
+
+async fn expired_confirmations_can_be_resent<D: TestDb>() {
+    let app = test_app::<D>().await;
+    app.db
+        .insert_unconfirmed_user(&UnconfirmedUser {
+            confirmation_token: "expired".to_string(),
+            host: support::WEBSITE_URL.to_string(),
+            created_at: Utc::now() - Duration::days(2),
+            confirmed: false,
+            username: "alice".to_string(),
+            email: "alice@blog.test".to_string(),
+            password: bcrypt::hash(PASSWORD, 4).unwrap(),
+            role: Role::Reader,
+        })
+        .await
+        .unwrap();
+
+    let confirm = |token: &str| {
+        TestRequest::post()
+            .uri("/api/auth/confirm")
+            .set_json(json!({ "confirmation_token": token }))
+    };
+    let response = app.call(confirm("expired")).await;
+    assert_eq!(response.status(), 410);
+    assert_eq!(support::read_text(response).await, "expired");
+
+    let resend = || {
+        TestRequest::post()
+            .uri("/api/auth/resend-confirmation")
+            .set_json(json!({ "email": "Alice@blog.test" }))
+    };
+    assert_eq!(app.call(resend()).await.status(), 200);
+    let token = app
+        .emailed_token("alice@blog.test", "/confirm/")
+        .await
+        .expect("no confirmation email");
+    assert_eq!(app.call(confirm("expired")).await.status(), 404);
+    assert_eq!(app.call(confirm(&token)).await.status(), 200);
+
+    // Nothing is left to resend once confirmed
+    assert_eq!(app.call(resend()).await.status(), 200);
+    assert_eq!(app.emailer.sent_emails().await.len(), 1);
+}
+
+async fn confirmation_links_only_point_to_allowed_origins<D: TestDb>() {
+    let app = test_app_with::<D>(
+        Config {
+            ALLOWED_ORIGINS: "http://mirror.test, not an origin".to_string(),
+            ..config()
+        },
+        no_backoff(),
+    )
+    .await;
+
+    for (username, host) in [("alice", "mirror.test"), ("bob", "evil.test")] {
+        let response = app
+            .call(
+                TestRequest::post()
+                    .uri("/api/auth/register")
+                    .insert_header(("host", host))
+                    .set_json(json!({
+                        "username": username,
+                        "email": format!("{}@blog.test", username),
+                        "password": PASSWORD,
+                    })),
+            )
+            .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    let emails = app.emailer.sent_emails().await;
+    assert!(emails[0].1.contains("http://mirror.test/confirm/"));
+    assert!(emails[1]
+        .1
+        .contains(&format!("{}/confirm/", support::WEBSITE_URL)));
+    assert!(!emails[1].1.contains("evil.test"));
+}
+
+async fn new_users_get_the_configured_role<D: TestDb>() {
+    let app = test_app_with::<D>(
+        Config {
+            NEW_USER_DEFAULT_ROLE: "Editor".to_string(),
+            ..config()
+        },
+        no_backoff(),
+    )
+    .await;
+
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/register")
+                .set_json(json!({
+                    "username": "alice",
+                    "email": "alice@blog.test",
+                    "password": PASSWORD,
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let pending = app
+        .db
+        .find_unconfirmed_user("alice")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(pending.role, Role::Editor);
+}
+
+#[test]
+#[should_panic(expected = "`NEW_USER_DEFAULT_ROLE` has to be Admin, Editor or Reader")]
+fn unknown_default_roles_are_refused() {
+    NewUserRole::from_config(&Config {
+        NEW_USER_DEFAULT_ROLE: "Edtior".to_string(),
+        ..config()
+    });
+}
+
+db_tests!(
+    new_users_get_the_configured_role,
+    register_confirm_and_login,
+    register_rejects_invalid_and_taken_details,
+    confirm_unknown_token_is_not_found,
+    login_with_wrong_password_fails,
+    logout_ends_the_session,
+    expired_confirmations_can_be_resent,
+    confirmation_links_only_point_to_allowed_origins,
+);
@@ -0,0 +1,127 @@
+mod support;
+
+use actix_web::test::{self, TestRequest};
+use serde_json::json;
+
+use backend::{utils::import_archive, Config};
+use common::{Comment, CreatePostRequest, ImportOutcome, PostCreatedResponse, Role};
+use support::{config, db_tests, no_backoff, post, read_json, test_app_with, TestDb};
+
+async fn archives_restore_the_whole_blog<D: TestDb>() {
+    let uploads = tempfile::tempdir().unwrap();
+    std::fs::write(uploads.path().join("diagram.png"), b"not really a png").unwrap();
+    let app = test_app_with::<D>(
+        Config {
+            FILE_UPLOAD_PATH: uploads.path().to_string_lossy().into_owned(),
+            ..config()
+        },
+        no_backoff(),
+    )
+    .await;
+    let admin = app.user("alice", Role::Admin).await;
+    let reader = app.user("bob", Role::Reader).await;
+
+    let illustrated = app
+        .create_post(
+            &admin,
+            &CreatePostRequest {
+                content: "See ![the diagram](/uploads/diagram.png).".to_string(),
+                tags: vec!["rust".to_string()],
+                ..post("Illustrated", true)
+            },
+        )
+        .await;
+    let draft = app.create_post(&admin, &post("Draft", false)).await;
+    let response = app
+        .call_as(
+            &admin,
+            TestRequest::post()
+                .uri("/api/series/create")
+                .set_json(json!({
+                    "title": "A series",
+                    "description": "In two parts",
+                    "posts": [illustrated, draft],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let PostCreatedResponse { slug: series } = read_json(response).await;
+    let comment = |parent_id: Option<String>, content: &str| {
+        TestRequest::post()
+            .uri("/api/comment/create")
+            .set_json(json!({
+                "post_slug": illustrated,
+                "parent_id": parent_id,
+                "content": content,
+            }))
+    };
+    let response = app.call_as(&admin, comment(None, "First")).await;
+    let first: Comment = read_json(response).await;
+    let response = app
+        .call_as(&reader, comment(Some(first.id), "A reply, still pending"))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .call_as(&admin, TestRequest::get().uri("/api/backup/export"))
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/x-tar"
+    );
+    let archive = test::read_body(response).await;
+
+    let (restored, _dir) = D::empty().await;
+    let restored_uploads = tempfile::tempdir().unwrap();
+    let report = import_archive(
+        &restored,
+        archive.as_ref(),
+        &restored_uploads.path().to_string_lossy(),
+        false,
+        "alice",
+    )
+    .await
+    .unwrap_or_else(|_| panic!("the archive can't be read"));
+    assert!(report
+        .files
+        .iter()
+        .all(|file| file.outcome == ImportOutcome::Created));
+
+    for slug in [&illustrated, &draft] {
+        assert_eq!(
+            restored.get_post(slug, true).await.unwrap(),
+            app.db.get_post(slug, true).await.unwrap()
+        );
+        assert_eq!(
+            restored.get_comments(slug).await.unwrap(),
+            app.db.get_comments(slug).await.unwrap()
+        );
+    }
+    assert_eq!(restored.get_comments(&illustrated).await.unwrap().len(), 2);
+    assert_eq!(
+        restored.get_series(&series).await.unwrap(),
+        app.db.get_series(&series).await.unwrap()
+    );
+    assert_eq!(
+        std::fs::read(restored_uploads.path().join("diagram.png")).unwrap(),
+        b"not really a png"
+    );
+
+    // Importing again changes nothing
+    let report = import_archive(
+        &restored,
+        archive.as_ref(),
+        &restored_uploads.path().to_string_lossy(),
+        false,
+        "alice",
+    )
+    .await
+    .unwrap_or_else(|_| panic!("the archive can't be read"));
+    assert!(report
+        .files
+        .iter()
+        .all(|file| matches!(file.outcome, ImportOutcome::Skipped(_))));
+}
+
+db_tests!(archives_restore_the_whole_blog);
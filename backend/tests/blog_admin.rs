@@ -0,0 +1,126 @@
+//! Runs the `blog-admin` binary over a SQLite database, with only the
+//! environment variables the command needs.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use backend::database::{post::PostDb, sql::SqlDBHandler, user::UserDb};
+use backend::models::User;
+use common::Role;
+
+/// A database with a user of each role, and the directory holding it.
+async fn database() -> (SqlDBHandler, String, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}", dir.path().join("blog.db").display());
+    let db = SqlDBHandler::new(&url).await.unwrap();
+
+    for (username, role) in [("alice", Role::Editor), ("bob", Role::Reader)] {
+        db.insert_user(&User {
+            id: None,
+            username: username.to_string(),
+            email: format!("{}@blog.test", username),
+            password: bcrypt::hash("correct horse battery staple", 4).unwrap(),
+            role,
+            password_changed_at: None,
+            token_scopes: None,
+        })
+        .await
+        .unwrap();
+    }
+
+    (db, url, dir)
+}
+
+fn blog_admin(dir: &Path, database_url: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_blog-admin"))
+        .args(args)
+        // Away from any `.env` file, which would fill in the rest
+        .current_dir(dir)
+        .env_clear()
+        .env("DATABASE_URL", database_url)
+        .env("FILE_UPLOAD_PATH", dir.join("uploads"))
+        .env("FILE_UPLOAD_URL", "/uploads")
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[actix_web::test]
+async fn invalid_arguments_print_the_usage() {
+    let (_db, url, dir) = database().await;
+
+    for args in [
+        &[][..],
+        &["posts"],
+        &["import-site"],
+        &["import-site", "site"],
+        &["import-site", "site", "--author"],
+        &["import-site", "site", "other-site", "--author", "alice"],
+        &["export-site", "out", "--per-page", "0"],
+        &["export-site", "out", "--per-page", "many"],
+    ] {
+        let output = blog_admin(dir.path(), &url, args);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(
+            stderr(&output).starts_with("usage: blog-admin"),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[actix_web::test]
+async fn sites_are_imported_for_an_existing_author() {
+    let (db, url, dir) = database().await;
+    let site = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/site");
+    let site = site.to_str().unwrap();
+    std::fs::create_dir(dir.path().join("uploads")).unwrap();
+
+    let output = blog_admin(
+        dir.path(),
+        &url,
+        &["import-site", site, "--author", "carol"],
+    );
+    assert!(!output.status.success());
+    assert_eq!(stderr(&output).trim(), "no user carol");
+    let output = blog_admin(dir.path(), &url, &["import-site", site, "--author", "bob"]);
+    assert!(!output.status.success());
+    assert_eq!(stderr(&output).trim(), "bob can't write posts");
+
+    let output = blog_admin(
+        dir.path(),
+        &url,
+        &["import-site", site, "--author", "alice"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("2 of 2 files imported"));
+
+    let post = db.get_post("hello-world", true).await.unwrap().unwrap();
+    assert_eq!(post.title, "Hello, world");
+    assert_eq!(post.author, "alice");
+    assert!(post.public);
+    assert_eq!(post.tags, ["rust", "hugo"]);
+    let uploaded = post
+        .content
+        .split(['(', ')'])
+        .find(|part| part.starts_with("/uploads/"))
+        .unwrap();
+    let uploaded = uploaded.trim_start_matches("/uploads/");
+    assert_eq!(
+        std::fs::read(dir.path().join("uploads").join(uploaded)).unwrap(),
+        b"not really a png"
+    );
+    assert!(!db.get_post("notes", true).await.unwrap().unwrap().public);
+
+    // Importing again skips what is there
+    let output = blog_admin(
+        dir.path(),
+        &url,
+        &["import-site", site, "--author", "alice"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("0 of 2 files imported"));
+}
@@ -0,0 +1,181 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{Comment, CommentStatus, Role};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+async fn comments_of_readers_wait_for_moderation<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let reader = app.user("bob", Role::Reader).await;
+    let slug = app.create_post(&editor, &post("Post", true)).await;
+
+    let response = app
+        .call_as(
+            &reader,
+            TestRequest::post()
+                .uri("/api/comment/create")
+                .set_json(json!({
+                    "post_slug": slug,
+                    "parent_id": null,
+                    "content": "First!",
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let comment: Comment = read_json(response).await;
+    assert_eq!(comment.status, CommentStatus::Pending);
+
+    let list = || TestRequest::get().uri(&format!("/api/comment/list/{}", slug));
+    let visible: Vec<Comment> = read_json(app.call(list()).await).await;
+    assert!(visible.is_empty());
+    let visible: Vec<Comment> = read_json(app.call_as(&reader, list()).await).await;
+    assert_eq!(visible.len(), 1);
+
+    let queue: Vec<Comment> = read_json(
+        app.call_as(&editor, TestRequest::get().uri("/api/comment/queue"))
+            .await,
+    )
+    .await;
+    assert_eq!(queue, std::slice::from_ref(&comment));
+    let response = app
+        .call_as(&reader, TestRequest::get().uri("/api/comment/queue"))
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/comment/moderate")
+                .set_json(json!({ "id": comment.id, "status": "Approved" })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let visible: Vec<Comment> = read_json(app.call(list()).await).await;
+    assert_eq!(visible[0].status, CommentStatus::Approved);
+
+    // Editing sends it back to the queue
+    let response = app
+        .call_as(
+            &reader,
+            TestRequest::post()
+                .uri("/api/comment/update")
+                .set_json(json!({ "id": comment.id, "content": "Second?" })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let visible: Vec<Comment> = read_json(app.call(list()).await).await;
+    assert!(visible.is_empty());
+}
+
+async fn deleting_a_comment_deletes_its_replies<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let reader = app.user("bob", Role::Reader).await;
+    let slug = app.create_post(&editor, &post("Post", true)).await;
+
+    let create = |parent_id: Option<&str>| {
+        TestRequest::post()
+            .uri("/api/comment/create")
+            .set_json(json!({
+                "post_slug": slug,
+                "parent_id": parent_id,
+                "content": "Hello",
+            }))
+    };
+    let parent: Comment = read_json(app.call_as(&editor, create(None)).await).await;
+    assert_eq!(parent.status, CommentStatus::Approved);
+    let reply: Comment = read_json(app.call_as(&reader, create(Some(&parent.id))).await).await;
+    assert_eq!(reply.parent_id.as_deref(), Some(parent.id.as_str()));
+    let response = app.call_as(&reader, create(Some("missing"))).await;
+    assert_eq!(response.status(), 400);
+
+    // Only the author or a moderator can delete it
+    let other = app.user("carol", Role::Reader).await;
+    let delete = || TestRequest::delete().uri(&format!("/api/comment/delete/{}", parent.id));
+    assert_eq!(app.call_as(&other, delete()).await.status(), 403);
+    assert_eq!(app.call_as(&editor, delete()).await.status(), 200);
+
+    let left: Vec<Comment> = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/comment/list/{}", slug)),
+        )
+        .await,
+    )
+    .await;
+    assert!(left.is_empty());
+}
+
+async fn comments_need_a_user_valid_content_and_a_visible_post<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let reader = app.user("bob", Role::Reader).await;
+    let slug = app.create_post(&editor, &post("Post", true)).await;
+    let draft = app.create_post(&editor, &post("Draft", false)).await;
+
+    let create = |post_slug: &str, content: &str| {
+        TestRequest::post()
+            .uri("/api/comment/create")
+            .set_json(json!({
+                "post_slug": post_slug,
+                "parent_id": null,
+                "content": content,
+            }))
+    };
+    assert_eq!(app.call(create(&slug, "Hi")).await.status(), 401);
+    assert_eq!(app.call_as(&reader, create(&slug, "")).await.status(), 400);
+    assert_eq!(
+        app.call_as(&reader, create(&draft, "Hi")).await.status(),
+        404
+    );
+    assert_eq!(
+        app.call_as(&reader, create("missing", "Hi")).await.status(),
+        404
+    );
+
+    // Other readers can't change it
+    let comment: Comment = read_json(app.call_as(&reader, create(&slug, "Hi")).await).await;
+    let other = app.user("carol", Role::Reader).await;
+    let response = app
+        .call_as(
+            &other,
+            TestRequest::post()
+                .uri("/api/comment/update")
+                .set_json(json!({ "id": comment.id, "content": "Mine now" })),
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+}
+
+async fn the_server_rendered_forms_redirect_back_to_the_post<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app.create_post(&editor, &post("Post", true)).await;
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri("/comment").set_form([
+                ("post_slug", slug.as_str()),
+                ("parent_id", ""),
+                ("content", "From the form"),
+            ]),
+        )
+        .await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(
+        response.headers().get("location").unwrap(),
+        &format!("/post/{}#comments", slug)
+    );
+}
+
+db_tests!(
+    comments_of_readers_wait_for_moderation,
+    deleting_a_comment_deletes_its_replies,
+    comments_need_a_user_valid_content_and_a_visible_post,
+    the_server_rendered_forms_redirect_back_to_the_post,
+);
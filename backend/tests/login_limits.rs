@@ -0,0 +1,127 @@
+mod support;
+
+use std::net::SocketAddr;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use backend::{services::login_limiter::LoginLimits, Config};
+use common::Role;
+use support::{config, db_tests, no_backoff, test_app_with, TestDb, PASSWORD};
+
+const PROXY: &str = "10.0.0.1:443";
+
+fn login(username: &str, password: &str, peer: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/login")
+        .peer_addr(peer.parse::<SocketAddr>().unwrap())
+        .set_json(json!({ "username": username, "password": password }))
+}
+
+async fn accounts_are_locked_after_too_many_failures<D: TestDb>() {
+    let app = test_app_with::<D>(
+        config(),
+        LoginLimits {
+            max_account_failures: 3,
+            ..no_backoff()
+        },
+    )
+    .await;
+    app.add_user("alice", Role::Editor).await;
+    app.add_user("bob", Role::Editor).await;
+
+    for _ in 0..3 {
+        let response = app.call(login("alice", "wrong", "1.1.1.1:1")).await;
+        assert_eq!(response.status(), 401);
+    }
+
+    // Not even the right password gets in, from anywhere
+    let response = app.call(login("Alice", PASSWORD, "2.2.2.2:1")).await;
+    assert_eq!(response.status(), 429);
+    let retry_after: u64 = response
+        .headers()
+        .get("retry-after")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(retry_after, 900);
+
+    // Other accounts from the same IP are fine
+    let response = app.call(login("bob", PASSWORD, "1.1.1.1:1")).await;
+    assert_eq!(response.status(), 200);
+}
+
+async fn forwarded_headers_are_ignored_from_untrusted_peers<D: TestDb>() {
+    let app = test_app_with::<D>(
+        config(),
+        LoginLimits {
+            max_ip_failures: 2,
+            ..no_backoff()
+        },
+    )
+    .await;
+    app.add_user("alice", Role::Editor).await;
+
+    for (username, forwarded) in [("nobody", "3.3.3.3"), ("someone", "4.4.4.4")] {
+        let response = app
+            .call(
+                login(username, "wrong", "1.1.1.1:1").insert_header(("x-forwarded-for", forwarded)),
+            )
+            .await;
+        assert_eq!(response.status(), 401);
+    }
+
+    let response = app
+        .call(login("alice", PASSWORD, "1.1.1.1:2").insert_header(("x-forwarded-for", "5.5.5.5")))
+        .await;
+    assert_eq!(response.status(), 429);
+}
+
+async fn trusted_proxies_forward_the_client_ip<D: TestDb>() {
+    let app = test_app_with::<D>(
+        Config {
+            TRUSTED_PROXIES: "10.0.0.1, 10.0.0.2".to_string(),
+            ..config()
+        },
+        LoginLimits {
+            max_ip_failures: 2,
+            ..no_backoff()
+        },
+    )
+    .await;
+    app.add_user("alice", Role::Editor).await;
+
+    let through_proxy = |username: &str, password: &str, forwarded: &str| {
+        login(username, password, PROXY).insert_header(("x-forwarded-for", forwarded.to_string()))
+    };
+    for username in ["nobody", "someone"] {
+        let response = app
+            .call(through_proxy(username, "wrong", "1.1.1.1, 10.0.0.2"))
+            .await;
+        assert_eq!(response.status(), 401);
+    }
+
+    // Only the client behind the proxy is locked out
+    let response = app.call(through_proxy("alice", PASSWORD, "2.2.2.2")).await;
+    assert_eq!(response.status(), 200);
+    // Even when it claims to be someone else, as the proxy adds its real IP last
+    let response = app
+        .call(through_proxy("alice", PASSWORD, "2.2.2.2, 1.1.1.1"))
+        .await;
+    assert_eq!(response.status(), 429);
+    let response = app
+        .call(
+            login("alice", PASSWORD, PROXY)
+                .insert_header(("forwarded", "for=2.2.2.2, for=\"1.1.1.1:1234\"")),
+        )
+        .await;
+    assert_eq!(response.status(), 429);
+}
+
+db_tests!(
+    accounts_are_locked_after_too_many_failures,
+    forwarded_headers_are_ignored_from_untrusted_peers,
+    trusted_proxies_forward_the_client_ip,
+);
@@ -0,0 +1,110 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+
+use common::{CreatePostRequest, GetPostsResponse, Role, SearchResponse};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn titles(response: &GetPostsResponse) -> Vec<&str> {
+    response
+        .posts
+        .iter()
+        .map(|post| post.title.as_str())
+        .collect()
+}
+
+async fn cursors_walk_the_posts_both_ways<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    // Spaced out so that the order doesn't depend on the clock
+    for (days, title) in ["A", "B", "C", "D", "E"].into_iter().enumerate() {
+        let request = CreatePostRequest {
+            published_at: Some(Utc::now() - Duration::days(5 - days as i64)),
+            ..post(title, true)
+        };
+        app.create_post(&editor, &request).await;
+    }
+
+    let app = &app;
+    let page = |query: String| async move {
+        let response = app
+            .call(TestRequest::get().uri(&format!("/api/post/get-list?per_page=2{}", query)))
+            .await;
+        assert_eq!(response.status(), 200);
+        read_json::<GetPostsResponse, _>(response).await
+    };
+
+    let first = page(String::new()).await;
+    assert_eq!(titles(&first), ["E", "D"]);
+    assert_eq!(first.prev, None);
+    let second = page(format!("&after={}", first.next.unwrap())).await;
+    assert_eq!(titles(&second), ["C", "B"]);
+    let last = page(format!("&after={}", second.next.unwrap())).await;
+    assert_eq!(titles(&last), ["A"]);
+    assert_eq!(last.next, None);
+
+    let back = page(format!("&before={}", last.prev.unwrap())).await;
+    assert_eq!(titles(&back), ["C", "B"]);
+    // A short page going back becomes the full first page
+    let back = page(format!("&before={}", back.prev.unwrap())).await;
+    assert_eq!(titles(&back), ["E", "D"]);
+    assert_eq!(back.prev, None);
+}
+
+async fn malformed_cursors_are_rejected<D: TestDb>() {
+    let app = test_app::<D>().await;
+
+    for query in ["after=not-a-cursor", "before=bm8tY29sb24"] {
+        let response = app
+            .call(TestRequest::get().uri(&format!("/api/post/get-list?{}", query)))
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+}
+
+async fn page_sizes_are_clamped<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    for title in ["A", "B", "C"] {
+        app.create_post(&editor, &post(title, true)).await;
+    }
+
+    let list = |query: &str| TestRequest::get().uri(&format!("/api/post/get-list?{}", query));
+    let response = app.call(list(&format!("per_page={}", u64::MAX))).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        read_json::<GetPostsResponse, _>(response).await.posts.len(),
+        3
+    );
+    let response = app.call(list("per_page=0")).await;
+    assert_eq!(
+        read_json::<GetPostsResponse, _>(response).await.posts.len(),
+        1
+    );
+    let response = app
+        .call(list(&format!("per_page={0}&page={0}", u64::MAX)))
+        .await;
+    assert!(read_json::<GetPostsResponse, _>(response)
+        .await
+        .posts
+        .is_empty());
+
+    let response = app
+        .call(TestRequest::get().uri(&format!(
+            "/api/post/search?q=content&per_page={0}&page={0}",
+            u64::MAX
+        )))
+        .await;
+    assert_eq!(response.status(), 200);
+    assert!(read_json::<SearchResponse, _>(response)
+        .await
+        .hits
+        .is_empty());
+}
+
+db_tests!(
+    cursors_walk_the_posts_both_ways,
+    malformed_cursors_are_rejected,
+    page_sizes_are_clamped,
+);
@@ -0,0 +1,79 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use backend::services::login_limiter::LoginLimits;
+use common::Role;
+use support::{config, db_tests, no_backoff, test_app, test_app_with, TestDb};
+
+fn forgot(email: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/forgot-password")
+        .set_json(json!({ "email": email }))
+}
+
+fn reset(token: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/reset-password")
+        .set_json(json!({ "token": token, "password": "a new password" }))
+}
+
+async fn reset_links_work_once<D: TestDb>() {
+    let app = test_app::<D>().await;
+    app.add_user("alice", Role::Reader).await;
+
+    let response = app.call(forgot("Alice@Blog.test")).await;
+    assert_eq!(response.status(), 200);
+    let token = app
+        .emailed_token("alice@blog.test", "/reset-password/")
+        .await
+        .unwrap();
+    // Only its hash is stored
+    assert!(app.db.take_password_reset(&token).await.unwrap().is_none());
+
+    assert_eq!(app.call(reset(&token)).await.status(), 200);
+    assert_eq!(app.call(reset(&token)).await.status(), 404);
+    let response = app
+        .call(
+            TestRequest::post()
+                .uri("/api/auth/login")
+                .set_json(json!({ "username": "alice", "password": "a new password" })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // Unknown addresses get the same answer, and no email
+    let response = app.call(forgot("nobody@blog.test")).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(app.emailer.sent_emails().await.len(), 1);
+}
+
+async fn reset_requests_are_throttled<D: TestDb>() {
+    let app = test_app_with::<D>(
+        config(),
+        LoginLimits {
+            max_ip_reset_requests: 4,
+            max_email_reset_requests: 2,
+            ..no_backoff()
+        },
+    )
+    .await;
+    app.add_user("alice", Role::Reader).await;
+
+    // Past the limit of the address, requests look the same but send nothing
+    for _ in 0..3 {
+        let response = app.call(forgot("alice@blog.test")).await;
+        assert_eq!(response.status(), 200);
+    }
+    assert_eq!(app.emailer.sent_emails().await.len(), 2);
+
+    // Past the limit of the IP, whatever the address, it has to wait
+    let response = app.call(forgot("bob@blog.test")).await;
+    assert_eq!(response.status(), 200);
+    let response = app.call(forgot("carol@blog.test")).await;
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+db_tests!(reset_links_work_once, reset_requests_are_throttled,);
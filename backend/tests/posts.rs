@@ -0,0 +1,168 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{utils::MAX_PREVIEW_LENGTH, CreatePostRequest, GetPostsResponse, Post, Role};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+async fn create_read_update_and_delete<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+
+    let slug = app.create_post(&editor, &post("Hello world", true)).await;
+    assert_eq!(slug, "hello-world");
+
+    let response = app
+        .call(TestRequest::get().uri("/api/post/read/hello-world"))
+        .await;
+    assert_eq!(response.status(), 200);
+    let read: Post = read_json(response).await;
+    assert_eq!(read.title, "Hello world");
+    assert_eq!(read.author, "alice");
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri("/api/post/update").set_json(json!({
+                "slug": slug,
+                "title": "Hello again",
+                "content": "New content",
+                "summary": null,
+                "public": true,
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let read: Post = read_json(
+        app.call(TestRequest::get().uri("/api/post/read/hello-world"))
+            .await,
+    )
+    .await;
+    assert_eq!(read.title, "Hello again");
+    assert_eq!(read.content, "New content");
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::delete().uri("/api/post/delete/hello-world"),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = app
+        .call(TestRequest::get().uri("/api/post/read/hello-world"))
+        .await;
+    assert_eq!(response.status(), 404);
+}
+
+async fn same_titles_get_unique_slugs<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+
+    let first = app.create_post(&editor, &post("Twice", true)).await;
+    let second = app.create_post(&editor, &post("Twice", true)).await;
+    assert_ne!(first, second);
+}
+
+async fn readers_and_visitors_cannot_write<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let reader = app.user("bob", Role::Reader).await;
+    let slug = app.create_post(&editor, &post("Mine", true)).await;
+
+    let create = || {
+        TestRequest::post()
+            .uri("/api/post/create")
+            .set_json(post("Theirs", true))
+    };
+    assert_eq!(app.call(create()).await.status(), 401);
+    assert_eq!(app.call_as(&reader, create()).await.status(), 403);
+
+    let delete = || TestRequest::delete().uri(&format!("/api/post/delete/{}", slug));
+    assert_eq!(app.call(delete()).await.status(), 401);
+    assert_eq!(app.call_as(&reader, delete()).await.status(), 403);
+}
+
+async fn editors_only_edit_their_own_posts<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let alice = app.user("alice", Role::Editor).await;
+    let bob = app.user("bob", Role::Editor).await;
+    let admin = app.user("carol", Role::Admin).await;
+    let slug = app.create_post(&alice, &post("Alice's post", true)).await;
+
+    let update = || {
+        TestRequest::post().uri("/api/post/update").set_json(json!({
+            "slug": slug,
+            "title": "Changed",
+            "content": "Changed",
+            "summary": null,
+            "public": true,
+        }))
+    };
+    assert_eq!(app.call_as(&bob, update()).await.status(), 403);
+    assert_eq!(app.call_as(&admin, update()).await.status(), 200);
+}
+
+async fn drafts_are_only_shown_to_those_who_can_view_them<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let reader = app.user("bob", Role::Reader).await;
+    app.create_post(&editor, &post("Published", true)).await;
+    let draft = app.create_post(&editor, &post("Draft", false)).await;
+
+    let read = || TestRequest::get().uri(&format!("/api/post/read/{}", draft));
+    assert_eq!(app.call(read()).await.status(), 404);
+    assert_eq!(app.call_as(&reader, read()).await.status(), 404);
+    assert_eq!(app.call_as(&editor, read()).await.status(), 200);
+
+    // Posts created in the same millisecond have no set order
+    let titles = |response: GetPostsResponse| {
+        let mut titles: Vec<_> = response.posts.into_iter().map(|post| post.title).collect();
+        titles.sort();
+        titles
+    };
+    let list = || TestRequest::get().uri("/api/post/get-list");
+    assert_eq!(
+        titles(read_json(app.call(list()).await).await),
+        ["Published"]
+    );
+    assert_eq!(
+        titles(read_json(app.call_as(&reader, list()).await).await),
+        ["Published"]
+    );
+    assert_eq!(
+        titles(read_json(app.call_as(&editor, list()).await).await),
+        ["Draft", "Published"]
+    );
+}
+
+async fn previews_cut_multibyte_text_between_characters<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    // Three bytes per character after the first, so the limit falls inside one
+    let content = format!("a{}", "日本語".repeat(MAX_PREVIEW_LENGTH));
+    app.create_post(
+        &editor,
+        &CreatePostRequest {
+            content: content.clone(),
+            ..post("Unicode", true)
+        },
+    )
+    .await;
+
+    let response: GetPostsResponse =
+        read_json(app.call(TestRequest::get().uri("/api/post/get-list")).await).await;
+    let preview = response.posts[0].summary.strip_suffix("...").unwrap();
+    assert!(content.starts_with(preview));
+    assert!(preview.len() <= MAX_PREVIEW_LENGTH);
+    assert!(preview.len() > MAX_PREVIEW_LENGTH - 3);
+}
+
+db_tests!(
+    create_read_update_and_delete,
+    same_titles_get_unique_slugs,
+    readers_and_visitors_cannot_write,
+    editors_only_edit_their_own_posts,
+    drafts_are_only_shown_to_those_who_can_view_them,
+    previews_cut_multibyte_text_between_characters,
+);
@@ -0,0 +1,150 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use common::{DiffTag, Post, PostRevision, RevisionDiff, Role};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+async fn every_save_adds_a_revision<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app.create_post(&editor, &post("First", true)).await;
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri("/api/post/update").set_json(json!({
+                "slug": slug,
+                "title": "Second",
+                "content": "The content of First.\nAnd more.",
+                "summary": null,
+                "public": true,
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let revisions: Vec<PostRevision> = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/post/revisions/{}", slug)),
+        )
+        .await,
+    )
+    .await;
+    let titles: Vec<_> = revisions
+        .iter()
+        .map(|revision| (revision.number, revision.title.as_str()))
+        .collect();
+    assert_eq!(titles, [(1, "First"), (2, "Second")]);
+
+    let diff: RevisionDiff = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/post/revisions/{}/diff?from=1&to=2", slug)),
+        )
+        .await,
+    )
+    .await;
+    let inserted: Vec<_> = diff
+        .content
+        .iter()
+        .filter(|line| line.tag == DiffTag::Insert)
+        .map(|line| line.text.as_str())
+        .collect();
+    assert_eq!(inserted, ["The content of First.", "And more."]);
+
+    // Visitors and readers don't get to see the history
+    let history = || TestRequest::get().uri(&format!("/api/post/revisions/{}", slug));
+    assert_eq!(app.call(history()).await.status(), 401);
+    let reader = app.user("bob", Role::Reader).await;
+    assert_eq!(app.call_as(&reader, history()).await.status(), 403);
+}
+
+async fn restore_brings_back_the_whole_post<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let published_at = Utc::now() - Duration::days(3);
+    let slug = app
+        .create_post(
+            &editor,
+            &common::CreatePostRequest {
+                published_at: Some(published_at),
+                tags: vec!["rust".to_string(), "web".to_string()],
+                ..post("Original", true)
+            },
+        )
+        .await;
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri("/api/post/update").set_json(json!({
+                "slug": slug,
+                "title": "Changed",
+                "content": "Changed",
+                "summary": "Changed",
+                "public": false,
+                "published_at": Utc::now() - Duration::days(1),
+                "tags": ["other"],
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri(&format!("/api/post/revisions/{}/1/restore", slug)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let restored: Post = read_json(
+        app.call(TestRequest::get().uri(&format!("/api/post/read/{}", slug)))
+            .await,
+    )
+    .await;
+    assert_eq!(restored.title, "Original");
+    assert_eq!(restored.summary, None);
+    assert!(restored.public);
+    assert_eq!(
+        restored.published_at.timestamp_millis(),
+        published_at.timestamp_millis()
+    );
+    assert_eq!(restored.tags, ["rust", "web"]);
+
+    // The restore is a revision of its own
+    let revisions: Vec<PostRevision> = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/post/revisions/{}", slug)),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[2].tags.as_deref(), Some(&restored.tags[..]));
+}
+
+async fn only_the_author_restores_their_post<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let alice = app.user("alice", Role::Editor).await;
+    let bob = app.user("bob", Role::Editor).await;
+    let slug = app.create_post(&alice, &post("Alice's", true)).await;
+
+    let restore = || TestRequest::post().uri(&format!("/api/post/revisions/{}/1/restore", slug));
+    assert_eq!(app.call_as(&bob, restore()).await.status(), 403);
+    assert_eq!(app.call_as(&alice, restore()).await.status(), 200);
+
+    let missing = TestRequest::post().uri(&format!("/api/post/revisions/{}/9/restore", slug));
+    assert_eq!(app.call_as(&alice, missing).await.status(), 404);
+}
+
+db_tests!(
+    every_save_adds_a_revision,
+    restore_brings_back_the_whole_post,
+    only_the_author_restores_their_post,
+);
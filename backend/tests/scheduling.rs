@@ -0,0 +1,57 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+
+use common::{CreatePostRequest, GetPostsResponse, PostRevision, Role};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn scheduled(title: &str) -> CreatePostRequest {
+    CreatePostRequest {
+        published_at: Some(Utc::now() + Duration::days(7)),
+        ..post(title, true)
+    }
+}
+
+async fn scheduled_posts_stay_hidden_until_their_date<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &post("Now", true)).await;
+    let slug = app.create_post(&editor, &scheduled("Later")).await;
+
+    let read = || TestRequest::get().uri(&format!("/api/post/read/{}", slug));
+    assert_eq!(app.call(read()).await.status(), 404);
+    assert_eq!(app.call_as(&editor, read()).await.status(), 200);
+
+    let list: GetPostsResponse =
+        read_json(app.call(TestRequest::get().uri("/api/post/get-list")).await).await;
+    let titles: Vec<_> = list.posts.iter().map(|post| post.title.as_str()).collect();
+    assert_eq!(titles, ["Now"]);
+
+    let feed = support::read_text(app.call(TestRequest::get().uri("/rss")).await).await;
+    assert!(feed.contains("Now"));
+    assert!(!feed.contains("Later"));
+}
+
+async fn revisions_of_scheduled_posts_are_dated_when_saved<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app.create_post(&editor, &scheduled("Later")).await;
+
+    let revisions: Vec<PostRevision> = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/post/revisions/{}", slug)),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(revisions.len(), 1);
+    assert!(revisions[0].created_at <= Utc::now());
+    assert!(revisions[0].published_at > Some(Utc::now()));
+}
+
+db_tests!(
+    scheduled_posts_stay_hidden_until_their_date,
+    revisions_of_scheduled_posts_are_dated_when_saved,
+);
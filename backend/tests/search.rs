@@ -0,0 +1,98 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+
+use backend::database::MAX_SEARCH_RESULTS;
+use common::{CreatePostRequest, Post, Role, SearchResponse};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn titles(response: &SearchResponse) -> Vec<&str> {
+    response
+        .hits
+        .iter()
+        .map(|hit| hit.post.title.as_str())
+        .collect()
+}
+
+async fn matches_in_titles_rank_first<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(
+        &editor,
+        &CreatePostRequest {
+            content: "Mostly about ownership, and a bit about borrowing.".to_string(),
+            ..post("Ownership", true)
+        },
+    )
+    .await;
+    app.create_post(&editor, &post("Borrowing", true)).await;
+    app.create_post(&editor, &post("Lifetimes", true)).await;
+
+    let search = |query: &str| TestRequest::get().uri(&format!("/api/post/search?q={}", query));
+    let response: SearchResponse = read_json(app.call(search("BORROWING")).await).await;
+    assert_eq!(titles(&response), ["Borrowing", "Ownership"]);
+
+    // The snippet highlights what matched
+    let highlighted: Vec<_> = response.hits[1]
+        .snippet
+        .iter()
+        .filter(|fragment| fragment.highlight)
+        .map(|fragment| fragment.text.to_lowercase())
+        .collect();
+    assert_eq!(highlighted, ["borrowing"]);
+
+    let response: SearchResponse = read_json(app.call(search("nothing")).await).await;
+    assert!(response.hits.is_empty());
+}
+
+async fn drafts_are_only_found_by_those_who_can_view_them<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &post("Secret draft", false)).await;
+
+    let search = || TestRequest::get().uri("/api/post/search?q=secret");
+    let response: SearchResponse = read_json(app.call(search()).await).await;
+    assert!(response.hits.is_empty());
+    let response: SearchResponse = read_json(app.call_as(&editor, search()).await).await;
+    assert_eq!(titles(&response), ["Secret draft"]);
+}
+
+async fn only_the_most_relevant_results_are_kept<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let post = |slug: String, title: &str, days_ago: i64| Post {
+        slug,
+        title: title.to_string(),
+        content: "Something about rust.".to_string(),
+        summary: None,
+        author: "alice".to_string(),
+        published_at: Utc::now() - Duration::days(days_ago),
+        public: true,
+        tags: Vec::new(),
+    };
+    // Older than every post that only mentions it
+    app.db
+        .create_post(&post("rust".to_string(), "Rust", 1000))
+        .await
+        .unwrap();
+    for n in 0..MAX_SEARCH_RESULTS {
+        app.db
+            .create_post(&post(format!("post-{}", n), "Post", n as i64))
+            .await
+            .unwrap();
+    }
+
+    let response: SearchResponse = read_json(
+        app.call(TestRequest::get().uri("/api/post/search?q=rust&per_page=10"))
+            .await,
+    )
+    .await;
+    assert_eq!(response.hits[0].post.title, "Rust");
+    assert_eq!(response.pages, MAX_SEARCH_RESULTS as u64 / 10);
+}
+
+db_tests!(
+    matches_in_titles_rank_first,
+    drafts_are_only_found_by_those_who_can_view_them,
+    only_the_most_relevant_results_are_kept,
+);
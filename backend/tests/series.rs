@@ -0,0 +1,112 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{PostCreatedResponse, Role, SeriesOverview};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn part_slugs(series: &SeriesOverview) -> Vec<&str> {
+    series.parts.iter().map(|part| part.slug.as_str()).collect()
+}
+
+async fn series_list_their_parts_in_order<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let first = app.create_post(&editor, &post("Part one", true)).await;
+    let second = app.create_post(&editor, &post("Part two", true)).await;
+    let draft = app.create_post(&editor, &post("Part three", false)).await;
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/series/create")
+                .set_json(json!({
+                    "title": "A series",
+                    "description": "In three parts",
+                    "posts": [second, first, draft],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let slug = read_json::<PostCreatedResponse, _>(response).await.slug;
+    assert_eq!(slug, "a-series");
+
+    let read = || TestRequest::get().uri("/api/series/read/a-series");
+    let series: SeriesOverview = read_json(app.call(read()).await).await;
+    assert_eq!(part_slugs(&series), [second.as_str(), first.as_str()]);
+    let series: SeriesOverview = read_json(app.call_as(&editor, read()).await).await;
+    assert_eq!(
+        part_slugs(&series),
+        [second.as_str(), first.as_str(), draft.as_str()]
+    );
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/series/update")
+                .set_json(json!({
+                    "slug": slug,
+                    "title": "A series",
+                    "description": "Reordered",
+                    "posts": [first, second],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let series: SeriesOverview = read_json(app.call(read()).await).await;
+    assert_eq!(series.description, "Reordered");
+    assert_eq!(part_slugs(&series), [first.as_str(), second.as_str()]);
+
+    let list: Vec<SeriesOverview> = read_json(
+        app.call(TestRequest::get().uri("/api/series/get-list"))
+            .await,
+    )
+    .await;
+    assert_eq!(list.len(), 1);
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::delete().uri("/api/series/delete/a-series"),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(app.call(read()).await.status(), 404);
+}
+
+async fn parts_have_to_exist_and_belong_to_one_series<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app.create_post(&editor, &post("Part", true)).await;
+
+    let create = |posts: serde_json::Value| {
+        TestRequest::post()
+            .uri("/api/series/create")
+            .set_json(json!({
+                "title": "Series",
+                "description": "",
+                "posts": posts,
+            }))
+    };
+    let response = app.call_as(&editor, create(json!(["missing"]))).await;
+    assert_eq!(response.status(), 400);
+    let response = app.call_as(&editor, create(json!([slug, slug]))).await;
+    assert_eq!(response.status(), 400);
+
+    let response = app.call_as(&editor, create(json!([slug]))).await;
+    assert_eq!(response.status(), 200);
+    let response = app.call_as(&editor, create(json!([slug]))).await;
+    assert_eq!(response.status(), 409);
+
+    let reader = app.user("bob", Role::Reader).await;
+    let response = app.call_as(&reader, create(json!([]))).await;
+    assert_eq!(response.status(), 403);
+}
+
+db_tests!(
+    series_list_their_parts_in_order,
+    parts_have_to_exist_and_belong_to_one_series,
+);
@@ -0,0 +1,111 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use common::{PostCreatedResponse, Role};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn rename(slug: &str, new_slug: &str) -> TestRequest {
+    TestRequest::post().uri("/api/post/update").set_json(json!({
+        "slug": slug,
+        "title": "Title",
+        "content": "Content",
+        "summary": null,
+        "public": true,
+        "new_slug": new_slug,
+    }))
+}
+
+async fn old_slugs_redirect_to_the_new_one<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &post("Old name", true)).await;
+
+    let response = app.call_as(&editor, rename("old-name", "New Name")).await;
+    assert_eq!(response.status(), 200);
+    let slug = read_json::<PostCreatedResponse, _>(response).await.slug;
+    assert_eq!(slug, "new-name");
+
+    let response = app
+        .call(TestRequest::get().uri("/api/post/read/old-name"))
+        .await;
+    assert_eq!(response.status(), 301);
+    assert_eq!(
+        response.headers().get("location").unwrap(),
+        "/api/post/read/new-name"
+    );
+
+    // A second rename keeps every old slug pointing at the post
+    app.call_as(&editor, rename("new-name", "newest-name"))
+        .await;
+    for old in ["old-name", "new-name"] {
+        let response = app
+            .call(TestRequest::get().uri(&format!("/api/post/read/{}", old)))
+            .await;
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/api/post/read/newest-name"
+        );
+    }
+
+    // And the post can take one of them back
+    let response = app
+        .call_as(&editor, rename("newest-name", "old-name"))
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = app
+        .call(TestRequest::get().uri("/api/post/read/old-name"))
+        .await;
+    assert_eq!(response.status(), 200);
+}
+
+async fn renames_cannot_take_a_slug_in_use<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &post("First", true)).await;
+    app.create_post(&editor, &post("Second", true)).await;
+
+    let response = app.call_as(&editor, rename("second", "first")).await;
+    assert_eq!(response.status(), 409);
+    let response = app.call_as(&editor, rename("second", "!!!")).await;
+    assert_eq!(response.status(), 400);
+
+    // Old slugs of other posts are taken too
+    app.call_as(&editor, rename("first", "renamed")).await;
+    let response = app.call_as(&editor, rename("second", "first")).await;
+    assert_eq!(response.status(), 409);
+    // And new posts don't get them
+    let slug = app.create_post(&editor, &post("First", true)).await;
+    assert_ne!(slug, "first");
+}
+
+async fn redirects_to_drafts_are_hidden_from_visitors<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &post("Draft", false)).await;
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post().uri("/api/post/update").set_json(json!({
+                "slug": "draft",
+                "title": "Draft",
+                "content": "Content",
+                "summary": null,
+                "public": false,
+                "new_slug": "still-a-draft",
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let old = || TestRequest::get().uri("/api/post/read/draft");
+    assert_eq!(app.call(old()).await.status(), 404);
+    assert_eq!(app.call_as(&editor, old()).await.status(), 301);
+}
+
+db_tests!(
+    old_slugs_redirect_to_the_new_one,
+    renames_cannot_take_a_slug_in_use,
+    redirects_to_drafts_are_hidden_from_visitors,
+);
@@ -0,0 +1,50 @@
+mod support;
+
+use backend::utils::{export_site, Highlighter, StaticExport};
+use common::{CreatePostRequest, Role};
+use support::{config, db_tests, post, test_app, TestDb};
+
+async fn the_export_has_a_page_for_each_public_post<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let admin = app.user("alice", Role::Admin).await;
+
+    // 2 bytes a character after the first, the description limit falls inside one
+    let summary = format!("a{}", "é".repeat(150));
+    let accented = app
+        .create_post(
+            &admin,
+            &CreatePostRequest {
+                summary: Some(summary.clone()),
+                ..post("Accented", true)
+            },
+        )
+        .await;
+    let draft = app.create_post(&admin, &post("Draft", false)).await;
+
+    let out_dir = tempfile::tempdir().unwrap();
+    let written = export_site(
+        &app.db,
+        &config(),
+        &Highlighter::new(),
+        &StaticExport {
+            out_dir: out_dir.path(),
+            per_page: 10,
+        },
+    )
+    .await
+    .unwrap_or_else(|_| panic!("the site can't be exported"));
+    assert!(written > 0);
+
+    let page =
+        std::fs::read_to_string(out_dir.path().join(format!("post/{}/index.html", accented)))
+            .unwrap();
+    assert!(page.contains(&format!(r#"description" content="a{}""#, "é".repeat(99))));
+    assert!(!page.contains(&summary));
+    assert!(!out_dir
+        .path()
+        .join(format!("post/{}/index.html", draft))
+        .exists());
+    assert!(out_dir.path().join("blog/index.html").is_file());
+}
+
+db_tests!(the_export_has_a_page_for_each_public_post);
@@ -0,0 +1,288 @@
+// Each test file uses its own part of these helpers
+#![allow(dead_code)]
+
+use actix_http::Request;
+use actix_identity::IdentityMiddleware;
+use actix_session::{storage::CookieSessionStore, SessionMiddleware};
+use actix_web::{
+    body::MessageBody,
+    cookie::{Cookie, Key},
+    dev::{Service, ServiceResponse},
+    test::{self, TestRequest},
+    web::Data,
+    App, Error,
+};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use backend::{
+    database::{memory::InMemoryDBHandler, sql::SqlDBHandler, DBHandler},
+    handlers,
+    models::User,
+    services::{
+        email::Emailer,
+        login_limiter::{LoginLimiter, LoginLimits},
+    },
+    utils::{Highlighter, NewUserRole, SiteUrl},
+    Config,
+};
+use common::{CreatePostRequest, PostCreatedResponse, Role};
+
+pub const WEBSITE_URL: &str = "http://blog.test";
+/// The password of every user made by `TestApp::add_user`.
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// A database the tests run against, see `db_tests!`.
+pub trait TestDb: DBHandler + Clone + Send + Sync + 'static {
+    /// An empty database, with the directory holding it if it is a file.
+    async fn empty() -> (Self, Option<TempDir>);
+}
+
+impl TestDb for InMemoryDBHandler {
+    async fn empty() -> (Self, Option<TempDir>) {
+        (InMemoryDBHandler::new(), None)
+    }
+}
+
+impl TestDb for SqlDBHandler {
+    async fn empty() -> (Self, Option<TempDir>) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("blog.db").display());
+
+        (SqlDBHandler::new(&url).await.unwrap(), Some(dir))
+    }
+}
+
+/// Runs tests written as `async fn name<D: TestDb>()` over each database, as
+/// `memory::name` and `sqlite::name`.
+macro_rules! db_tests {
+    ($($test:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[actix_web::test]
+                async fn $test() {
+                    super::$test::<backend::database::memory::InMemoryDBHandler>().await
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[actix_web::test]
+                async fn $test() {
+                    super::$test::<backend::database::sql::SqlDBHandler>().await
+                }
+            )*
+        }
+    };
+}
+pub(crate) use db_tests;
+
+pub fn config() -> Config {
+    Config {
+        DATABASE_URL: "memory:".to_string(),
+        SMTP_SERVER: "localhost".to_string(),
+        SMTP_USERNAME: "blog@blog.test".to_string(),
+        SMTP_PASSWORD: String::new(),
+        NEW_USER_DEFAULT_ROLE: "reader".to_string(),
+        WEBSITE_URL: WEBSITE_URL.to_string(),
+        RSS_TITLE: "Test blog".to_string(),
+        RSS_DESCRIPTION: "A blog for the tests".to_string(),
+        REDIS_URL: String::new(),
+        FILE_UPLOAD_PATH: std::env::temp_dir().to_string_lossy().into_owned(),
+        FILE_UPLOAD_URL: "/uploads".to_string(),
+        ALLOWED_ORIGINS: String::new(),
+        TRASH_RETENTION_DAYS: "30".to_string(),
+        TRUSTED_PROXIES: String::new(),
+        LOGIN_MAX_IP_FAILURES: "50".to_string(),
+        LOGIN_MAX_ACCOUNT_FAILURES: "10".to_string(),
+        LOGIN_FAILURE_WINDOW_SECS: "3600".to_string(),
+        LOGIN_BACKOFF_SECS: "1".to_string(),
+        LOGIN_LOCKOUT_SECS: "900".to_string(),
+        LOGIN_NOTIFY_OWNER_AFTER: "0".to_string(),
+        PASSWORD_RESET_MAX_IP_REQUESTS: "10".to_string(),
+        PASSWORD_RESET_MAX_EMAIL_REQUESTS: "3".to_string(),
+        PASSWORD_RESET_WINDOW_SECS: "3600".to_string(),
+    }
+}
+
+/// The server with every route, over an empty database, an emailer that
+/// keeps what it sends and sessions kept in cookies.
+pub struct TestApp<S, D> {
+    pub service: S,
+    pub db: D,
+    pub emailer: Emailer,
+    _db_dir: Option<TempDir>,
+}
+
+pub async fn test_app<D: TestDb>(
+) -> TestApp<impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>, D>
+{
+    test_app_with(config(), no_backoff()).await
+}
+
+/// The default limits, but without the wait after a failed login so that
+/// tests can fail one and retry right away.
+pub fn no_backoff() -> LoginLimits {
+    LoginLimits {
+        backoff: Duration::ZERO,
+        ..LoginLimits::from_config(&config())
+    }
+}
+
+pub async fn test_app_with<D: TestDb>(
+    config: Config,
+    limits: LoginLimits,
+) -> TestApp<impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>, D>
+{
+    let (db, db_dir) = D::empty().await;
+    let emailer = Emailer::stub(&config.SMTP_USERNAME);
+    let site_url = SiteUrl::new(&config.WEBSITE_URL, &config.ALLOWED_ORIGINS);
+    let new_user_role = NewUserRole::from_config(&config);
+
+    let service = test::init_service(
+        App::new()
+            .wrap(IdentityMiddleware::default())
+            .wrap(SessionMiddleware::new(
+                CookieSessionStore::default(),
+                Key::generate(),
+            ))
+            .app_data(Data::new(db.clone()))
+            .app_data(Data::new(emailer.clone()))
+            .app_data(Data::new(config))
+            .app_data(Data::new(Highlighter::new()))
+            .app_data(Data::new(site_url))
+            .app_data(Data::new(LoginLimiter::in_memory(limits)))
+            .app_data(Data::new(new_user_role))
+            .configure(handlers::routes::<D>),
+    )
+    .await;
+
+    TestApp {
+        service,
+        db,
+        emailer,
+        _db_dir: db_dir,
+    }
+}
+
+impl<S, B, D> TestApp<S, D>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    D: DBHandler,
+{
+    pub async fn call(&self, request: TestRequest) -> ServiceResponse<B> {
+        test::call_service(&self.service, request.to_request()).await
+    }
+
+    /// Makes the request in the session of `cookie`.
+    pub async fn call_as(
+        &self,
+        cookie: &Cookie<'static>,
+        request: TestRequest,
+    ) -> ServiceResponse<B> {
+        self.call(request.cookie(cookie.clone())).await
+    }
+
+    /// Adds a confirmed user with `PASSWORD` as password.
+    pub async fn add_user(&self, username: &str, role: Role) {
+        self.db
+            .insert_user(&User {
+                id: None,
+                username: username.to_string(),
+                email: format!("{}@blog.test", username),
+                // The lowest cost keeps the tests fast, verifying doesn't care
+                password: bcrypt::hash(PASSWORD, 4).unwrap(),
+                role,
+                password_changed_at: None,
+                token_scopes: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Logs `username` in with `PASSWORD` and returns the session cookie.
+    pub async fn login(&self, username: &str) -> Cookie<'static> {
+        let response = self
+            .call(
+                TestRequest::post()
+                    .uri("/api/auth/login")
+                    .set_json(serde_json::json!({ "username": username, "password": PASSWORD })),
+            )
+            .await;
+        assert_eq!(response.status(), 200, "logging in {}", username);
+
+        session_cookie(&response)
+    }
+
+    /// Adds a user with `role` and logs them in.
+    pub async fn user(&self, username: &str, role: Role) -> Cookie<'static> {
+        self.add_user(username, role).await;
+        self.login(username).await
+    }
+
+    /// Creates a post as the user of `cookie` and returns its slug.
+    pub async fn create_post(&self, cookie: &Cookie<'static>, post: &CreatePostRequest) -> String {
+        let response = self
+            .call_as(
+                cookie,
+                TestRequest::post().uri("/api/post/create").set_json(post),
+            )
+            .await;
+        assert_eq!(response.status(), 200, "creating {}", post.title);
+
+        read_json::<PostCreatedResponse, _>(response).await.slug
+    }
+
+    /// The token at the end of the last link to `path` emailed to `to`.
+    pub async fn emailed_token(&self, to: &str, path: &str) -> Option<String> {
+        self.emailer
+            .sent_emails()
+            .await
+            .into_iter()
+            .rev()
+            .filter(|(recipients, _)| recipients.iter().any(|recipient| recipient == to))
+            .find_map(|(_, email)| {
+                let start = email.find(path)? + path.len();
+                let token = email[start..]
+                    .chars()
+                    .take_while(char::is_ascii_alphanumeric)
+                    .collect();
+                Some(token)
+            })
+    }
+}
+
+pub fn post(title: &str, public: bool) -> CreatePostRequest {
+    CreatePostRequest {
+        title: title.to_string(),
+        content: format!("The content of {}.", title),
+        summary: None,
+        public,
+        published_at: None,
+        tags: Vec::new(),
+    }
+}
+
+/// The cookie the session middleware set on `response`.
+pub fn session_cookie<B>(response: &ServiceResponse<B>) -> Cookie<'static> {
+    response
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == "id")
+        .expect("no session cookie")
+        .into_owned()
+}
+
+pub async fn read_json<T: DeserializeOwned, B: MessageBody>(response: ServiceResponse<B>) -> T {
+    let body = test::read_body(response).await;
+    serde_json::from_slice(&body).unwrap_or_else(|err| {
+        panic!("{}: {}", err, String::from_utf8_lossy(&body));
+    })
+}
+
+pub async fn read_text<B: MessageBody>(response: ServiceResponse<B>) -> String {
+    String::from_utf8(test::read_body(response).await.to_vec()).unwrap()
+}
@@ -0,0 +1,96 @@
+mod support;
+
+use actix_web::test::TestRequest;
+
+use common::{CreatePostRequest, GetPostsResponse, Post, Role, TagCount};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+fn tagged(title: &str, public: bool, tags: &[&str]) -> CreatePostRequest {
+    CreatePostRequest {
+        tags: tags.iter().map(ToString::to_string).collect(),
+        ..post(title, public)
+    }
+}
+
+async fn tags_are_normalized<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app
+        .create_post(
+            &editor,
+            &tagged("Tagged", true, &["Rust Lang", " rust-lang ", "", "Web!"]),
+        )
+        .await;
+
+    let read: Post = read_json(
+        app.call(TestRequest::get().uri(&format!("/api/post/read/{}", slug)))
+            .await,
+    )
+    .await;
+    assert_eq!(read.tags, ["rust-lang", "web"]);
+
+    // Listings by tag normalize it the same way
+    for tag in ["Rust%20Lang", "RUST-lang", "rust-lang"] {
+        let response: GetPostsResponse = read_json(
+            app.call(TestRequest::get().uri(&format!("/api/post/get-list?tag={}", tag)))
+                .await,
+        )
+        .await;
+        assert_eq!(response.posts.len(), 1, "listing ?tag={}", tag);
+    }
+}
+
+async fn tag_counts_and_listings_leave_drafts_out<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    app.create_post(&editor, &tagged("One", true, &["rust", "web"]))
+        .await;
+    app.create_post(&editor, &tagged("Two", true, &["rust"]))
+        .await;
+    app.create_post(&editor, &tagged("Draft", false, &["rust", "draft"]))
+        .await;
+
+    let counts = |counts: Vec<TagCount>| {
+        counts
+            .into_iter()
+            .map(|count| (count.tag, count.count))
+            .collect::<Vec<_>>()
+    };
+    let tags = || TestRequest::get().uri("/api/post/tags");
+    assert_eq!(
+        counts(read_json(app.call(tags()).await).await),
+        [("rust".to_string(), 2), ("web".to_string(), 1)]
+    );
+    assert_eq!(
+        counts(read_json(app.call_as(&editor, tags()).await).await),
+        [
+            ("rust".to_string(), 3),
+            ("draft".to_string(), 1),
+            ("web".to_string(), 1)
+        ]
+    );
+
+    // Posts created in the same millisecond have no set order
+    let titles = |response: GetPostsResponse| {
+        let mut titles: Vec<_> = response.posts.into_iter().map(|post| post.title).collect();
+        titles.sort();
+        titles
+    };
+    let list = |tag: &str| TestRequest::get().uri(&format!("/api/post/get-list?tag={}", tag));
+    assert_eq!(
+        titles(read_json(app.call(list("rust")).await).await),
+        ["One", "Two"]
+    );
+    assert_eq!(
+        titles(read_json(app.call(list("web")).await).await),
+        ["One"]
+    );
+    assert!(titles(read_json(app.call(list("draft")).await).await).is_empty());
+    // Tags are matched whole, not as part of other tags
+    assert!(titles(read_json(app.call(list("rus")).await).await).is_empty());
+}
+
+db_tests!(
+    tags_are_normalized,
+    tag_counts_and_listings_leave_drafts_out,
+);
@@ -0,0 +1,196 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+use backend::utils::purge_expired_trash;
+use common::{PostRevision, Role, SeriesOverview, TrashedPost};
+use support::{db_tests, post, read_json, test_app, TestDb};
+
+async fn deleted_posts_can_be_restored<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let admin = app.user("root", Role::Admin).await;
+    let slug = app.create_post(&editor, &post("Oops", true)).await;
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::delete().uri(&format!("/api/post/delete/{}", slug)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let read = || TestRequest::get().uri(&format!("/api/post/read/{}", slug));
+    assert_eq!(app.call_as(&admin, read()).await.status(), 404);
+
+    let trash = || TestRequest::get().uri("/api/trash/get-list");
+    assert_eq!(app.call_as(&editor, trash()).await.status(), 403);
+    let trashed: Vec<TrashedPost> = read_json(app.call_as(&admin, trash()).await).await;
+    assert_eq!(trashed.len(), 1);
+    assert_eq!(trashed[0].post.slug, slug);
+    assert_eq!(trashed[0].deleted_by, "alice");
+
+    // The slug stays taken while the post is in the trash
+    let other = app.create_post(&editor, &post("Oops", true)).await;
+    assert_ne!(other, slug);
+
+    let response = app
+        .call_as(
+            &admin,
+            TestRequest::post().uri(&format!("/api/trash/restore/{}", slug)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(app.call(read()).await.status(), 200);
+    let trashed: Vec<TrashedPost> = read_json(app.call_as(&admin, trash()).await).await;
+    assert!(trashed.is_empty());
+
+    // Its history came back with it
+    let revisions: Vec<PostRevision> = read_json(
+        app.call_as(
+            &editor,
+            TestRequest::get().uri(&format!("/api/post/revisions/{}", slug)),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(revisions.len(), 1);
+}
+
+async fn trashed_posts_keep_their_place_in_a_series<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let admin = app.user("root", Role::Admin).await;
+    let first = app.create_post(&editor, &post("One", true)).await;
+    let second = app.create_post(&editor, &post("Two", true)).await;
+    let third = app.create_post(&editor, &post("Three", true)).await;
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/series/create")
+                .set_json(json!({
+                    "title": "Series",
+                    "description": "",
+                    "posts": [first, second, third],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let parts = || async {
+        let series: SeriesOverview = read_json(
+            app.call(TestRequest::get().uri("/api/series/read/series"))
+                .await,
+        )
+        .await;
+        series
+            .parts
+            .into_iter()
+            .map(|part| part.slug)
+            .collect::<Vec<_>>()
+    };
+
+    app.call_as(
+        &editor,
+        TestRequest::delete().uri(&format!("/api/post/delete/{}", second)),
+    )
+    .await;
+    assert_eq!(parts().await, [first.clone(), third.clone()]);
+
+    // The series can still be edited with the trashed part in it
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/series/update")
+                .set_json(json!({
+                    "slug": "series",
+                    "title": "Series",
+                    "description": "Edited",
+                    "posts": [first, second, third],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    // But other series can't take it
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/series/create")
+                .set_json(json!({
+                    "title": "Other",
+                    "description": "",
+                    "posts": [second],
+                })),
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    app.call_as(
+        &admin,
+        TestRequest::post().uri(&format!("/api/trash/restore/{}", second)),
+    )
+    .await;
+    assert_eq!(
+        parts().await,
+        [first.clone(), second.clone(), third.clone()]
+    );
+
+    // Purging is what takes it out of the series
+    app.call_as(
+        &editor,
+        TestRequest::delete().uri(&format!("/api/post/delete/{}", second)),
+    )
+    .await;
+    let response = app
+        .call_as(
+            &admin,
+            TestRequest::delete().uri(&format!("/api/trash/purge/{}", second)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = app
+        .call_as(
+            &admin,
+            TestRequest::post().uri(&format!("/api/trash/restore/{}", second)),
+        )
+        .await;
+    assert_eq!(response.status(), 404);
+    let slugs = app
+        .db
+        .get_series("series")
+        .await
+        .unwrap()
+        .map(|series| series.posts);
+    assert_eq!(slugs, Some(vec![first, third]));
+}
+
+async fn expired_posts_are_purged<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let editor = app.user("alice", Role::Editor).await;
+    let slug = app.create_post(&editor, &post("Old", true)).await;
+    app.call_as(
+        &editor,
+        TestRequest::delete().uri(&format!("/api/post/delete/{}", slug)),
+    )
+    .await;
+
+    let purged = purge_expired_trash(&app.db, chrono::Duration::days(1))
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+    let purged = purge_expired_trash(&app.db, chrono::Duration::zero())
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    assert!(app.db.get_trashed_posts().await.unwrap().is_empty());
+}
+
+db_tests!(
+    deleted_posts_can_be_restored,
+    trashed_posts_keep_their_place_in_a_series,
+    expired_posts_are_purged,
+);
@@ -0,0 +1,181 @@
+mod support;
+
+use actix_web::{cookie::Cookie, dev::ServiceResponse, test::TestRequest};
+use chrono::Utc;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha1::Sha1;
+
+use common::{LoginChallenge, RecoveryCodes, Role, SiteSettings, TwoFactorSetup};
+use support::{db_tests, read_json, session_cookie, test_app, TestDb, PASSWORD};
+
+/// The code an authenticator app would show for `secret` right now.
+fn totp(secret: &str) -> String {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    let step = Utc::now().timestamp() / 30;
+
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+
+    format!("{:06}", binary % 1_000_000)
+}
+
+fn login(username: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/login")
+        .set_json(json!({ "username": username, "password": PASSWORD }))
+}
+
+fn setup(password: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/auth/two-factor/setup")
+        .set_json(json!({ "password": password }))
+}
+
+fn code(path: &str, code: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(path)
+        .set_json(json!({ "code": code }))
+}
+
+/// The session cookie `response` set, or `cookie` if it kept the session.
+fn next_cookie<B>(cookie: Cookie<'static>, response: &ServiceResponse<B>) -> Cookie<'static> {
+    response
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == "id")
+        .map(Cookie::into_owned)
+        .unwrap_or(cookie)
+}
+
+async fn two_factor_is_set_up_with_the_password_and_asked_at_login<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let cookie = app.user("alice", Role::Reader).await;
+
+    let response = app.call_as(&cookie, setup("wrong")).await;
+    assert_eq!(response.status(), 400);
+    let response = app.call_as(&cookie, setup(PASSWORD)).await;
+    assert_eq!(response.status(), 200);
+    let TwoFactorSetup { secret, .. } = read_json(response).await;
+
+    let response = app
+        .call_as(&cookie, code("/api/auth/two-factor/enable", "000000x"))
+        .await;
+    assert_eq!(response.status(), 400);
+    let first_code = totp(&secret);
+    let response = app
+        .call_as(&cookie, code("/api/auth/two-factor/enable", &first_code))
+        .await;
+    assert_eq!(response.status(), 200);
+    let RecoveryCodes { codes } = read_json(response).await;
+    assert_eq!(codes.len(), 10);
+
+    let response = app.call(login("alice")).await;
+    assert_eq!(response.status(), 202);
+    let pending = session_cookie(&response);
+    assert_eq!(
+        read_json::<LoginChallenge, _>(response).await,
+        LoginChallenge::TwoFactor
+    );
+
+    // The code that enabled it was used up, recovery codes work once
+    let response = app
+        .call_as(&pending, code("/api/auth/login/two-factor", &first_code))
+        .await;
+    assert_eq!(response.status(), 401);
+    let response = app
+        .call_as(&pending, code("/api/auth/login/two-factor", &codes[0]))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.call(login("alice")).await;
+    let pending = session_cookie(&response);
+    let response = app
+        .call_as(&pending, code("/api/auth/login/two-factor", &codes[0]))
+        .await;
+    assert_eq!(response.status(), 401);
+}
+
+async fn admins_can_require_two_factor<D: TestDb>() {
+    let app = test_app::<D>().await;
+    let admin = app.user("alice", Role::Admin).await;
+    let editor = app.user("bob", Role::Editor).await;
+    app.add_user("carol", Role::Reader).await;
+
+    let response = app
+        .call_as(&editor, TestRequest::get().uri("/api/settings"))
+        .await;
+    assert_eq!(response.status(), 403);
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/settings/update")
+                .set_json(SiteSettings {
+                    require_two_factor: true,
+                }),
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .call_as(
+            &admin,
+            TestRequest::post()
+                .uri("/api/settings/update")
+                .set_json(SiteSettings {
+                    require_two_factor: true,
+                }),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // Sessions without it are over, logging in goes through setting it up
+    let response = app
+        .call_as(&editor, TestRequest::get().uri("/api/settings"))
+        .await;
+    assert_eq!(response.status(), 401);
+    let response = app.call(login("carol")).await;
+    assert_eq!(response.status(), 200);
+    let response = app.call(login("bob")).await;
+    assert_eq!(response.status(), 202);
+    let pending = session_cookie(&response);
+    assert_eq!(
+        read_json::<LoginChallenge, _>(response).await,
+        LoginChallenge::TwoFactorSetup
+    );
+
+    let response = app.call_as(&pending, setup("wrong")).await;
+    assert_eq!(response.status(), 400);
+    let response = app.call_as(&pending, setup(PASSWORD)).await;
+    assert_eq!(response.status(), 200);
+    let pending = next_cookie(pending, &response);
+    let TwoFactorSetup { secret, .. } = read_json(response).await;
+    let response = app
+        .call_as(
+            &pending,
+            code("/api/auth/two-factor/enable", &totp(&secret)),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    let editor = next_cookie(pending, &response);
+
+    let response = app
+        .call_as(
+            &editor,
+            TestRequest::post()
+                .uri("/api/auth/two-factor/disable")
+                .set_json(json!({ "password": PASSWORD })),
+        )
+        .await;
+    assert_eq!(response.status(), 403);
+}
+
+db_tests!(
+    two_factor_is_set_up_with_the_password_and_asked_at_login,
+    admins_can_require_two_factor,
+);
@@ -1,15 +1,147 @@
+use std::fmt;
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use pulldown_cmark::HeadingLevel;
 use serde::{Deserialize, Serialize};
 
 pub mod utils;
 
+/// Something a user may be allowed to do, granted through their `Role`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum Capability {
+    CreatePost,
+    EditOwnPost,
+    EditAnyPost,
+    /// See drafts and scheduled posts.
+    ViewDrafts,
+    ManageSeries,
+    ModerateComments,
+    ManageTrash,
+    ManageBackups,
+    ManageUsers,
+}
+
+impl Capability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::CreatePost => "CreatePost",
+            Capability::EditOwnPost => "EditOwnPost",
+            Capability::EditAnyPost => "EditAnyPost",
+            Capability::ViewDrafts => "ViewDrafts",
+            Capability::ManageSeries => "ManageSeries",
+            Capability::ModerateComments => "ModerateComments",
+            Capability::ManageTrash => "ManageTrash",
+            Capability::ManageBackups => "ManageBackups",
+            Capability::ManageUsers => "ManageUsers",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(capability: &str) -> Result<Self, Self::Err> {
+        Role::Admin
+            .capabilities()
+            .iter()
+            .find(|known| known.as_str().eq_ignore_ascii_case(capability))
+            .copied()
+            .ok_or_else(|| format!("unknown capability {}", capability))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Role {
+    Admin,
+    Editor,
+    /// Any other role, which only lets users comment.
+    #[default]
+    #[serde(other)]
+    Reader,
+}
+
+impl Role {
+    pub fn capabilities(self) -> &'static [Capability] {
+        match self {
+            Role::Admin => &[
+                Capability::CreatePost,
+                Capability::EditOwnPost,
+                Capability::EditAnyPost,
+                Capability::ViewDrafts,
+                Capability::ManageSeries,
+                Capability::ModerateComments,
+                Capability::ManageTrash,
+                Capability::ManageBackups,
+                Capability::ManageUsers,
+            ],
+            Role::Editor => &[
+                Capability::CreatePost,
+                Capability::EditOwnPost,
+                Capability::ViewDrafts,
+                Capability::ManageSeries,
+                Capability::ModerateComments,
+            ],
+            Role::Reader => &[],
+        }
+    }
+
+    pub fn can(self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    /// Whether the role may change a post, depending on whether its holder
+    /// wrote it.
+    pub fn can_edit_post(self, is_author: bool) -> bool {
+        self.can(Capability::EditAnyPost) || (is_author && self.can(Capability::EditOwnPost))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "Admin",
+            Role::Editor => "Editor",
+            Role::Reader => "Reader",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Unlike deserializing, parsing rejects unknown roles.
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role.to_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "editor" => Ok(Role::Editor),
+            "reader" => Ok(Role::Reader),
+            _ => Err(format!("unknown role {}", role)),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct CreatePostRequest {
     pub title: String,
     pub content: String,
     pub summary: Option<String>,
     pub public: bool,
+    /// A time in the future schedules the post; `None` publishes it now.
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -24,6 +156,21 @@ pub struct UpdatePostRequest {
     pub title: String,
     pub summary: Option<String>,
     pub public: bool,
+    /// `None` keeps the current publication time.
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Moves the post to another slug, the current one keeps redirecting to it.
+    #[serde(default)]
+    pub new_slug: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TransferPostRequest {
+    pub slug: String,
+    /// Username of the new author, who must be able to write posts.
+    pub author: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
@@ -35,20 +182,261 @@ pub struct Post {
     pub author: String,
     pub published_at: DateTime<Utc>,
     pub public: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Post {
+    /// Public posts stay hidden from readers until `published_at` has passed.
+    pub fn is_scheduled(&self) -> bool {
+        self.public && self.published_at > Utc::now()
+    }
+
+    /// The text shown in listings: the summary written by the author, or the
+    /// start of the content. Stored along with the post when it is saved.
+    pub fn preview(&self) -> String {
+        match self.summary {
+            Some(ref summary) => summary.clone(),
+            None => utils::get_summary(&self.content, utils::MAX_PREVIEW_LENGTH),
+        }
+    }
+}
+
+/// What listings need to know about a post, without its content.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+pub struct PostSummary {
+    pub slug: String,
+    pub title: String,
+    /// See `Post::preview`.
+    pub summary: String,
+    pub author: String,
+    pub published_at: DateTime<Utc>,
+    pub public: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl PostSummary {
+    pub fn is_scheduled(&self) -> bool {
+        self.public && self.published_at > Utc::now()
+    }
+}
+
+impl From<&Post> for PostSummary {
+    fn from(value: &Post) -> Self {
+        Self {
+            slug: value.slug.clone(),
+            title: value.title.clone(),
+            summary: value.preview(),
+            author: value.author.clone(),
+            published_at: value.published_at,
+            public: value.public,
+            tags: value.tags.clone(),
+        }
+    }
+}
+
+/// An ordered group of posts, e.g. a tutorial split in several parts.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+pub struct Series {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    /// Slugs of the posts in reading order.
+    pub posts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SeriesPart {
+    pub slug: String,
+    pub title: String,
+}
+
+/// A series with only the parts the reader is allowed to see.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SeriesOverview {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub parts: Vec<SeriesPart>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CreateSeriesRequest {
+    pub title: String,
+    pub description: String,
+    pub posts: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UpdateSeriesRequest {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub posts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone, Copy)]
+pub enum CommentStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Comment {
+    pub id: String,
+    pub post_slug: String,
+    /// The comment this one replies to, `None` for top level comments.
+    pub parent_id: Option<String>,
+    pub author: String,
+    /// Markdown, rendered with `utils::render_comment_markdown`.
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub status: CommentStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CreateCommentRequest {
+    pub post_slug: String,
+    pub parent_id: Option<String>,
+    pub content: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UpdateCommentRequest {
+    pub id: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ModerateCommentRequest {
+    pub id: String,
+    pub status: CommentStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CommentQueueQuery {
+    pub status: Option<CommentStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TrashedPost {
+    pub post: Post,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PostRevision {
+    pub slug: String,
+    pub number: u64,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    pub title: String,
+    pub content: String,
+    pub summary: Option<String>,
+    pub public: bool,
+    /// `None` on revisions saved before the publication date was kept.
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    /// `None` on revisions saved before the tags were kept.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum DiffTag {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RevisionDiff {
+    pub from: u64,
+    pub to: u64,
+    pub title: Vec<DiffLine>,
+    pub summary: Vec<DiffLine>,
+    pub content: Vec<DiffLine>,
 }
 
 #[derive(Deserialize, Serialize)]
+pub struct RevisionDiffQuery {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct PostsQueryParams {
     pub page: Option<u64>,
     pub per_page: Option<u64>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    /// Only list posts with this tag.
+    pub tag: Option<String>,
+    /// Cursor from `GetPostsResponse::next`, lists the posts older than it.
+    pub after: Option<String>,
+    /// Cursor from `GetPostsResponse::prev`, lists the posts newer than it.
+    pub before: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct SearchQueryParams {
+    #[serde(default)]
+    pub q: String,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// Piece of a search snippet, `highlight` is set on the words that matched.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SnippetFragment {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SearchHit {
+    pub post: PostSummary,
+    pub snippet: Vec<SnippetFragment>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SearchResponse {
+    pub hits: Vec<SearchHit>,
+    pub pages: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u64,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct GetPostsResponse {
-    pub posts: Vec<Post>,
+    pub posts: Vec<PostSummary>,
     pub pages: Result<u64, ()>,
+    /// Opaque cursor for the next (older) page, `None` on the last one.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Opaque cursor for the previous (newer) page, `None` on the first one.
+    #[serde(default)]
+    pub prev: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -64,8 +452,134 @@ pub struct Header {
     pub id: String,
 }
 
+#[derive(Deserialize, Serialize, Default)]
+pub struct ImportQuery {
+    /// Replace posts and files that already exist instead of skipping them.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// What happened to one file of an imported archive.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum ImportOutcome {
+    Created,
+    Overwritten,
+    /// Left alone, with the reason.
+    Skipped(String),
+    /// Couldn't be imported, with the reason.
+    Failed(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ImportedFile {
+    /// Path of the file inside the archive.
+    pub path: String,
+    pub outcome: ImportOutcome,
+    /// Parts of the file that were imported as they were or left out.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ImportReport {
+    pub files: Vec<ImportedFile>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct UploadResponse {
     pub parent_path: String,
     pub filename: String,
 }
+
+/// What a login still needs once the password was accepted, sent with a
+/// `202 Accepted`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum LoginChallenge {
+    /// A code from the authenticator app, or a recovery code.
+    TwoFactor,
+    /// The role of the user requires two-factor authentication, which they
+    /// have to set up before being logged in.
+    TwoFactorSetup,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Whether the role of the user requires it, so it can't be disabled.
+    pub required: bool,
+    pub recovery_codes_left: usize,
+}
+
+/// Site wide settings that admins change at runtime.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SiteSettings {
+    /// Whether Admins and Editors have to log in with a second factor.
+    pub require_two_factor: bool,
+}
+
+/// A new TOTP secret, unused until a first code from it is entered.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TwoFactorSetup {
+    /// Base32, for typing into the authenticator app.
+    pub secret: String,
+    /// `otpauth://` URI, for a QR code.
+    pub provisioning_uri: String,
+}
+
+/// Recovery codes, each usable once instead of a TOTP code. They are only
+/// ever shown when generated.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TwoFactorCodeRequest {
+    /// A TOTP code, or a recovery code where one is accepted.
+    pub code: String,
+}
+
+/// Asks for the password again before changing security settings.
+#[derive(Deserialize, Serialize)]
+pub struct PasswordConfirmation {
+    pub password: String,
+}
+
+/// A personal API token as its owner sees it, without the token itself.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    /// What requests made with the token may do, as far as the role of its
+    /// owner allows.
+    pub scopes: Vec<Capability>,
+    pub created_at: DateTime<Utc>,
+    /// `None` when it never expires.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ApiTokenList {
+    pub tokens: Vec<ApiToken>,
+    /// The scopes new tokens may be given, the capabilities of the role of
+    /// the user.
+    pub available_scopes: Vec<Capability>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<Capability>,
+    /// `None` for a token that never expires.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A new API token. The token is only ever shown in this response.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ApiTokenCreated {
+    pub token: String,
+    pub api_token: ApiToken,
+}
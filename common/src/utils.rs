@@ -1,6 +1,6 @@
 use std::cmp::min;
 
-use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{html::push_html, Event, Options, Parser, Tag, TagEnd};
 use regex::Regex;
 
 pub fn is_valid_email(email: &str) -> bool {
@@ -36,6 +36,23 @@ pub fn title_to_slug(title: &str) -> String {
         .join("-")
 }
 
+/// Turns user input into tags usable in `/tag/{tag}` URLs, dropping empty ones
+/// and duplicates while keeping the original order.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for tag in tags.iter().map(|tag| title_to_slug(tag)) {
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    normalized
+}
+
+/// Length of the generated previews shown in post listings.
+pub const MAX_PREVIEW_LENGTH: usize = 150;
+
 pub fn get_summary(html_text: &str, max_len: usize) -> String {
     let mut summary = String::new();
 
@@ -48,6 +65,7 @@ pub fn get_summary(html_text: &str, max_len: usize) -> String {
             | Options::ENABLE_GFM,
     );
     let mut in_p = false;
+    let mut truncated = false;
 
     for event in parser {
         match event {
@@ -58,8 +76,14 @@ pub fn get_summary(html_text: &str, max_len: usize) -> String {
                 in_p = false;
             }
             Event::Text(text) if in_p => {
-                summary += &text[..min(max_len - summary.len(), text.len())];
-                if summary.len() >= max_len {
+                // Cut on a character boundary so multibyte text isn't split
+                let mut end = min(max_len - summary.len(), text.len());
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                summary += &text[..end];
+                if end < text.len() || summary.len() >= max_len {
+                    truncated = true;
                     break;
                 }
             }
@@ -67,9 +91,47 @@ pub fn get_summary(html_text: &str, max_len: usize) -> String {
         }
     }
 
-    if summary.len() >= max_len {
+    if truncated {
         summary += "...";
     }
 
     summary
 }
+
+/// Longest comment accepted, in bytes.
+pub const MAX_COMMENT_LENGTH: usize = 5000;
+
+pub fn is_valid_comment(content: &str) -> bool {
+    !content.trim().is_empty() && content.len() <= MAX_COMMENT_LENGTH
+}
+
+/// Renders the markdown subset allowed in comments: raw HTML is shown as
+/// text, images are reduced to their alt text, headings become paragraphs
+/// and only http(s) and mailto links are kept.
+pub fn render_comment_markdown(content: &str) -> String {
+    let mut dropped_links: Vec<bool> = vec![];
+
+    let events =
+        Parser::new_ext(content, Options::ENABLE_STRIKETHROUGH).filter_map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+            Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image) => None,
+            Event::Start(Tag::Heading { .. }) => Some(Event::Start(Tag::Paragraph)),
+            Event::End(TagEnd::Heading(_)) => Some(Event::End(TagEnd::Paragraph)),
+            Event::Start(Tag::Link { ref dest_url, .. }) => {
+                let allowed = ["http://", "https://", "mailto:"]
+                    .iter()
+                    .any(|scheme| dest_url.to_lowercase().starts_with(scheme));
+                dropped_links.push(!allowed);
+                allowed.then_some(event)
+            }
+            Event::End(TagEnd::Link) => match dropped_links.pop() {
+                Some(true) => None,
+                _ => Some(event),
+            },
+            _ => Some(event),
+        });
+
+    let mut html_output = String::new();
+    push_html(&mut html_output, events);
+    html_output
+}
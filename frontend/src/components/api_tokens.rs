@@ -0,0 +1,298 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement, SubmitEvent};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    components::{NotificationLevel, ServiceNotification},
+    routes::AppRoute,
+    services::auth::{AuthError, AuthService},
+    utils::set_title,
+};
+use common::{ApiTokenList, Capability, CreateApiTokenRequest};
+
+/// Lifetimes new tokens may be given, in days, `0` for one that never expires.
+const EXPIRY_DAYS: [i64; 4] = [30, 90, 365, 0];
+
+fn error_text(err: AuthError) -> String {
+    match err {
+        AuthError::InvalidApiToken(field) => match field.as_str() {
+            "name" => "give it a name of at most 100 characters",
+            "scopes" => "your role doesn't grant some of the scopes",
+            "expires_at" => "the expiry has to be in the future",
+            _ => "please try again later",
+        },
+        AuthError::Unauthorized => "you are no longer logged in",
+        AuthError::NetworkError => "can't reach server",
+        _ => "please try again later",
+    }
+    .to_string()
+}
+
+fn format_date(datetime: DateTime<Utc>) -> String {
+    datetime
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// Mints, lists and revokes the personal API tokens of the user, for scripts
+/// that send them in an `Authorization: Bearer` header.
+#[function_component(ApiTokenSettings)]
+pub fn api_token_settings() -> Html {
+    let token_list = use_state(|| None::<ApiTokenList>);
+    let logged_out = use_state(|| false);
+    // Only shown right after it is created
+    let new_token = use_state(|| None::<String>);
+    let name = use_state(String::new);
+    let scopes = use_state(Vec::<Capability>::new);
+    let expiry_days = use_state(|| EXPIRY_DAYS[0]);
+    let service_notification_text = use_state(String::new);
+    let service_notification_level = use_state(NotificationLevel::default);
+    let disable_submit = use_state(|| false);
+    // Bumped after every change to fetch the tokens again
+    let reload = use_state(|| 0u32);
+
+    {
+        let token_list = token_list.clone();
+        let logged_out = logged_out.clone();
+
+        use_effect_with(*reload, move |_| {
+            set_title("API tokens");
+            spawn_local(async move {
+                match AuthService::get_api_tokens().await {
+                    Ok(fetched) => token_list.set(Some(fetched)),
+                    Err(AuthError::Unauthorized) => logged_out.set(true),
+                    Err(_) => log::error!("Error fetching the API tokens"),
+                }
+            });
+
+            || ()
+        });
+    }
+
+    let notify = {
+        let service_notification_text = service_notification_text.clone();
+        let service_notification_level = service_notification_level.clone();
+        let disable_submit = disable_submit.clone();
+        Callback::from(move |(text, level): (String, NotificationLevel)| {
+            disable_submit.set(false);
+            service_notification_text.set(text);
+            service_notification_level.set(level);
+        })
+    };
+
+    let on_create = {
+        let name = name.clone();
+        let scopes = scopes.clone();
+        let expiry_days = expiry_days.clone();
+        let new_token = new_token.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            disable_submit.set(true);
+
+            let request = CreateApiTokenRequest {
+                name: (*name).clone(),
+                scopes: (*scopes).clone(),
+                expires_at: (*expiry_days > 0).then(|| Utc::now() + Duration::days(*expiry_days)),
+            };
+            let name = name.clone();
+            let scopes = scopes.clone();
+            let new_token = new_token.clone();
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                match AuthService::create_api_token(&request).await {
+                    Ok(created) => {
+                        name.set(String::new());
+                        scopes.set(Vec::new());
+                        new_token.set(Some(created.token));
+                        notify.emit((
+                            format!("Created the token {}", created.api_token.name),
+                            NotificationLevel::Success,
+                        ));
+                        reload.set(*reload + 1);
+                    }
+                    Err(err) => notify.emit((
+                        format!("Error creating the token, {}", error_text(err)),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_revoke = {
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |id: String| {
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                match AuthService::revoke_api_token(&id).await {
+                    Ok(()) => {
+                        notify.emit((
+                            "The token no longer works".to_string(),
+                            NotificationLevel::Success,
+                        ));
+                        reload.set(*reload + 1);
+                    }
+                    Err(err) => notify.emit((
+                        format!("Error revoking the token, {}", error_text(err)),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_name_input = {
+        let name = name.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                name.set(input.value());
+            }
+        })
+    };
+
+    let on_expiry_change = {
+        let expiry_days = expiry_days.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+                if let Ok(days) = select.value().parse() {
+                    expiry_days.set(days);
+                }
+            }
+        })
+    };
+
+    let on_token_saved = {
+        let new_token = new_token.clone();
+        let service_notification_text = service_notification_text.clone();
+        Callback::from(move |_| {
+            new_token.set(None);
+            service_notification_text.set(String::new());
+        })
+    };
+
+    let content = if *logged_out {
+        html! {
+            <>
+                <p>{"Log in to manage your API tokens."}</p>
+                <Link<AppRoute> to={AppRoute::Login} classes="bottom">{"Go to login"}</Link<AppRoute>>
+            </>
+        }
+    } else if let Some(token) = (*new_token).clone() {
+        html! {
+            <>
+                <p>{"Copy the token now, it won't be shown again. Scripts send it in an Authorization: Bearer header."}</p>
+                <pre class="secret">{ token }</pre>
+                <button onclick={on_token_saved}>{"I copied it"}</button>
+            </>
+        }
+    } else if let Some(token_list) = (*token_list).clone() {
+        let scope_checkbox = |scope: Capability| {
+            let checked = scopes.contains(&scope);
+            let scopes = scopes.clone();
+            let onchange = Callback::from(move |e: Event| {
+                if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                    let mut new_scopes = (*scopes).clone();
+                    new_scopes.retain(|other| *other != scope);
+                    if input.checked() {
+                        new_scopes.push(scope);
+                    }
+                    scopes.set(new_scopes);
+                }
+            });
+
+            html! {
+                <label>
+                    <input type="checkbox" {checked} {onchange} />
+                    { scope.as_str() }
+                </label>
+            }
+        };
+
+        html! {
+            <>
+                if token_list.tokens.is_empty() {
+                    <p>{"You have no API tokens."}</p>
+                }
+                <ul class="api-tokens">
+                    { for token_list.tokens.iter().map(|token| {
+                        let id = token.id.clone();
+                        let on_revoke = on_revoke.clone();
+                        let scopes = token
+                            .scopes
+                            .iter()
+                            .map(|scope| scope.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        let expired = token.expires_at.is_some_and(|expires_at| expires_at <= Utc::now());
+
+                        html! {
+                            <li>
+                                <strong>{ &token.name }</strong>
+                                <p class="details">
+                                    { if scopes.is_empty() { "No scopes".to_string() } else { scopes } }
+                                </p>
+                                <p class="details">
+                                    { format!("Created {}", format_date(token.created_at)) }
+                                    { match token.expires_at {
+                                        Some(expires_at) if expired => format!(", expired {}", format_date(expires_at)),
+                                        Some(expires_at) => format!(", expires {}", format_date(expires_at)),
+                                        None => ", never expires".to_string(),
+                                    } }
+                                    { match token.last_used_at {
+                                        Some(last_used_at) => format!(", last used {}", format_date(last_used_at)),
+                                        None => ", never used".to_string(),
+                                    } }
+                                </p>
+                                <button onclick={move |_| on_revoke.emit(id.clone())}>{"Revoke"}</button>
+                            </li>
+                        }
+                    }) }
+                </ul>
+                <form onsubmit={on_create}>
+                    <div class="input-wrapper">
+                        <i class="icon-edit icon"></i>
+                        <input
+                            type="text"
+                            placeholder="Token name"
+                            value={(*name).clone()}
+                            oninput={on_name_input}
+                        />
+                    </div>
+                    <div class="scopes">
+                        { for token_list.available_scopes.iter().copied().map(scope_checkbox) }
+                    </div>
+                    <select onchange={on_expiry_change}>
+                        { for EXPIRY_DAYS.iter().map(|days| html! {
+                            <option value={days.to_string()} selected={*days == *expiry_days}>
+                                { if *days == 0 { "Never expires".to_string() } else { format!("Expires in {} days", days) } }
+                            </option>
+                        }) }
+                    </select>
+                    <button disabled={name.trim().is_empty() || *disable_submit} type="submit">{"Create token"}</button>
+                </form>
+            </>
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div class="login">
+            <h2>{"API tokens"}</h2>
+            if !(*service_notification_text).is_empty() {
+                <ServiceNotification message={(*service_notification_text).clone()} level={*service_notification_level} />
+            }
+
+            { content }
+        </div>
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 use yew::prelude::*;
 
 use crate::components::PostCard;
-use common::Post;
+use common::PostSummary;
 
 #[derive(PartialEq, Properties)]
 pub struct BlogProps {
@@ -11,28 +11,68 @@ pub struct BlogProps {
     #[prop_or(0)]
     pub pages: u64,
     #[prop_or_default]
-    pub posts: Vec<Arc<Post>>,
+    pub posts: Vec<Arc<PostSummary>>,
+    /// Set when listing the posts of a single tag.
+    #[prop_or_default]
+    pub tag: Option<AttrValue>,
+    /// Cursor of the next (older) page.
+    #[prop_or_default]
+    pub next: Option<AttrValue>,
+    /// Cursor of the previous (newer) page.
+    #[prop_or_default]
+    pub prev: Option<AttrValue>,
+    /// Link the other pages as `{base}/page/{n}/` directories, for the
+    /// static export.
+    #[prop_or_default]
+    pub static_pages: bool,
 }
 
 #[function_component(Blog)]
-pub fn blog(BlogProps { page, pages, posts }: &BlogProps) -> Html {
-    let next_page_url = if page < pages {
-        Some(format!("/blog?page={}", page + 1))
-    } else {
-        None
+pub fn blog(
+    BlogProps {
+        page,
+        pages,
+        posts,
+        tag,
+        next,
+        prev,
+        static_pages,
+    }: &BlogProps,
+) -> Html {
+    let base_url = match tag {
+        Some(tag) => format!("/tag/{}", tag),
+        None => String::from("/blog"),
+    };
+
+    let page_url = |page: u64| match (*static_pages, page) {
+        (true, 1) => format!("{}/", base_url),
+        (true, page) => format!("{}/page/{}/", base_url, page),
+        (false, page) => format!("{}?page={}", base_url, page),
+    };
+
+    // Offset pages only show up when following old `?page=` links
+    let next_page_url = match next {
+        Some(cursor) => Some(format!("{}?after={}", base_url, cursor)),
+        None if *page > 0 && page < pages => Some(page_url(page + 1)),
+        None => None,
     };
 
-    let prev_page_url = if *page > 1 {
-        Some(format!("/blog?page={}", page - 1))
-    } else {
-        None
+    let prev_page_url = match prev {
+        Some(cursor) => Some(format!("{}?before={}", base_url, cursor)),
+        None if *page > 1 => Some(page_url(page - 1)),
+        None => None,
     };
 
     html! {
         <>
             <section class="blog-header">
-                <img class="avatar-img" src="static/avatar.webp" alt="d^_^b" />
-                <p>{ "...about physics, mathematics, programmig, hacking, and anything I find ineresting enough to share." }</p>
+                if let Some(tag) = tag {
+                    <h1 class="tag-title">{ format!("#{}", tag) }</h1>
+                    <p><a href="/blog">{ "All posts" }</a></p>
+                } else {
+                    <img class="avatar-img" src="/static/avatar.webp" alt="d^_^b" />
+                    <p>{ "...about physics, mathematics, programmig, hacking, and anything I find ineresting enough to share." }</p>
+                }
             </section>
 
 
@@ -52,7 +92,11 @@ pub fn blog(BlogProps { page, pages, posts }: &BlogProps) -> Html {
                             <i class="icon-left icon"></i> { "Previous page" }
                         </span>
                     }
-                    <div>{ page } { " / " }  {pages} </div>
+                    if *page > 0 {
+                        <div>{ page } { " / " }  {pages} </div>
+                    } else {
+                        <div>{ format!("{} pages", pages) }</div>
+                    }
                     if let Some(next_url) = next_page_url {
                         <a href={ next_url }>
                             { "Next page" } <i class="icon-right icon"></i>
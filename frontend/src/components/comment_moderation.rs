@@ -0,0 +1,136 @@
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::{
+    pages::Layout,
+    services::api::{ApiError, ApiService},
+    utils::set_title,
+};
+use common::{utils::render_comment_markdown, Comment, CommentStatus};
+
+const STATUSES: [CommentStatus; 3] = [
+    CommentStatus::Pending,
+    CommentStatus::Approved,
+    CommentStatus::Rejected,
+];
+
+#[function_component(CommentModeration)]
+pub fn comment_moderation() -> Html {
+    let status = use_state(|| CommentStatus::Pending);
+    let comments = use_state(Vec::<Comment>::new);
+    // Bumped after every action to fetch the queue again
+    let reload = use_state(|| 0u32);
+
+    {
+        let comments = comments.clone();
+
+        use_effect_with((*status, *reload), move |(status, _)| {
+            let status = *status;
+            set_title("Comment moderation");
+            spawn_local(async move {
+                match ApiService::get_comment_queue(status).await {
+                    Ok(fetched) => comments.set(fetched),
+                    Err(err) => log::error!("Error fetching comments: {:?}", err),
+                }
+            });
+
+            || ()
+        });
+    }
+
+    let api_error_cb = Callback::from(|err: ApiError| log::error!("{:?}", err));
+
+    html! {
+        <Layout>
+            <div class="comment-moderation">
+                <h1>{ "Comments" }</h1>
+                <div class="moderation-tabs">
+                    { for STATUSES.iter().map(|tab| {
+                        let tab = *tab;
+                        let onclick = {
+                            let status = status.clone();
+                            Callback::from(move |_| status.set(tab))
+                        };
+
+                        html! {
+                            <button class={classes!((tab == *status).then_some("active"))} {onclick}>
+                                { format!("{:?}", tab) }
+                            </button>
+                        }
+                    }) }
+                </div>
+
+                if comments.is_empty() {
+                    <p class="details">{ "Nothing here." }</p>
+                }
+
+                <ul class="comment-thread">
+                    { for comments.iter().map(|comment| {
+                        let id = comment.id.clone();
+                        let moderate = |new_status: CommentStatus| {
+                            let id = id.clone();
+                            let reload = reload.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            Callback::from(move |_| {
+                                let id = id.clone();
+                                let reload = reload.clone();
+                                let api_error_cb = api_error_cb.clone();
+                                spawn_local(async move {
+                                    match ApiService::moderate_comment(&id, new_status).await {
+                                        Ok(()) => reload.set(*reload + 1),
+                                        Err(err) => api_error_cb.emit(err),
+                                    }
+                                });
+                            })
+                        };
+
+                        let on_delete = {
+                            let id = id.clone();
+                            let reload = reload.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            Callback::from(move |_| {
+                                let id = id.clone();
+                                let reload = reload.clone();
+                                let api_error_cb = api_error_cb.clone();
+                                spawn_local(async move {
+                                    match ApiService::delete_comment(&id).await {
+                                        Ok(()) => reload.set(*reload + 1),
+                                        Err(err) => api_error_cb.emit(err),
+                                    }
+                                });
+                            })
+                        };
+
+                        html! {
+                            <li class="comment">
+                                <div class="details">
+                                    <b>{ &comment.author }</b>
+                                    { " on " }
+                                    <a href={ format!("/post/{}#comment-{}", comment.post_slug, comment.id) }>
+                                        { &comment.post_slug }
+                                    </a>
+                                    { " · " }
+                                    <time datetime={comment.created_at.to_rfc2822()}>
+                                        { comment.created_at.format("%d %b %Y %H:%M").to_string() }
+                                    </time>
+                                </div>
+                                <div class="comment-content">
+                                    { Html::from_html_unchecked(render_comment_markdown(&comment.content).into()) }
+                                </div>
+                                <div class="comment-actions">
+                                    if comment.status != CommentStatus::Approved {
+                                        <button onclick={moderate(CommentStatus::Approved)}>{ "Approve" }</button>
+                                    }
+                                    if comment.status != CommentStatus::Rejected {
+                                        <button onclick={moderate(CommentStatus::Rejected)}>{ "Reject" }</button>
+                                    }
+                                    <button onclick={on_delete}>{ "Delete" }</button>
+                                </div>
+                            </li>
+                        }
+                    }) }
+                </ul>
+            </div>
+        </Layout>
+    }
+}
@@ -0,0 +1,139 @@
+use yew::prelude::*;
+
+use crate::utils::User;
+use common::{
+    utils::{render_comment_markdown, MAX_COMMENT_LENGTH},
+    Capability, Comment, CommentStatus,
+};
+
+#[derive(PartialEq, Properties)]
+pub struct CommentsProps {
+    pub post_slug: AttrValue,
+    /// Every comment the user may see, in any order.
+    pub comments: Vec<Comment>,
+    #[prop_or_default]
+    pub user: Option<User>,
+}
+
+fn comment_form(post_slug: &str, parent_id: Option<&str>, text: &str) -> Html {
+    html! {
+        <form class="comment-form" method="post" action="/comment">
+            <input type="hidden" name="post_slug" value={post_slug.to_string()} />
+            if let Some(parent_id) = parent_id {
+                <input type="hidden" name="parent_id" value={parent_id.to_string()} />
+            }
+            <textarea name="content" required=true maxlength={MAX_COMMENT_LENGTH.to_string()} placeholder="Markdown is supported"></textarea>
+            <button type="submit">{ text }</button>
+        </form>
+    }
+}
+
+fn status_badge(status: CommentStatus) -> Html {
+    match status {
+        CommentStatus::Pending => {
+            html! { <span class="comment-status pending">{ "Awaiting moderation" }</span> }
+        }
+        CommentStatus::Rejected => {
+            html! { <span class="comment-status rejected">{ "Rejected" }</span> }
+        }
+        CommentStatus::Approved => html! {},
+    }
+}
+
+fn comment_thread(
+    comments: &[Comment],
+    parent_id: Option<&str>,
+    post_slug: &str,
+    user: &Option<User>,
+) -> Html {
+    let replies: Vec<&Comment> = comments
+        .iter()
+        .filter(|comment| comment.parent_id.as_deref() == parent_id)
+        .collect();
+
+    if replies.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <ul class="comment-thread">
+            { for replies.into_iter().map(|comment| {
+                let is_author = user.as_ref().is_some_and(|user| user.username == comment.author);
+                let is_moderator = user
+                    .as_ref()
+                    .is_some_and(|user| user.can(Capability::ModerateComments));
+
+                html! {
+                    <li id={ format!("comment-{}", comment.id) } class="comment">
+                        <div class="details">
+                            <b>{ &comment.author }</b>
+                            { " · " }
+                            <time datetime={comment.created_at.to_rfc2822()}>
+                                { comment.created_at.format("%d %b %Y %H:%M").to_string() }
+                            </time>
+                            if comment.edited_at.is_some() {
+                                { " · edited" }
+                            }
+                            { status_badge(comment.status) }
+                        </div>
+                        <div class="comment-content">
+                            { Html::from_html_unchecked(render_comment_markdown(&comment.content).into()) }
+                        </div>
+
+                        if user.is_some() {
+                            <div class="comment-actions">
+                                <details>
+                                    <summary>{ "Reply" }</summary>
+                                    { comment_form(post_slug, Some(&comment.id), "Reply") }
+                                </details>
+                                if is_author {
+                                    <details>
+                                        <summary>{ "Edit" }</summary>
+                                        <form class="comment-form" method="post" action={ format!("/comment/{}/edit", comment.id) }>
+                                            <textarea name="content" required=true maxlength={MAX_COMMENT_LENGTH.to_string()} value={comment.content.clone()}></textarea>
+                                            <button type="submit">{ "Save" }</button>
+                                        </form>
+                                    </details>
+                                }
+                                if is_author || is_moderator {
+                                    <form method="post" action={ format!("/comment/{}/delete", comment.id) }>
+                                        <button type="submit">{ "Delete" }</button>
+                                    </form>
+                                }
+                            </div>
+                        }
+
+                        { comment_thread(comments, Some(&comment.id), post_slug, user) }
+                    </li>
+                }
+            }) }
+        </ul>
+    }
+}
+
+#[function_component(Comments)]
+pub fn comments(
+    CommentsProps {
+        post_slug,
+        comments,
+        user,
+    }: &CommentsProps,
+) -> Html {
+    let mut comments = comments.clone();
+    comments.sort_by_key(|comment| comment.created_at);
+
+    html! {
+        <section id="comments" class="comments">
+            <h2>{ format!("Comments ({})", comments.len()) }</h2>
+            { comment_thread(&comments, None, post_slug, user) }
+
+            if user.is_some() {
+                { comment_form(post_slug, None, "Comment") }
+            } else {
+                <p class="details">
+                    <a href="/login">{ "Log in" }</a>{ " to leave a comment." }
+                </p>
+            }
+        </section>
+    }
+}
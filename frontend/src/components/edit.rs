@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use pulldown_cmark::{html::push_html, Options, Parser};
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{HtmlInputElement, HtmlTextAreaElement};
@@ -5,10 +6,30 @@ use yew::prelude::*;
 use yew_router::history::History;
 
 use crate::{
+    components::RevisionHistory,
     pages::Layout,
     services::api::{ApiError, ApiService},
     utils::set_title,
 };
+use common::UpdatePostRequest;
+
+/// Format used by `<input type="datetime-local">`.
+const DATETIME_INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+fn to_datetime_input(datetime: DateTime<Utc>) -> String {
+    datetime
+        .with_timezone(&Local)
+        .format(DATETIME_INPUT_FORMAT)
+        .to_string()
+}
+
+fn from_datetime_input(value: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(value, DATETIME_INPUT_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
 
 #[derive(Properties, PartialEq)]
 struct Props {
@@ -22,7 +43,11 @@ fn edit_page(props: &Props) -> Html {
     let content = use_state(String::new);
     let summary = use_state(String::new);
     let public = use_state(|| false);
+    let published_at = use_state(String::new);
+    let tags = use_state(String::new);
+    let new_slug = use_state(String::new);
     let preview = use_state(|| false);
+    let history = use_state(|| false);
     let slug = props.slug.clone();
 
     {
@@ -30,6 +55,9 @@ fn edit_page(props: &Props) -> Html {
         let content = content.clone();
         let summary = summary.clone();
         let public = public.clone();
+        let published_at = published_at.clone();
+        let tags = tags.clone();
+        let new_slug = new_slug.clone();
         let slug = slug.clone();
 
         use_effect_with(slug, move |slug| {
@@ -37,6 +65,9 @@ fn edit_page(props: &Props) -> Html {
             let content = content.clone();
             let summary = summary.clone();
             let public = public.clone();
+            let published_at = published_at.clone();
+            let tags = tags.clone();
+            let new_slug = new_slug.clone();
 
             if let Some(slug) = slug.clone() {
                 spawn_local(async move {
@@ -48,6 +79,9 @@ fn edit_page(props: &Props) -> Html {
                             content.set(post.content);
                             summary.set(post.summary.unwrap_or_default());
                             public.set(post.public);
+                            published_at.set(to_datetime_input(post.published_at));
+                            tags.set(post.tags.join(", "));
+                            new_slug.set(post.slug);
                         }
                         Ok(None) => yew_router::history::BrowserHistory::new().replace("/404"),
                         Err(_) => {
@@ -109,6 +143,33 @@ fn edit_page(props: &Props) -> Html {
         })
     };
 
+    let on_published_at_change = {
+        let published_at = published_at.clone();
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                published_at.set(input.value());
+            }
+        })
+    };
+
+    let on_tags_input = {
+        let tags = tags.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                tags.set(input.value());
+            }
+        })
+    };
+
+    let on_slug_input = {
+        let new_slug = new_slug.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                new_slug.set(input.value());
+            }
+        })
+    };
+
     let on_editor_keydown = {
         let content = content.clone();
         Callback::from(move |e: KeyboardEvent| {
@@ -159,16 +220,28 @@ fn edit_page(props: &Props) -> Html {
 
     let on_toggle_preview = {
         let preview = preview.clone();
+        let history = history.clone();
         Callback::from(move |_| {
+            history.set(false);
             preview.set(!*preview);
         })
     };
 
+    let on_toggle_history = {
+        let history = history.clone();
+        Callback::from(move |_| {
+            history.set(!*history);
+        })
+    };
+
     let on_publish = {
         let title = title.clone();
         let content = content.clone();
         let summary = summary.clone();
         let public = public.clone();
+        let published_at = published_at.clone();
+        let tags = tags.clone();
+        let new_slug = new_slug.clone();
         let slug = props.slug.clone();
 
         Callback::from(move |_| {
@@ -176,22 +249,32 @@ fn edit_page(props: &Props) -> Html {
             let content = content.clone();
             let summary = summary.clone();
             let public = public.clone();
+            let published_at = from_datetime_input(&published_at);
+            let tags: Vec<String> = tags.split(',').map(String::from).collect();
+            let new_slug = new_slug.clone();
             let api_error_cb = Callback::from(|err: ApiError| log::error!("{:?}", err));
             let slug = slug.clone();
 
             spawn_local(async move {
                 match if let Some(slug) = slug {
-                    ApiService::_update_post(
-                        &slug,
-                        &content,
-                        &title,
-                        if !summary.is_empty() {
-                            Some(&summary)
+                    ApiService::_update_post(&UpdatePostRequest {
+                        slug,
+                        content: (*content).clone(),
+                        title: (*title).clone(),
+                        summary: if !summary.is_empty() {
+                            Some((*summary).clone())
                         } else {
                             None
                         },
-                        *public,
-                    )
+                        public: *public,
+                        published_at,
+                        tags,
+                        new_slug: if !new_slug.is_empty() {
+                            Some((*new_slug).clone())
+                        } else {
+                            None
+                        },
+                    })
                     .await
                 } else {
                     ApiService::create_post(
@@ -203,6 +286,8 @@ fn edit_page(props: &Props) -> Html {
                             None
                         },
                         *public,
+                        published_at,
+                        &tags,
                     )
                     .await
                 } {
@@ -229,6 +314,10 @@ fn edit_page(props: &Props) -> Html {
     let mut html_out = String::new();
     push_html(&mut html_out, parser);
 
+    let scheduled_for = from_datetime_input(&published_at)
+        .filter(|datetime| *public && *datetime > Utc::now())
+        .map(|datetime| datetime.with_timezone(&Local));
+
     html! {
         <Layout>
             <div class="post-title">
@@ -239,18 +328,36 @@ fn edit_page(props: &Props) -> Html {
             <div class="create-container">
                 <div class="editor-bar">
                     <div class="clickable" onclick={on_toggle_preview}>
-                        if *preview {
+                        if *preview && !*history {
                             <i class="icon-pencil icon"></i> { "Edit" }
                         } else {
                             <i class="icon-eye icon"></i> { "Preview" }
                         }
                     </div>
+                    if slug.is_some() {
+                        <div class="clickable" onclick={on_toggle_history}>
+                            if *history {
+                                <i class="icon-pencil icon"></i> { "Edit" }
+                            } else {
+                                <i class="icon-link icon"></i> { "History" }
+                            }
+                        </div>
+                    }
                     <button disabled={title.is_empty() || content.is_empty()}
                         onclick={on_publish}>
-                        { "Publish" }
+                        if scheduled_for.is_some() { { "Schedule" } } else { { "Publish" } }
                     </button>
+                    if let Some(datetime) = scheduled_for {
+                        <span class="scheduled">
+                            { format!("Scheduled for {}", datetime.format("%d %b %Y %H:%M")) }
+                        </span>
+                    }
                 </div>
-                if *preview {
+                if let (true, Some(slug)) = (*history, slug.clone()) {
+                    <div class="md-preview">
+                        <RevisionHistory slug={slug} />
+                    </div>
+                } else if *preview {
                     <div class="md-preview">
                         { Html::from_html_unchecked(html_out.into()) }
                     </div>
@@ -259,6 +366,22 @@ fn edit_page(props: &Props) -> Html {
                         <label for="public"> { "Public" } </label>
                         <input type="checkbox" name="public" onchange={on_public_change} checked={*public}/>
 
+                        <label for="published-at"> { "Publish at" } </label>
+                        <input type="datetime-local" name="published-at"
+                        value={(*published_at).clone()}
+                        onchange={on_published_at_change}/>
+
+                        if slug.is_some() {
+                            <label for="slug"> { "Slug" } </label>
+                            <input type="text" name="slug"
+                            value={(*new_slug).clone()}
+                            oninput={on_slug_input}/>
+                        }
+
+                        <input type="text" name="tags" placeholder={"Tags, separated by commas..."}
+                        value={(*tags).clone()}
+                        oninput={on_tags_input}/>
+
                         <textarea placeholder={"Write here the summary..." }
                         rows={5}
                         value={(*summary).clone()}
@@ -0,0 +1,94 @@
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, SubmitEvent};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    components::{NotificationLevel, ServiceNotification},
+    routes::AppRoute,
+    services::auth::{AuthError, AuthService},
+};
+use common::utils::is_valid_email;
+
+#[function_component(ForgotPasswordForm)]
+pub fn forgot_password_form() -> Html {
+    let email = use_state(String::new);
+    let service_notification_text = use_state(String::new);
+    let service_notification_level = use_state(NotificationLevel::default);
+    let disable_submit = use_state(|| false);
+
+    let valid_email = is_valid_email(&email);
+    let enabled = valid_email && !*disable_submit;
+
+    let onsubmit = {
+        let service_notification_text = service_notification_text.clone();
+        let service_notification_level = service_notification_level.clone();
+        let disable_submit = disable_submit.clone();
+        let email = email.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            service_notification_text.set(String::new());
+            disable_submit.set(true);
+
+            let email = email.clone();
+            let service_notification_text = service_notification_text.clone();
+            let service_notification_level = service_notification_level.clone();
+            let disable_submit = disable_submit.clone();
+            let success_text =
+                "If there is an account with that e-mail, a reset link was sent to it".to_string();
+
+            spawn_local(async move {
+                match AuthService::forgot_password(email.as_str()).await {
+                    Ok(()) => {
+                        service_notification_text.set(success_text.clone());
+                        service_notification_level.set(NotificationLevel::Info);
+                    }
+                    Err(err) => {
+                        disable_submit.set(false);
+                        let error_text = match err {
+                            AuthError::NetworkError => "can't reach server".to_string(),
+                            _ => "please try again later".to_string(),
+                        };
+                        service_notification_text
+                            .set(format!("Error sending the reset link, {}", error_text));
+                        service_notification_level.set(NotificationLevel::Error);
+                    }
+                }
+            });
+        })
+    };
+
+    let on_email_input = {
+        let email = email.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                email.set(input.value());
+            }
+        })
+    };
+
+    html! {
+        <div class="login">
+            <h2>{"Forgot your password?"}</h2>
+            if !(*service_notification_text).is_empty() {
+                <ServiceNotification message={(*service_notification_text).clone()} level={*service_notification_level} />
+            }
+
+            <form onsubmit={onsubmit}>
+                <div class="input-wrapper">
+                    <i class="icon-mail-alt icon"></i>
+                    <input
+                        type="email"
+                        placeholder="Email"
+                        value={(*email).clone()}
+                        oninput={on_email_input}
+                    />
+                </div>
+                <button disabled={!enabled} type="submit">{"Send reset link"}</button>
+            </form>
+
+            <Link<AppRoute> to={AppRoute::Login} classes="bottom">{"Remembered it? Login"}</Link<AppRoute>>
+        </div>
+    }
+}
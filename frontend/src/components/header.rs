@@ -1,6 +1,7 @@
 use yew::prelude::*;
 
 use crate::utils::*;
+use common::Capability;
 
 #[derive(PartialEq, Properties)]
 pub struct HeaderProps {
@@ -21,15 +22,24 @@ pub fn header(HeaderProps { user }: &HeaderProps) -> Html {
             <nav>
                 <a href={ "/" }> { "Home" } </a>
                 <a href={ "/blog" }> { "Blog" } </a>
+                <a href={ "/search" }> { "Search" } </a>
                 <div> { "|" }</div>
                 <div class="header-user">
-                    if let Some(User {username, role }) = user {
-                        if role == "Admin" || role == "Editor" {
+                    if let Some(user) = user {
+                        if user.can(Capability::CreatePost) {
                             <a classes="clickable" href={ "/create" }>
                                 <i class="icon-edit icon"></i> { "Create" }
                             </a>
                         }
-                        <div class="username">{ username }</div>
+                        if user.can(Capability::ModerateComments) {
+                            <a href={ "/moderation" }> { "Comments" } </a>
+                        }
+                        if user.can(Capability::ManageTrash) {
+                            <a href={ "/trash" }> { "Trash" } </a>
+                        }
+                        <a href={ "/two-factor" }> { "2FA" } </a>
+                        <a href={ "/tokens" }> { "Tokens" } </a>
+                        <div class="username">{ &user.username }</div>
                         <a class="button" href="/logout"> { "Logout" } </a>
                     }
                     else {
@@ -1,24 +1,46 @@
+mod api_tokens;
 mod blog;
+mod comment_moderation;
+mod comments;
 mod edit;
 mod footer;
+mod forgot_password;
 mod header;
 mod home;
+mod password_reset;
 mod post;
 mod post_card;
+mod resend_confirmation;
+mod revision_history;
+mod search;
+mod series;
 mod service_notifications;
+mod trash;
+mod two_factor;
 mod user_confirmation;
 mod user_login;
 mod user_registration;
 
+pub use api_tokens::ApiTokenSettings;
 pub use blog::*;
+pub use comment_moderation::CommentModeration;
+pub use comments::*;
 pub use edit::*;
 pub use home::Home;
 pub use post::*;
 
 pub use footer::Footer;
+pub use forgot_password::ForgotPasswordForm;
 pub use header::Header;
+pub use password_reset::PasswordResetForm;
 pub use post_card::PostCard;
+pub use resend_confirmation::ResendConfirmationForm;
+pub use revision_history::RevisionHistory;
+pub use search::*;
+pub use series::*;
 pub use service_notifications::*;
+pub use trash::TrashPage;
+pub use two_factor::TwoFactorSettings;
 pub use user_confirmation::UserConfirmation;
 pub use user_login::LoginForm;
 pub use user_registration::UserRegistration;
@@ -0,0 +1,128 @@
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, SubmitEvent};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    components::{NotificationLevel, ServiceNotification},
+    routes::AppRoute,
+    services::auth::{AuthError, AuthService},
+};
+use common::utils::is_valid_password;
+
+#[derive(Properties, PartialEq)]
+pub struct PasswordResetProps {
+    pub token: String,
+}
+
+#[function_component(PasswordResetForm)]
+pub fn password_reset_form(props: &PasswordResetProps) -> Html {
+    let password = use_state(String::new);
+    let repeated_password = use_state(String::new);
+    let service_notification_text = use_state(String::new);
+    let service_notification_level = use_state(NotificationLevel::default);
+    let disable_submit = use_state(|| false);
+    let done = use_state(|| false);
+
+    let valid_password = is_valid_password(&password) && *password == *repeated_password;
+    let enabled = valid_password && !*disable_submit && !*done;
+
+    let onsubmit = {
+        let service_notification_text = service_notification_text.clone();
+        let service_notification_level = service_notification_level.clone();
+        let disable_submit = disable_submit.clone();
+        let done = done.clone();
+        let password = password.clone();
+        let token = props.token.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            service_notification_text.set(String::new());
+            disable_submit.set(true);
+
+            let password = password.clone();
+            let token = token.clone();
+            let service_notification_text = service_notification_text.clone();
+            let service_notification_level = service_notification_level.clone();
+            let disable_submit = disable_submit.clone();
+            let done = done.clone();
+
+            spawn_local(async move {
+                match AuthService::reset_password(&token, password.as_str()).await {
+                    Ok(()) => {
+                        service_notification_text
+                            .set("Your password was changed, you can log in now".to_string());
+                        service_notification_level.set(NotificationLevel::Success);
+                        done.set(true);
+                    }
+                    Err(err) => {
+                        disable_submit.set(false);
+                        let error_text = match err {
+                            AuthError::InvalidResetLink => {
+                                "this link was already used or has expired".to_string()
+                            }
+                            AuthError::NetworkError => "can't reach server".to_string(),
+                            _ => "please try again later".to_string(),
+                        };
+                        service_notification_text
+                            .set(format!("Error changing the password, {}", error_text));
+                        service_notification_level.set(NotificationLevel::Error);
+                    }
+                }
+            });
+        })
+    };
+
+    let on_password_input = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                password.set(input.value());
+            }
+        })
+    };
+
+    let on_repeated_password_input = {
+        let repeated_password = repeated_password.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                repeated_password.set(input.value());
+            }
+        })
+    };
+
+    html! {
+        <div class="login">
+            <h2>{"Choose a new password"}</h2>
+            if !(*service_notification_text).is_empty() {
+                <ServiceNotification message={(*service_notification_text).clone()} level={*service_notification_level} />
+            }
+
+            <form onsubmit={onsubmit}>
+                <div class="input-wrapper">
+                    <i class="icon-lock icon"></i>
+                    <input
+                        type="password"
+                        placeholder="New password"
+                        value={(*password).clone()}
+                        oninput={on_password_input}
+                    />
+                </div>
+                <div class="input-wrapper">
+                    <i class="icon-lock icon"></i>
+                    <input
+                        type="password"
+                        placeholder="Repeat the new password"
+                        value={(*repeated_password).clone()}
+                        oninput={on_repeated_password_input}
+                    />
+                </div>
+                <button disabled={!enabled} type="submit">{"Change password"}</button>
+            </form>
+
+            <Link<AppRoute> to={AppRoute::ForgotPassword} classes="link">{"Need a new link?"}</Link<AppRoute>>
+
+            <Link<AppRoute> to={AppRoute::Login} classes="bottom">{"Go to login"}</Link<AppRoute>>
+        </div>
+    }
+}
@@ -2,8 +2,11 @@ use std::sync::Arc;
 
 use yew::prelude::*;
 
-use crate::utils::User;
-use common::{Header, Post};
+use crate::{
+    components::{Comments, SeriesBox},
+    utils::User,
+};
+use common::{Comment, Header, Post, SeriesOverview};
 
 #[derive(PartialEq, Properties)]
 pub struct PostProps {
@@ -16,6 +19,10 @@ pub struct PostProps {
     pub headers: Vec<Header>,
     #[prop_or_default]
     pub user: Option<User>,
+    #[prop_or_default]
+    pub series: Option<Arc<SeriesOverview>>,
+    #[prop_or_default]
+    pub comments: Vec<Comment>,
 }
 
 #[function_component(PostPage)]
@@ -26,6 +33,8 @@ pub fn post_page(
         post_content,
         headers,
         user,
+        series,
+        comments,
     }: &PostProps,
 ) -> Html {
     html! {
@@ -36,6 +45,14 @@ pub fn post_page(
                     <time datetime={post.published_at.to_rfc2822()}>
                         { post.published_at.format("%d %b %Y").to_string() }
                     </time>
+                    if post.is_scheduled() {
+                        { format!(" · Scheduled for {}", post.published_at.format("%H:%M UTC")) }
+                    }
+                    <div class="tags">
+                        { for post.tags.iter().map(|tag| html! {
+                            <a class="tag" href={ format!("/tag/{}", tag) }>{ format!("#{}", tag) }</a>
+                        }) }
+                    </div>
                 </div>
             </div>
 
@@ -57,11 +74,16 @@ pub fn post_page(
                 </a>
 
                 <div class="post">
+                    if let Some(series) = series {
+                        <SeriesBox series={series.clone()} current={post.slug.clone()} />
+                    }
                     { Html::from_html_unchecked((*post_content).clone().into()) }
                 </div>
 
-                if let Some(User { username: _, role }) = user {
-                    if role == "Admin" || role == "Editor" {
+                <Comments post_slug={post.slug.clone()} comments={comments.clone()} user={user.clone()} />
+
+                if let Some(user) = user {
+                    if user.can_edit_post(&post.author) {
                         <div class="post-edit-bar">
                             <a class="clickable" href={ format!("/edit/{}", post.slug.clone()) }>
                                 <i class="icon-edit icon"></i> { "Edit this post" }
@@ -75,19 +97,19 @@ pub fn post_page(
                             }
 
                             <dialog id="delete-dialog">
-                                { "Are you sure you want to delete this post?" }
-                                <div>
+                                { "Move this post to the trash?" }
+                                <form method="post" action={ format!("/delete/{}", slug) }>
                                     {
                                         Html::from_html_unchecked(r#"
-                                        <button onclick="document.getElementById('delete-dialog').close()">
+                                        <button type="button" onclick="document.getElementById('delete-dialog').close()">
                                             Cancel
                                         </button>
                                         "#.into())
                                     }
-                                    <a id="delete-button" class="button" href={ format!("/delete/{}", slug) }>
+                                    <button id="delete-button" type="submit">
                                         { "Accept" }
-                                    </a>
-                                </div>
+                                    </button>
+                                </form>
                             </dialog>
                         </div>
                     }
@@ -1,26 +1,48 @@
 use std::sync::Arc;
 
-use common::{utils::get_summary, Post};
+use common::{PostSummary, SnippetFragment};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct Props {
-    pub post: Arc<Post>,
+    pub post: Arc<PostSummary>,
+    /// Shown instead of the summary, e.g. for search results.
+    #[prop_or_default]
+    pub snippet: Option<Vec<SnippetFragment>>,
 }
 
-const MAX_CONTENT_PREVIEW_LENGTH: usize = 150;
-
 #[function_component(PostCard)]
-pub fn post_card(Props { post }: &Props) -> Html {
-    let generated_summary = get_summary(&post.content, MAX_CONTENT_PREVIEW_LENGTH);
-    let summary = post.summary.as_deref().unwrap_or(&generated_summary);
+pub fn post_card(Props { post, snippet }: &Props) -> Html {
+    let visibility = if !post.public {
+        " non-public"
+    } else if post.is_scheduled() {
+        " scheduled"
+    } else {
+        ""
+    };
 
     html! {
-        <a class={ format!("post-card{}", if post.public { "" } else { " non-public" } ) } href={ format!("/post/{}", post.slug) } >
+        <a class={ format!("post-card{}", visibility) } href={ format!("/post/{}", post.slug) } >
             <h2 style={ format!("view-transition-name: {}", post.slug) }> { &post.title } </h2>
-            <p class="preview"> { summary } </p>
+            if let Some(snippet) = snippet {
+                <p class="preview">
+                    { for snippet.iter().map(|fragment| if fragment.highlight {
+                        html! { <mark>{ &fragment.text }</mark> }
+                    } else {
+                        html! { { &fragment.text } }
+                    }) }
+                </p>
+            } else {
+                <p class="preview"> { &post.summary } </p>
+            }
             <div class="lower-strip">
+                <div class="tags">
+                    { for post.tags.iter().map(|tag| html! { <span class="tag">{ format!("#{}", tag) }</span> }) }
+                </div>
                 <time datetime={post.published_at.to_rfc2822()}> { &post.published_at.format("%d %b %Y").to_string() } </time>
+                if post.is_scheduled() {
+                    <span class="scheduled"> { "Scheduled" } </span>
+                }
             </div>
         </a>
     }
@@ -0,0 +1,137 @@
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::services::api::{ApiError, ApiService};
+use common::{DiffLine, DiffTag, PostRevision, RevisionDiff};
+
+#[derive(Properties, PartialEq)]
+pub struct RevisionHistoryProps {
+    pub slug: String,
+}
+
+fn diff_section(name: &str, lines: &[DiffLine]) -> Html {
+    if lines.iter().all(|line| line.tag == DiffTag::Equal) {
+        return html! {};
+    }
+
+    html! {
+        <>
+            <h4>{ name }</h4>
+            <pre class="diff">
+                { for lines.iter().map(|line| {
+                    let (class, marker) = match line.tag {
+                        DiffTag::Equal => ("diff-line", "  "),
+                        DiffTag::Insert => ("diff-line insert", "+ "),
+                        DiffTag::Delete => ("diff-line delete", "- "),
+                    };
+                    html! { <div class={class}>{ marker }{ &line.text }</div> }
+                }) }
+            </pre>
+        </>
+    }
+}
+
+#[function_component(RevisionHistory)]
+pub fn revision_history(RevisionHistoryProps { slug }: &RevisionHistoryProps) -> Html {
+    let revisions = use_state(Vec::<PostRevision>::new);
+    let diff = use_state(|| None::<RevisionDiff>);
+
+    {
+        let revisions = revisions.clone();
+
+        use_effect_with(slug.clone(), move |slug| {
+            let slug = slug.clone();
+            spawn_local(async move {
+                match ApiService::get_revisions(&slug).await {
+                    Ok(fetched) => revisions.set(fetched),
+                    Err(err) => log::error!("Error fetching revisions: {:?}", err),
+                }
+            });
+
+            || ()
+        });
+    }
+
+    let latest = revisions.last().map(|revision| revision.number);
+    let api_error_cb = Callback::from(|err: ApiError| log::error!("{:?}", err));
+
+    html! {
+        <div class="revision-history">
+            <ul>
+                { for revisions.iter().rev().map(|revision| {
+                    let number = revision.number;
+
+                    let on_diff = {
+                        let diff = diff.clone();
+                        let slug = slug.clone();
+                        let api_error_cb = api_error_cb.clone();
+                        Callback::from(move |_| {
+                            let diff = diff.clone();
+                            let slug = slug.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            if let Some(latest) = latest {
+                                spawn_local(async move {
+                                    match ApiService::get_revision_diff(&slug, number, latest).await {
+                                        Ok(fetched) => diff.set(Some(fetched)),
+                                        Err(err) => api_error_cb.emit(err),
+                                    }
+                                });
+                            }
+                        })
+                    };
+
+                    let on_restore = {
+                        let slug = slug.clone();
+                        let api_error_cb = api_error_cb.clone();
+                        Callback::from(move |_| {
+                            let slug = slug.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            spawn_local(async move {
+                                match ApiService::restore_revision(&slug, number).await {
+                                    Ok(slug) => {
+                                        if let Some(window) = web_sys::window() {
+                                            let _ = window.location().replace(&format!("/edit/{}", slug));
+                                        }
+                                    }
+                                    Err(err) => api_error_cb.emit(err),
+                                }
+                            });
+                        })
+                    };
+
+                    html! {
+                        <li>
+                            <div>
+                                <b>{ format!("#{} ", number) }</b>
+                                { &revision.title }
+                            </div>
+                            <div class="details">
+                                { format!("{} · ", revision.author) }
+                                <time datetime={revision.created_at.to_rfc2822()}>
+                                    { revision.created_at.format("%d %b %Y %H:%M").to_string() }
+                                </time>
+                            </div>
+                            if Some(number) == latest {
+                                <span class="disabled">{ "Current" }</span>
+                            } else {
+                                <div class="revision-actions">
+                                    <button onclick={on_diff}>{ "Compare with current" }</button>
+                                    <button onclick={on_restore}>{ "Restore" }</button>
+                                </div>
+                            }
+                        </li>
+                    }
+                }) }
+            </ul>
+
+            if let Some(diff) = &*diff {
+                <div class="revision-diff">
+                    <h3>{ format!("#{} → #{}", diff.from, diff.to) }</h3>
+                    { diff_section("Title", &diff.title) }
+                    { diff_section("Summary", &diff.summary) }
+                    { diff_section("Content", &diff.content) }
+                </div>
+            }
+        </div>
+    }
+}
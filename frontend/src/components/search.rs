@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use yew::prelude::*;
+
+use crate::components::PostCard;
+use common::SearchHit;
+
+#[derive(PartialEq, Properties)]
+pub struct SearchPageProps {
+    #[prop_or_default]
+    pub query: AttrValue,
+    #[prop_or(0)]
+    pub page: u64,
+    #[prop_or(0)]
+    pub pages: u64,
+    #[prop_or_default]
+    pub hits: Vec<Arc<SearchHit>>,
+}
+
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b' ' => String::from("+"),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[function_component(SearchPage)]
+pub fn search_page(
+    SearchPageProps {
+        query,
+        page,
+        pages,
+        hits,
+    }: &SearchPageProps,
+) -> Html {
+    let page_url = |page: u64| format!("/search?q={}&page={}", encode_query_value(query), page);
+
+    html! {
+        <>
+            <section class="blog-header">
+                <form class="search-form" action="/search" method="get">
+                    <input type="search" name="q" placeholder="Search..." value={query.clone()} />
+                    <button type="submit">{ "Search" }</button>
+                </form>
+            </section>
+
+            <div class="blog-posts-list">
+                <div class="posts-container">
+                    if hits.is_empty() && !query.is_empty() {
+                        <p>{ "No posts found." }</p>
+                    }
+                    { for hits.iter().map(|hit| html! {
+                        <PostCard post={Arc::new(hit.post.clone())} snippet={hit.snippet.clone()} />
+                    }) }
+                </div>
+                if *pages > 1 {
+                    <div class="posts-container-navigation">
+                        if *page > 1 {
+                            <a href={ page_url(page - 1) }>
+                                <i class="icon-left icon"></i> { "Previous page" }
+                            </a>
+                        } else {
+                            <span class="disabled">
+                                <i class="icon-left icon"></i> { "Previous page" }
+                            </span>
+                        }
+                        <div>{ page } { " / " } { pages }</div>
+                        if page < pages {
+                            <a href={ page_url(page + 1) }>
+                                { "Next page" } <i class="icon-right icon"></i>
+                            </a>
+                        } else {
+                            <span class="disabled">
+                                { "Next page" } <i class="icon-right icon"></i>
+                            </span>
+                        }
+                    </div>
+                }
+            </div>
+        </>
+    }
+}
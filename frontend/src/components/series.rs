@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use yew::prelude::*;
+
+use crate::components::PostCard;
+use common::{PostSummary, Series, SeriesOverview};
+
+#[derive(PartialEq, Properties)]
+pub struct SeriesBoxProps {
+    pub series: Arc<SeriesOverview>,
+    /// Slug of the post being read.
+    pub current: AttrValue,
+}
+
+#[function_component(SeriesBox)]
+pub fn series_box(SeriesBoxProps { series, current }: &SeriesBoxProps) -> Html {
+    let position = series
+        .parts
+        .iter()
+        .position(|part| part.slug == current.as_str());
+    let prev = position.and_then(|index| index.checked_sub(1).map(|index| &series.parts[index]));
+    let next = position.and_then(|index| series.parts.get(index + 1));
+
+    html! {
+        <aside class="series-box">
+            <div class="series-title">
+                <a href={ format!("/series/{}", series.slug) }>{ &series.title }</a>
+                if let Some(index) = position {
+                    <span class="details">{ format!(" · Part {} of {}", index + 1, series.parts.len()) }</span>
+                }
+            </div>
+            <ol>
+                { for series.parts.iter().map(|part| html! {
+                    <li>
+                        if part.slug == current.as_str() {
+                            <b>{ &part.title }</b>
+                        } else {
+                            <a href={ format!("/post/{}", part.slug) }>{ &part.title }</a>
+                        }
+                    </li>
+                }) }
+            </ol>
+            <div class="series-navigation">
+                if let Some(prev) = prev {
+                    <a href={ format!("/post/{}", prev.slug) }>
+                        <i class="icon-left icon"></i> { &prev.title }
+                    </a>
+                } else {
+                    <span></span>
+                }
+                if let Some(next) = next {
+                    <a href={ format!("/post/{}", next.slug) }>
+                        { &next.title } <i class="icon-right icon"></i>
+                    </a>
+                }
+            </div>
+        </aside>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct SeriesPageProps {
+    #[prop_or_default]
+    pub series: Arc<Series>,
+    #[prop_or_default]
+    pub posts: Vec<Arc<PostSummary>>,
+}
+
+#[function_component(SeriesPage)]
+pub fn series_page(SeriesPageProps { series, posts }: &SeriesPageProps) -> Html {
+    html! {
+        <>
+            <section class="blog-header">
+                <h1>{ &series.title }</h1>
+                <p>{ &series.description }</p>
+            </section>
+
+            <div class="blog-posts-list">
+                <div class="posts-container">
+                    { for posts.iter().map(|post| html! {
+                        <PostCard post={post.clone()} />
+                    }) }
+                </div>
+            </div>
+        </>
+    }
+}
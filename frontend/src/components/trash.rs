@@ -0,0 +1,104 @@
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::{
+    pages::Layout,
+    services::api::{ApiError, ApiService},
+    utils::set_title,
+};
+use common::TrashedPost;
+
+#[function_component(TrashPage)]
+pub fn trash_page() -> Html {
+    let trash = use_state(Vec::<TrashedPost>::new);
+    // Bumped after every action to fetch the trash again
+    let reload = use_state(|| 0u32);
+
+    {
+        let trash = trash.clone();
+
+        use_effect_with(*reload, move |_| {
+            set_title("Trash");
+            spawn_local(async move {
+                match ApiService::get_trash().await {
+                    Ok(fetched) => trash.set(fetched),
+                    Err(err) => log::error!("Error fetching the trash: {:?}", err),
+                }
+            });
+
+            || ()
+        });
+    }
+
+    let api_error_cb = Callback::from(|err: ApiError| log::error!("{:?}", err));
+
+    html! {
+        <Layout>
+            <div class="trash">
+                <h1>{ "Trash" }</h1>
+
+                if trash.is_empty() {
+                    <p class="details">{ "The trash is empty." }</p>
+                }
+
+                <ul>
+                    { for trash.iter().map(|trashed| {
+                        let slug = trashed.post.slug.clone();
+
+                        let on_restore = {
+                            let slug = slug.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            Callback::from(move |_| {
+                                let slug = slug.clone();
+                                let api_error_cb = api_error_cb.clone();
+                                spawn_local(async move {
+                                    match ApiService::restore_trashed_post(&slug).await {
+                                        Ok(slug) => {
+                                            if let Some(window) = web_sys::window() {
+                                                let _ = window.location().replace(&format!("/post/{}", slug));
+                                            }
+                                        }
+                                        Err(err) => api_error_cb.emit(err),
+                                    }
+                                });
+                            })
+                        };
+
+                        let on_purge = {
+                            let slug = slug.clone();
+                            let reload = reload.clone();
+                            let api_error_cb = api_error_cb.clone();
+                            Callback::from(move |_| {
+                                let slug = slug.clone();
+                                let reload = reload.clone();
+                                let api_error_cb = api_error_cb.clone();
+                                spawn_local(async move {
+                                    match ApiService::purge_trashed_post(&slug).await {
+                                        Ok(_) => reload.set(*reload + 1),
+                                        Err(err) => api_error_cb.emit(err),
+                                    }
+                                });
+                            })
+                        };
+
+                        html! {
+                            <li>
+                                <b>{ &trashed.post.title }</b>
+                                <div class="details">
+                                    { format!("/post/{} · deleted by {} · ", slug, trashed.deleted_by) }
+                                    <time datetime={trashed.deleted_at.to_rfc2822()}>
+                                        { trashed.deleted_at.format("%d %b %Y %H:%M").to_string() }
+                                    </time>
+                                </div>
+                                <div class="trash-actions">
+                                    <button onclick={on_restore}>{ "Restore" }</button>
+                                    <button onclick={on_purge}>{ "Delete forever" }</button>
+                                </div>
+                            </li>
+                        }
+                    }) }
+                </ul>
+            </div>
+        </Layout>
+    }
+}
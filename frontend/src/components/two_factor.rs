@@ -0,0 +1,398 @@
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, SubmitEvent};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{
+    components::{NotificationLevel, ServiceNotification},
+    routes::AppRoute,
+    services::{
+        api::ApiService,
+        auth::{AuthError, AuthService},
+    },
+    utils::set_title,
+};
+use common::{SiteSettings, TwoFactorSetup, TwoFactorStatus};
+
+fn error_text(err: AuthError) -> String {
+    match err {
+        AuthError::InvalidTwoFactorCode => "the code is wrong, check the time on your device",
+        AuthError::WrongPassword => "the password is wrong",
+        AuthError::Unauthorized => "you are no longer logged in",
+        AuthError::NetworkError => "can't reach server",
+        _ => "please try again later",
+    }
+    .to_string()
+}
+
+/// Sets up, turns off and renews the recovery codes of two-factor
+/// authentication. Users whose role requires it land here from the login
+/// page to set it up. Admins also decide here whether it is required.
+#[function_component(TwoFactorSettings)]
+pub fn two_factor_settings() -> Html {
+    let status = use_state(|| None::<TwoFactorStatus>);
+    // Only fetched for admins
+    let settings = use_state(|| None::<SiteSettings>);
+    let logged_out = use_state(|| false);
+    let setup = use_state(|| None::<TwoFactorSetup>);
+    // Only shown right after they are generated
+    let recovery_codes = use_state(|| None::<Vec<String>>);
+    let code = use_state(String::new);
+    let password = use_state(String::new);
+    let service_notification_text = use_state(String::new);
+    let service_notification_level = use_state(NotificationLevel::default);
+    let disable_submit = use_state(|| false);
+    // Bumped after every change to fetch the status again
+    let reload = use_state(|| 0u32);
+
+    {
+        let status = status.clone();
+        let settings = settings.clone();
+        let logged_out = logged_out.clone();
+
+        use_effect_with(*reload, move |_| {
+            set_title("Two-factor authentication");
+            spawn_local(async move {
+                match AuthService::get_two_factor_status().await {
+                    Ok(fetched) => status.set(Some(fetched)),
+                    Err(AuthError::Unauthorized) => logged_out.set(true),
+                    Err(_) => log::error!("Error fetching the two-factor status"),
+                }
+                if let Ok(fetched) = ApiService::get_settings().await {
+                    settings.set(Some(fetched));
+                }
+            });
+
+            || ()
+        });
+    }
+
+    let notify = {
+        let service_notification_text = service_notification_text.clone();
+        let service_notification_level = service_notification_level.clone();
+        let disable_submit = disable_submit.clone();
+        Callback::from(move |(text, level): (String, NotificationLevel)| {
+            disable_submit.set(false);
+            service_notification_text.set(text);
+            service_notification_level.set(level);
+        })
+    };
+
+    let on_setup = {
+        let password = password.clone();
+        let setup = setup.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        Callback::from(move |_| {
+            disable_submit.set(true);
+
+            let password = password.clone();
+            let setup = setup.clone();
+            let disable_submit = disable_submit.clone();
+            let notify = notify.clone();
+            spawn_local(async move {
+                match AuthService::setup_two_factor(password.as_str()).await {
+                    Ok(new_setup) => {
+                        password.set(String::new());
+                        disable_submit.set(false);
+                        setup.set(Some(new_setup));
+                    }
+                    Err(err) => notify.emit((
+                        format!(
+                            "Error setting up two-factor authentication, {}",
+                            error_text(err)
+                        ),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_enable = {
+        let code = code.clone();
+        let setup = setup.clone();
+        let recovery_codes = recovery_codes.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            disable_submit.set(true);
+
+            let code = code.clone();
+            let setup = setup.clone();
+            let recovery_codes = recovery_codes.clone();
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                match AuthService::enable_two_factor(code.as_str()).await {
+                    Ok(codes) => {
+                        setup.set(None);
+                        code.set(String::new());
+                        recovery_codes.set(Some(codes));
+                        notify.emit((
+                            "Two-factor authentication is on".to_string(),
+                            NotificationLevel::Success,
+                        ));
+                        reload.set(*reload + 1);
+                    }
+                    Err(err) => notify.emit((
+                        format!(
+                            "Error turning on two-factor authentication, {}",
+                            error_text(err)
+                        ),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_renew_codes = {
+        let password = password.clone();
+        let recovery_codes = recovery_codes.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |_| {
+            disable_submit.set(true);
+
+            let password = password.clone();
+            let recovery_codes = recovery_codes.clone();
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                match AuthService::renew_recovery_codes(password.as_str()).await {
+                    Ok(codes) => {
+                        password.set(String::new());
+                        recovery_codes.set(Some(codes));
+                        notify.emit((
+                            "The old recovery codes no longer work".to_string(),
+                            NotificationLevel::Success,
+                        ));
+                        reload.set(*reload + 1);
+                    }
+                    Err(err) => notify.emit((
+                        format!("Error creating new recovery codes, {}", error_text(err)),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_disable = {
+        let password = password.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |_| {
+            disable_submit.set(true);
+
+            let password = password.clone();
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                match AuthService::disable_two_factor(password.as_str()).await {
+                    Ok(()) => {
+                        password.set(String::new());
+                        notify.emit((
+                            "Two-factor authentication is off".to_string(),
+                            NotificationLevel::Success,
+                        ));
+                        reload.set(*reload + 1);
+                    }
+                    Err(err) => notify.emit((
+                        format!(
+                            "Error turning off two-factor authentication, {}",
+                            error_text(err)
+                        ),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_toggle_required = {
+        let settings = settings.clone();
+        let disable_submit = disable_submit.clone();
+        let notify = notify.clone();
+        let reload = reload.clone();
+        Callback::from(move |_| {
+            let Some(current) = (*settings).clone() else {
+                return;
+            };
+            disable_submit.set(true);
+
+            let notify = notify.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let updated = SiteSettings {
+                    require_two_factor: !current.require_two_factor,
+                };
+                match ApiService::update_settings(&updated).await {
+                    Ok(saved) => {
+                        let text = if saved.require_two_factor {
+                            "Admins and Editors now need two-factor authentication"
+                        } else {
+                            "Two-factor authentication is now optional"
+                        };
+                        notify.emit((text.to_string(), NotificationLevel::Success));
+                        reload.set(*reload + 1);
+                    }
+                    Err(_) => notify.emit((
+                        "Error saving the settings, please try again later".to_string(),
+                        NotificationLevel::Error,
+                    )),
+                }
+            });
+        })
+    };
+
+    let on_code_input = {
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                code.set(input.value());
+            }
+        })
+    };
+
+    let on_password_input = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                password.set(input.value());
+            }
+        })
+    };
+
+    let on_codes_saved = {
+        let recovery_codes = recovery_codes.clone();
+        let service_notification_text = service_notification_text.clone();
+        Callback::from(move |_| {
+            recovery_codes.set(None);
+            service_notification_text.set(String::new());
+        })
+    };
+
+    let content = if *logged_out {
+        html! {
+            <>
+                <p>{"Log in to manage two-factor authentication."}</p>
+                <Link<AppRoute> to={AppRoute::Login} classes="bottom">{"Go to login"}</Link<AppRoute>>
+            </>
+        }
+    } else if let Some(codes) = (*recovery_codes).clone() {
+        html! {
+            <>
+                <p>{"Keep these recovery codes somewhere safe. Each of them logs you in once if you lose your authenticator app, and they won't be shown again."}</p>
+                <ul class="recovery-codes">
+                    { for codes.iter().map(|code| html! { <li><code>{ code }</code></li> }) }
+                </ul>
+                <button onclick={on_codes_saved}>{"I saved them"}</button>
+                <a href="/" class="bottom">{"Go to the blog"}</a>
+            </>
+        }
+    } else if let Some(status) = (*status).clone() {
+        if status.enabled {
+            html! {
+                <>
+                    <p>{ format!("Two-factor authentication is on, with {} recovery codes left.", status.recovery_codes_left) }</p>
+                    <div class="input-wrapper">
+                        <i class="icon-lock icon"></i>
+                        <input
+                            type="password"
+                            placeholder="Password"
+                            value={(*password).clone()}
+                            oninput={on_password_input}
+                        />
+                    </div>
+                    <button disabled={password.is_empty() || *disable_submit} onclick={on_renew_codes}>
+                        {"New recovery codes"}
+                    </button>
+                    if status.required {
+                        <p class="details">{"Your role requires two-factor authentication, so it can't be turned off."}</p>
+                    } else {
+                        <button disabled={password.is_empty() || *disable_submit} onclick={on_disable}>
+                            {"Turn off"}
+                        </button>
+                    }
+                </>
+            }
+        } else if let Some(setup) = (*setup).clone() {
+            html! {
+                <>
+                    <p>{"Add this account to your authenticator app, by opening the link on your phone or typing the key in, then enter the code it shows."}</p>
+                    <a href={setup.provisioning_uri.clone()} class="link">{"Open in authenticator app"}</a>
+                    <pre class="secret">{ &setup.secret }</pre>
+                    <form onsubmit={on_enable}>
+                        <div class="input-wrapper">
+                            <i class="icon-lock icon"></i>
+                            <input
+                                type="text"
+                                placeholder="Code"
+                                autocomplete="one-time-code"
+                                value={(*code).clone()}
+                                oninput={on_code_input}
+                            />
+                        </div>
+                        <button disabled={code.trim().is_empty() || *disable_submit} type="submit">{"Turn on"}</button>
+                    </form>
+                </>
+            }
+        } else {
+            html! {
+                <>
+                    if status.required {
+                        <p>{"Your role requires two-factor authentication, set it up to finish logging in."}</p>
+                    } else {
+                        <p>{"Two-factor authentication asks for a code from an authenticator app on top of your password when you log in."}</p>
+                    }
+                    <div class="input-wrapper">
+                        <i class="icon-lock icon"></i>
+                        <input
+                            type="password"
+                            placeholder="Password"
+                            value={(*password).clone()}
+                            oninput={on_password_input}
+                        />
+                    </div>
+                    <button disabled={password.is_empty() || *disable_submit} onclick={on_setup}>
+                        {"Set up"}
+                    </button>
+                </>
+            }
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div class="login">
+            <h2>{"Two-factor authentication"}</h2>
+            if !(*service_notification_text).is_empty() {
+                <ServiceNotification message={(*service_notification_text).clone()} level={*service_notification_level} />
+            }
+
+            { content }
+
+            if let Some(settings) = (*settings).clone() {
+                <h3>{"For everyone"}</h3>
+                if settings.require_two_factor {
+                    <p>{"Admins and Editors have to use two-factor authentication."}</p>
+                    <button disabled={*disable_submit} onclick={on_toggle_required}>
+                        {"Make it optional"}
+                    </button>
+                } else {
+                    <p>{"Admins and Editors can log in without two-factor authentication."}</p>
+                    <button disabled={*disable_submit} onclick={on_toggle_required}>
+                        {"Require it"}
+                    </button>
+                }
+            }
+        </div>
+    }
+}
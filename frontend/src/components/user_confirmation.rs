@@ -1,42 +1,74 @@
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
-use crate::services::auth::AuthService;
+use crate::{
+    routes::AppRoute,
+    services::auth::{AuthError, AuthService},
+};
 
 #[derive(Properties, PartialEq)]
 pub struct ConfirmProps {
     pub token: String,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum ConfirmationStatus {
+    Confirming,
+    Confirmed,
+    Expired,
+    Used,
+    Unknown,
+    Failed,
+}
+
 #[function_component(UserConfirmation)]
 pub fn confirm_email(props: &ConfirmProps) -> Html {
-    let confirmation_status = use_state(|| "Confirming...".to_string());
-    let confirmed = use_state(|| false);
+    let status = use_state(|| ConfirmationStatus::Confirming);
 
     {
-        let confirmation_status = confirmation_status.clone();
-        let confirmed = confirmed.clone();
+        let status = status.clone();
         let token = props.token.clone();
 
         use_effect_with((), |_| {
             spawn_local(async move {
-                match AuthService::confirm(&token).await {
-                    Ok(_) => {
-                        confirmation_status.set("Confirmed!!!".to_string());
-                        confirmed.set(true);
-                    }
-                    Err(_) => confirmation_status.set("Error while confirming".to_string()),
-                };
+                status.set(match AuthService::confirm(&token).await {
+                    Ok(_) => ConfirmationStatus::Confirmed,
+                    Err(AuthError::ConfirmationExpired) => ConfirmationStatus::Expired,
+                    Err(AuthError::ConfirmationUsed) => ConfirmationStatus::Used,
+                    Err(AuthError::ConfirmationUnknown) => ConfirmationStatus::Unknown,
+                    Err(_) => ConfirmationStatus::Failed,
+                });
             })
         });
     }
 
-    html! {
-        <>
-            { (*confirmation_status).clone() }
-            if *confirmed {
+    match *status {
+        ConfirmationStatus::Confirming => html! { { "Confirming..." } },
+        ConfirmationStatus::Confirmed => html! {
+            <>
+                { "Confirmed!!!" }
                 <a href="/"> { "Go to the homepage " } </a>
-            }
-        </>
+            </>
+        },
+        ConfirmationStatus::Expired => html! {
+            <>
+                { "This confirmation link has expired. " }
+                <Link<AppRoute> to={AppRoute::ResendConfirmation}>{ "Get a new one" }</Link<AppRoute>>
+            </>
+        },
+        ConfirmationStatus::Used => html! {
+            <>
+                { "This account is confirmed already. " }
+                <Link<AppRoute> to={AppRoute::Login}>{ "Log in" }</Link<AppRoute>>
+            </>
+        },
+        ConfirmationStatus::Unknown => html! {
+            <>
+                { "This confirmation link isn't valid, make sure it was copied whole. " }
+                <Link<AppRoute> to={AppRoute::Register}>{ "Register again" }</Link<AppRoute>>
+            </>
+        },
+        ConfirmationStatus::Failed => html! { { "Error while confirming" } },
     }
 }
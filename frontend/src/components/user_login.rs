@@ -8,7 +8,10 @@ use crate::{
     routes::AppRoute,
     services::auth::{AuthError, AuthService},
 };
-use common::utils::{is_valid_password, is_valid_username};
+use common::{
+    utils::{is_valid_password, is_valid_username},
+    LoginChallenge,
+};
 
 #[function_component(LoginForm)]
 pub fn login_form() -> Html {
@@ -18,6 +21,10 @@ pub fn login_form() -> Html {
     let service_notification_level = use_state(NotificationLevel::default);
     let disable_submit = use_state(|| false);
 
+    // Set once the password was accepted and a second factor is needed
+    let asking_code = use_state(|| false);
+    let code = use_state(String::new);
+
     let valid_username = is_valid_username(&username);
     let valid_password = is_valid_password(&password);
     let enabled = valid_username && valid_password && !*disable_submit;
@@ -28,6 +35,7 @@ pub fn login_form() -> Html {
         let disable_submit = disable_submit.clone();
         let username = username.clone();
         let password = password.clone();
+        let asking_code = asking_code.clone();
 
         Callback::from(move |e: SubmitEvent| {
             e.prevent_default();
@@ -41,9 +49,11 @@ pub fn login_form() -> Html {
             let disable_submit = disable_submit.clone();
             let success_text = format!("Welcome back, {}!", *username);
 
+            let asking_code = asking_code.clone();
+
             spawn_local(async move {
                 match AuthService::login(username.as_str(), password.as_str()).await {
-                    Ok(()) => {
+                    Ok(None) => {
                         service_notification_text.set(success_text.clone());
                         service_notification_level.set(NotificationLevel::Success);
 
@@ -51,10 +61,69 @@ pub fn login_form() -> Html {
                             let _ = window.location().replace("/");
                         }
                     }
+                    Ok(Some(LoginChallenge::TwoFactor)) => {
+                        disable_submit.set(false);
+                        asking_code.set(true);
+                    }
+                    Ok(Some(LoginChallenge::TwoFactorSetup)) => {
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.location().replace("/two-factor");
+                        }
+                    }
+                    Err(err) => {
+                        disable_submit.set(false);
+                        let error_text = match err {
+                            AuthError::LoginError(err_str) => err_str,
+                            AuthError::NetworkError => "can't reach server".to_string(),
+                            _ => "something went wrong".to_string(),
+                        };
+                        service_notification_text.set(format!("Error logging in, {}", error_text));
+                        service_notification_level.set(NotificationLevel::Error);
+                    }
+                }
+            });
+        })
+    };
+
+    let on_code_submit = {
+        let service_notification_text = service_notification_text.clone();
+        let service_notification_level = service_notification_level.clone();
+        let disable_submit = disable_submit.clone();
+        let code = code.clone();
+        let asking_code = asking_code.clone();
+        let success_text = format!("Welcome back, {}!", *username);
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            service_notification_text.set(String::new());
+            disable_submit.set(true);
+
+            let code = code.clone();
+            let asking_code = asking_code.clone();
+            let service_notification_text = service_notification_text.clone();
+            let service_notification_level = service_notification_level.clone();
+            let disable_submit = disable_submit.clone();
+            let success_text = success_text.clone();
+
+            spawn_local(async move {
+                match AuthService::login_two_factor(code.as_str()).await {
+                    Ok(()) => {
+                        service_notification_text.set(success_text);
+                        service_notification_level.set(NotificationLevel::Success);
+
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.location().replace("/");
+                        }
+                    }
                     Err(err) => {
                         disable_submit.set(false);
+                        code.set(String::new());
                         let error_text = match err {
                             AuthError::LoginError(err_str) => err_str,
+                            AuthError::LoginExpired => {
+                                asking_code.set(false);
+                                "the login took too long, please start over".to_string()
+                            }
                             AuthError::NetworkError => "can't reach server".to_string(),
                             _ => "something went wrong".to_string(),
                         };
@@ -66,6 +135,42 @@ pub fn login_form() -> Html {
         })
     };
 
+    let on_code_input = {
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                code.set(input.value());
+            }
+        })
+    };
+
+    if *asking_code {
+        return html! {
+            <div class="login">
+                <h2>{"Two-factor authentication"}</h2>
+                if !(*service_notification_text).is_empty() {
+                    <ServiceNotification message={(*service_notification_text).clone()} level={*service_notification_level} />
+                }
+
+                <p>{"Enter the code from your authenticator app, or one of your recovery codes."}</p>
+
+                <form onsubmit={on_code_submit}>
+                    <div class="input-wrapper">
+                        <i class="icon-lock icon"></i>
+                        <input
+                            type="text"
+                            placeholder="Code"
+                            autocomplete="one-time-code"
+                            value={(*code).clone()}
+                            oninput={on_code_input}
+                        />
+                    </div>
+                    <button disabled={code.trim().is_empty() || *disable_submit} type="submit">{"Verify"}</button>
+                </form>
+            </div>
+        };
+    }
+
     let on_username_input = {
         let username = username.clone();
         Callback::from(move |e: InputEvent| {
@@ -113,7 +218,7 @@ pub fn login_form() -> Html {
                 <button disabled={!enabled} type="submit">{"Log In"}</button>
             </form>
 
-            <Link<AppRoute> to={AppRoute::Login} classes="link">{"Forgot username or password?"}</Link<AppRoute>>
+            <Link<AppRoute> to={AppRoute::ForgotPassword} classes="link">{"Forgot your password?"}</Link<AppRoute>>
 
             <Link<AppRoute> to={AppRoute::Register} classes="bottom">{"Don't have an account? Register"}</Link<AppRoute>>
         </div>
@@ -135,6 +135,8 @@ pub fn user_registration() -> Html {
                 <button disabled={!enabled} type="submit">{"Register"}</button>
             </form>
 
+            <Link<AppRoute> to={AppRoute::ResendConfirmation} classes="link">{"Didn't get the e-mail? Send it again"}</Link<AppRoute>>
+
             <Link<AppRoute> to={AppRoute::Login} classes="bottom">{"Already have an account? Login"}</Link<AppRoute>>
         </div>
     }
@@ -6,4 +6,5 @@ mod utils;
 
 pub use components::*;
 pub use pages::*;
-pub use utils::User as UsernameAndRole;
+pub use routes::AppRoute;
+pub use utils::{AppState, User as UsernameAndRole};
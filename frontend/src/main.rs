@@ -1,14 +1,11 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-mod components;
-mod pages;
-mod routes;
-mod services;
-mod utils;
-
-use components::{CreatePost, EditPost, LoginForm, UserConfirmation, UserRegistration};
-use routes::AppRoute;
+use frontend::{
+    ApiTokenSettings, AppRoute, CommentModeration, CreatePost, EditPost, ForgotPasswordForm,
+    LoginForm, PasswordResetForm, ResendConfirmationForm, TrashPage, TwoFactorSettings,
+    UserConfirmation, UserRegistration,
+};
 
 #[function_component(App)]
 fn app() -> Html {
@@ -24,8 +21,15 @@ fn switch(routes: AppRoute) -> Html {
         AppRoute::Login => html! { <LoginForm /> },
         AppRoute::Register => html! { <UserRegistration /> },
         AppRoute::Confirm { token } => html! { <UserConfirmation token={token} /> },
+        AppRoute::ResendConfirmation => html! { <ResendConfirmationForm /> },
+        AppRoute::ForgotPassword => html! { <ForgotPasswordForm /> },
+        AppRoute::ResetPassword { token } => html! { <PasswordResetForm token={token} /> },
+        AppRoute::TwoFactor => html! { <TwoFactorSettings /> },
+        AppRoute::ApiTokens => html! { <ApiTokenSettings /> },
         AppRoute::Create => html! { <CreatePost /> },
         AppRoute::Edit { slug } => html! { <EditPost slug={ slug } /> },
+        AppRoute::Moderation => html! { <CommentModeration /> },
+        AppRoute::Trash => html! { <TrashPage /> },
     }
 }
 
@@ -8,8 +8,22 @@ pub enum AppRoute {
     Register,
     #[at("/confirm/:token")]
     Confirm { token: String },
+    #[at("/resend-confirmation")]
+    ResendConfirmation,
+    #[at("/forgot-password")]
+    ForgotPassword,
+    #[at("/reset-password/:token")]
+    ResetPassword { token: String },
+    #[at("/two-factor")]
+    TwoFactor,
+    #[at("/tokens")]
+    ApiTokens,
     #[at("/create")]
     Create,
     #[at("/edit/:slug")]
     Edit { slug: String },
+    #[at("/moderation")]
+    Moderation,
+    #[at("/trash")]
+    Trash,
 }
@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
+
 use crate::{api_url, services::auth::AuthService};
 use common::{
-    CodeBlock, CreatePostRequest, GetPostsResponse, Post, PostCreatedResponse, UpdatePostRequest,
+    CodeBlock, Comment, CommentStatus, CreatePostRequest, GetPostsResponse, ModerateCommentRequest,
+    Post, PostCreatedResponse, PostRevision, PostSummary, RevisionDiff, SiteSettings, TrashedPost,
+    UpdatePostRequest,
 };
 use gloo_net::http::Request;
 use reqwest::StatusCode;
@@ -24,6 +28,8 @@ impl ApiService {
         content: &str,
         summary: Option<&str>,
         public: bool,
+        published_at: Option<DateTime<Utc>>,
+        tags: &[String],
     ) -> Result<String, ApiError> {
         if let Ok(builder) = AuthService::protected_post(&api_url!("/post/create")) {
             if let Ok(response) = builder
@@ -32,6 +38,8 @@ impl ApiService {
                     content: String::from(content),
                     summary: summary.map(String::from),
                     public,
+                    published_at,
+                    tags: tags.to_vec(),
                 })
                 .unwrap()
                 .send()
@@ -63,26 +71,9 @@ impl ApiService {
         Err(ApiError::RequestError)
     }
 
-    pub async fn _update_post(
-        slug: &str,
-        content: &str,
-        title: &str,
-        summary: Option<&str>,
-        public: bool,
-    ) -> Result<String, ApiError> {
+    pub async fn _update_post(post: &UpdatePostRequest) -> Result<String, ApiError> {
         if let Ok(builder) = AuthService::protected_post(&api_url!("/post/update")) {
-            if let Ok(response) = builder
-                .json(&UpdatePostRequest {
-                    slug: String::from(slug),
-                    content: String::from(content),
-                    title: String::from(title),
-                    summary: summary.map(String::from),
-                    public,
-                })
-                .unwrap()
-                .send()
-                .await
-            {
+            if let Ok(response) = builder.json(post).unwrap().send().await {
                 match StatusCode::from_u16(response.status()).unwrap() {
                     x if x.is_success() => {
                         if let Ok(PostCreatedResponse { slug }) =
@@ -174,7 +165,7 @@ impl ApiService {
         per_page: Option<u64>,
         sort_by: Option<String>,
         sort_order: Option<String>,
-    ) -> Result<(Vec<Post>, Result<u64, ()>), ApiError> {
+    ) -> Result<(Vec<PostSummary>, Result<u64, ()>), ApiError> {
         let mut params = vec![];
         if let Some(val) = page {
             params.push(("page", format!("{}", val)));
@@ -217,6 +208,333 @@ impl ApiService {
         Err(ApiError::RequestError)
     }
 
+    pub async fn get_revisions(slug: &str) -> Result<Vec<PostRevision>, ApiError> {
+        if let Ok(builder) =
+            AuthService::protected_get(&api_url!(format!("/post/revisions/{}", slug)))
+        {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(revisions) = response.json::<Vec<PostRevision>>().await {
+                            return Ok(revisions);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn get_revision_diff(
+        slug: &str,
+        from: u64,
+        to: u64,
+    ) -> Result<RevisionDiff, ApiError> {
+        if let Ok(builder) =
+            AuthService::protected_get(&api_url!(format!("/post/revisions/{}/diff", slug)))
+        {
+            if let Ok(response) = builder
+                .query([("from", from.to_string()), ("to", to.to_string())])
+                .send()
+                .await
+            {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(diff) = response.json::<RevisionDiff>().await {
+                            return Ok(diff);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn restore_revision(slug: &str, number: u64) -> Result<String, ApiError> {
+        if let Ok(builder) = AuthService::protected_post(&api_url!(format!(
+            "/post/revisions/{}/{}/restore",
+            slug, number
+        ))) {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(PostCreatedResponse { slug }) =
+                            response.json::<PostCreatedResponse>().await
+                        {
+                            return Ok(slug);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn get_comment_queue(status: CommentStatus) -> Result<Vec<Comment>, ApiError> {
+        if let Ok(builder) = AuthService::protected_get(&api_url!("/comment/queue")) {
+            if let Ok(response) = builder
+                .query([("status", format!("{:?}", status))])
+                .send()
+                .await
+            {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(comments) = response.json::<Vec<Comment>>().await {
+                            return Ok(comments);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn moderate_comment(id: &str, status: CommentStatus) -> Result<(), ApiError> {
+        if let Ok(builder) = AuthService::protected_post(&api_url!("/comment/moderate")) {
+            if let Ok(response) = builder
+                .json(&ModerateCommentRequest {
+                    id: String::from(id),
+                    status,
+                })
+                .unwrap()
+                .send()
+                .await
+            {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        return Ok(());
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn delete_comment(id: &str) -> Result<(), ApiError> {
+        if let Ok(builder) =
+            AuthService::_protected_delete(&api_url!(format!("/comment/delete/{}", id)))
+        {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        return Ok(());
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    /// Only answered for admins, others get `Unauthorized`.
+    pub async fn get_settings() -> Result<SiteSettings, ApiError> {
+        if let Ok(builder) = AuthService::protected_get(&api_url!("/settings")) {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(settings) = response.json::<SiteSettings>().await {
+                            return Ok(settings);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn update_settings(settings: &SiteSettings) -> Result<SiteSettings, ApiError> {
+        if let Ok(builder) = AuthService::protected_post(&api_url!("/settings/update")) {
+            if let Ok(response) = builder.json(settings).unwrap().send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(settings) = response.json::<SiteSettings>().await {
+                            return Ok(settings);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn get_trash() -> Result<Vec<TrashedPost>, ApiError> {
+        if let Ok(builder) = AuthService::protected_get(&api_url!("/trash/get-list")) {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(trash) = response.json::<Vec<TrashedPost>>().await {
+                            return Ok(trash);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn restore_trashed_post(slug: &str) -> Result<String, ApiError> {
+        if let Ok(builder) =
+            AuthService::protected_post(&api_url!(format!("/trash/restore/{}", slug)))
+        {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(PostCreatedResponse { slug }) =
+                            response.json::<PostCreatedResponse>().await
+                        {
+                            return Ok(slug);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
+    pub async fn purge_trashed_post(slug: &str) -> Result<u64, ApiError> {
+        if let Ok(builder) =
+            AuthService::_protected_delete(&api_url!(format!("/trash/purge/{}", slug)))
+        {
+            if let Ok(response) = builder.send().await {
+                match StatusCode::from_u16(response.status()).unwrap() {
+                    x if x.is_success() => {
+                        if let Ok(purged_count) = response.json::<u64>().await {
+                            return Ok(purged_count);
+                        }
+
+                        return Err(ApiError::UnknownResponse);
+                    }
+                    x if x.is_server_error() => {
+                        return Err(ApiError::ServerInternalError);
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        return Err(ApiError::Unauthorized);
+                    }
+                    _ => {
+                        return Err(ApiError::UnknownError);
+                    }
+                }
+            }
+        }
+
+        Err(ApiError::RequestError)
+    }
+
     pub async fn _highlight_code(
         code_blocks: HashMap<String, CodeBlock>,
     ) -> Result<HashMap<String, String>, ApiError> {
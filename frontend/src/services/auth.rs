@@ -1,9 +1,12 @@
-use gloo_net::http::{Request, RequestBuilder};
+use gloo_net::http::{Request, RequestBuilder, Response};
 use reqwest::StatusCode;
 use serde::Serialize;
 
 use crate::api_url;
-use crate::utils::*;
+use common::{
+    ApiTokenCreated, ApiTokenList, CreateApiTokenRequest, LoginChallenge, PasswordConfirmation,
+    RecoveryCodes, TwoFactorCodeRequest, TwoFactorSetup, TwoFactorStatus,
+};
 
 pub struct AuthService;
 
@@ -12,7 +15,43 @@ pub enum AuthError {
     LoginError(String),
     RegistrationError,
     ConfirmationError,
+    /// The confirmation link is older than a day.
+    ConfirmationExpired,
+    /// The account was confirmed already.
+    ConfirmationUsed,
+    /// No confirmation has that token.
+    ConfirmationUnknown,
     RegistrationConflict(String),
+    PasswordResetError,
+    /// The reset link was already used, expired or never existed.
+    InvalidResetLink,
+    /// The password was entered too long ago, or in another browser.
+    LoginExpired,
+    /// Not logged in, nor in the middle of a login that needs two-factor
+    /// authentication set up.
+    Unauthorized,
+    TwoFactorError,
+    InvalidTwoFactorCode,
+    WrongPassword,
+    ApiTokenError,
+    /// A field of a new API token was rejected, named by the server.
+    InvalidApiToken(String),
+}
+
+/// The wait in the `Retry-After` header of a rate limited login, in words.
+fn retry_after(response: &Response) -> String {
+    let secs: u64 = response
+        .headers()
+        .get("retry-after")
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(1);
+
+    match secs {
+        1 => "1 second".to_string(),
+        2..60 => format!("{} seconds", secs),
+        60 => "1 minute".to_string(),
+        _ => format!("{} minutes", secs.div_ceil(60)),
+    }
 }
 
 #[derive(Serialize)]
@@ -26,7 +65,6 @@ struct RegistrationForm<'a> {
     username: &'a str,
     email: &'a str,
     password: &'a str,
-    host: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -34,8 +72,24 @@ struct UserConfirmation<'a> {
     confirmation_token: &'a str,
 }
 
+#[derive(Serialize)]
+struct EmailLinkForm<'a> {
+    email: &'a str,
+}
+
+#[derive(Serialize)]
+struct PasswordResetForm<'a> {
+    token: &'a str,
+    password: &'a str,
+}
+
 impl AuthService {
-    pub async fn login(username: &str, password: &str) -> Result<(), AuthError> {
+    /// Logs in, unless the server asks for something else first, which is
+    /// returned.
+    pub async fn login(
+        username: &str,
+        password: &str,
+    ) -> Result<Option<LoginChallenge>, AuthError> {
         let result = Request::post(&api_url!("/auth/login"))
             .json(&LoginForm { username, password })
             .unwrap()
@@ -44,14 +98,31 @@ impl AuthService {
 
         if let Ok(response) = result {
             match response.status() {
+                202 => {
+                    return match response.json::<LoginChallenge>().await {
+                        Ok(challenge) => Ok(Some(challenge)),
+                        Err(_) => Err(AuthError::LoginError("Server error".to_string())),
+                    };
+                }
                 200..=299 => {
                     log::info!("Successfully loged in!");
-                    return Ok(());
+                    return Ok(None);
+                }
+                429 => {
+                    return Err(AuthError::LoginError(format!(
+                        "Too many failed logins, please try again in {}",
+                        retry_after(&response)
+                    )));
                 }
                 400..=499 => {
-                    return Err(AuthError::LoginError(
-                        "Incorrect username and password combination".to_string(),
-                    ));
+                    let mut message = "Incorrect username and password combination".to_string();
+                    if response.headers().get("retry-after").is_some() {
+                        message += &format!(
+                            ", please wait {} before trying again",
+                            retry_after(&response)
+                        );
+                    }
+                    return Err(AuthError::LoginError(message));
                 }
                 _ => {
                     return Err(AuthError::LoginError("Server error".to_string()));
@@ -64,15 +135,55 @@ impl AuthService {
         Err(AuthError::NetworkError)
     }
 
-    pub async fn register(username: &str, email: &str, password: &str) -> Result<(), AuthError> {
-        let host = get_current_host();
+    /// The second step of a login, with a code from the authenticator app or
+    /// a recovery code.
+    pub async fn login_two_factor(code: &str) -> Result<(), AuthError> {
+        let result = Request::post(&api_url!("/auth/login/two-factor"))
+            .json(&TwoFactorCodeRequest {
+                code: code.to_string(),
+            })
+            .unwrap()
+            .send()
+            .await;
 
+        if let Ok(response) = result {
+            return match response.status() {
+                200..=299 => {
+                    log::info!("Successfully loged in!");
+                    Ok(())
+                }
+                429 => Err(AuthError::LoginError(format!(
+                    "Too many failed logins, please try again in {}",
+                    retry_after(&response)
+                ))),
+                401 if response.text().await.is_ok_and(|body| body == "login") => {
+                    Err(AuthError::LoginExpired)
+                }
+                400..=499 => {
+                    let mut message = "Incorrect code".to_string();
+                    if response.headers().get("retry-after").is_some() {
+                        message += &format!(
+                            ", please wait {} before trying again",
+                            retry_after(&response)
+                        );
+                    }
+                    Err(AuthError::LoginError(message))
+                }
+                _ => Err(AuthError::LoginError("Server error".to_string())),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn register(username: &str, email: &str, password: &str) -> Result<(), AuthError> {
         let result = Request::post(&api_url!("/auth/register"))
             .json(&RegistrationForm {
                 username,
                 email,
                 password,
-                host,
             })
             .unwrap()
             .send()
@@ -116,8 +227,9 @@ impl AuthService {
                     log::info!("User confirmation successful!");
                     Ok(())
                 }
-                StatusCode::INTERNAL_SERVER_ERROR => Err(AuthError::ConfirmationError),
-                StatusCode::NOT_FOUND => Err(AuthError::ConfirmationError),
+                StatusCode::GONE => Err(AuthError::ConfirmationExpired),
+                StatusCode::CONFLICT => Err(AuthError::ConfirmationUsed),
+                StatusCode::NOT_FOUND => Err(AuthError::ConfirmationUnknown),
                 _ => Err(AuthError::ConfirmationError),
             };
         }
@@ -127,7 +239,261 @@ impl AuthService {
         Err(AuthError::NetworkError)
     }
 
-    pub fn _protected_get(url: &str) -> Result<RequestBuilder, AuthError> {
+    pub async fn resend_confirmation(email: &str) -> Result<(), AuthError> {
+        let result = Request::post(&api_url!("/auth/resend-confirmation"))
+            .json(&EmailLinkForm { email })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => Ok(()),
+                _ => Err(AuthError::ConfirmationError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn forgot_password(email: &str) -> Result<(), AuthError> {
+        let result = Request::post(&api_url!("/auth/forgot-password"))
+            .json(&EmailLinkForm { email })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => Ok(()),
+                _ => Err(AuthError::PasswordResetError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn reset_password(token: &str, password: &str) -> Result<(), AuthError> {
+        let result = Request::post(&api_url!("/auth/reset-password"))
+            .json(&PasswordResetForm { token, password })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => {
+                    log::info!("Password reset successful!");
+                    Ok(())
+                }
+                StatusCode::NOT_FOUND => Err(AuthError::InvalidResetLink),
+                _ => Err(AuthError::PasswordResetError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn get_two_factor_status() -> Result<TwoFactorStatus, AuthError> {
+        let result = Request::get(&api_url!("/auth/two-factor")).send().await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<TwoFactorStatus>()
+                    .await
+                    .map_err(|_| AuthError::TwoFactorError),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::TwoFactorError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    /// A new secret to add to the authenticator app, unused until enabled.
+    pub async fn setup_two_factor(password: &str) -> Result<TwoFactorSetup, AuthError> {
+        let result = Request::post(&api_url!("/auth/two-factor/setup"))
+            .json(&PasswordConfirmation {
+                password: password.to_string(),
+            })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<TwoFactorSetup>()
+                    .await
+                    .map_err(|_| AuthError::TwoFactorError),
+                StatusCode::BAD_REQUEST => Err(AuthError::WrongPassword),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::TwoFactorError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    /// Enables two-factor authentication with a first code, returning the
+    /// recovery codes.
+    pub async fn enable_two_factor(code: &str) -> Result<Vec<String>, AuthError> {
+        let result = Request::post(&api_url!("/auth/two-factor/enable"))
+            .json(&TwoFactorCodeRequest {
+                code: code.to_string(),
+            })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<RecoveryCodes>()
+                    .await
+                    .map(|recovery_codes| recovery_codes.codes)
+                    .map_err(|_| AuthError::TwoFactorError),
+                StatusCode::BAD_REQUEST => Err(AuthError::InvalidTwoFactorCode),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::TwoFactorError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn disable_two_factor(password: &str) -> Result<(), AuthError> {
+        let result = Request::post(&api_url!("/auth/two-factor/disable"))
+            .json(&PasswordConfirmation {
+                password: password.to_string(),
+            })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => Ok(()),
+                StatusCode::BAD_REQUEST => Err(AuthError::WrongPassword),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::TwoFactorError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    /// Replaces the recovery codes, returning the new ones.
+    pub async fn renew_recovery_codes(password: &str) -> Result<Vec<String>, AuthError> {
+        let result = Request::post(&api_url!("/auth/two-factor/recovery-codes"))
+            .json(&PasswordConfirmation {
+                password: password.to_string(),
+            })
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<RecoveryCodes>()
+                    .await
+                    .map(|recovery_codes| recovery_codes.codes)
+                    .map_err(|_| AuthError::TwoFactorError),
+                StatusCode::BAD_REQUEST => Err(AuthError::WrongPassword),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::TwoFactorError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn get_api_tokens() -> Result<ApiTokenList, AuthError> {
+        let result = Request::get(&api_url!("/auth/tokens")).send().await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<ApiTokenList>()
+                    .await
+                    .map_err(|_| AuthError::ApiTokenError),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::ApiTokenError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    /// Mints a new token, which is only returned this once.
+    pub async fn create_api_token(
+        request: &CreateApiTokenRequest,
+    ) -> Result<ApiTokenCreated, AuthError> {
+        let result = Request::post(&api_url!("/auth/tokens"))
+            .json(request)
+            .unwrap()
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => response
+                    .json::<ApiTokenCreated>()
+                    .await
+                    .map_err(|_| AuthError::ApiTokenError),
+                StatusCode::BAD_REQUEST => match response.text().await {
+                    Ok(field) => Err(AuthError::InvalidApiToken(field)),
+                    Err(_) => Err(AuthError::ApiTokenError),
+                },
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::ApiTokenError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub async fn revoke_api_token(id: &str) -> Result<(), AuthError> {
+        let result = Request::delete(&api_url!(format!("/auth/tokens/{}", id)))
+            .send()
+            .await;
+
+        if let Ok(response) = result {
+            return match StatusCode::from_u16(response.status()).unwrap() {
+                status_code if status_code.is_success() => Ok(()),
+                StatusCode::UNAUTHORIZED => Err(AuthError::Unauthorized),
+                _ => Err(AuthError::ApiTokenError),
+            };
+        }
+
+        // Handle network error
+        log::error!("Error in the request");
+        Err(AuthError::NetworkError)
+    }
+
+    pub fn protected_get(url: &str) -> Result<RequestBuilder, AuthError> {
         Ok(Request::get(url))
     }
 
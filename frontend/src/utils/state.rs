@@ -1,10 +1,21 @@
+use common::{Capability, Role};
 use serde::{Deserialize, Serialize};
 use yewdux::prelude::*;
 
 #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Clone)]
 pub struct User {
     pub username: String,
-    pub role: String,
+    pub role: Role,
+}
+
+impl User {
+    pub fn can(&self, capability: Capability) -> bool {
+        self.role.can(capability)
+    }
+
+    pub fn can_edit_post(&self, author: &str) -> bool {
+        self.role.can_edit_post(author == self.username)
+    }
 }
 
 #[derive(PartialEq, Debug, Default, Serialize, Deserialize, Store)]